@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use gol::{HashLife, LifeBoard, SparseLife, Term, Topology};
+
+const EDGES: [i32; 3] = [64, 256, 1024];
+const DENSITIES: [f64; 3] = [0.05, 0.2, 0.5];
+
+// The dense engine is split across threads when built with the parallel
+// feature and counts 64 cells at a time with simd, so run the suite with
+// and without them to compare
+const DENSE: &str = match (cfg!(feature = "parallel"), cfg!(feature = "simd")) {
+    (true, true) => "parallel simd",
+    (true, false) => "parallel",
+    (false, true) => "simd",
+    (false, false) => "dense",
+};
+
+fn soup(edge: i32, density: f64) -> LifeBoard {
+    let mut board = LifeBoard::from(Term { w: edge, h: edge }).with_topology(Topology::Torus);
+    board.randomize_with_seed(1, density);
+    board
+}
+
+// Every engine is stepped one generation at a time from the same soup. The
+// torus keeps the dense and sparse boards the same size, HashLife has no
+// edges so its soup spreads out over the plane as it goes.
+fn engines(c: &mut Criterion) {
+    for edge in EDGES.iter() {
+        let mut group = c.benchmark_group(format!("engines {}x{}", edge, edge));
+        if *edge > 256 {
+            group.sample_size(10);
+        }
+        for density in DENSITIES.iter() {
+            let board = soup(*edge, *density);
+            let fill = format!("{}% full", density * 100.0);
+
+            group.bench_with_input(BenchmarkId::new(DENSE, &fill), &board, |b, board| {
+                let mut board = board.clone();
+                b.iter(|| black_box(&mut board).step())
+            });
+            group.bench_with_input(BenchmarkId::new("sparse", &fill), &board, |b, board| {
+                let mut universe =
+                    SparseLife::from_board(board).expect("Unable to start the sparse engine");
+                b.iter(|| black_box(&mut universe).step())
+            });
+            group.bench_with_input(BenchmarkId::new("hashlife", &fill), &board, |b, board| {
+                let mut universe = HashLife::from_board(board).expect("Unable to start HashLife");
+                b.iter(|| black_box(&mut universe).advance(1))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, engines);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gol::{CellLocation, Edges, LifeBoard, Pattern, SparseLife, StampMode, Term, Topology};
+
+fn soup(edge: i32) -> LifeBoard {
+    let mut board = LifeBoard::from(Term { w: edge, h: edge });
+    board.randomize_with_seed(1, 0.35);
+    board
+}
+
+// Cloning then processing is what every generation used to cost, stepping
+// in place reuses the buffers instead
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step 256x256");
+    group.bench_function("clone and process", |b| {
+        let mut board = soup(256);
+        b.iter(|| board = black_box(board.clone().process()))
+    });
+    group.bench_function("in place", |b| {
+        let mut board = soup(256);
+        b.iter(|| black_box(&mut board).step())
+    });
+    group.finish();
+}
+
+// Nearly all of a generation is spent counting neighbours
+fn neighbours(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step 200x200");
+    for topology in &[Topology::Bounded, Topology::Torus] {
+        group.bench_function(topology.to_string(), |b| {
+            let mut board = soup(200).with_topology(*topology);
+            b.iter(|| black_box(&mut board).step())
+        });
+    }
+    group.finish();
+}
+
+// A handful of gliders on a big empty board, where only tracking the live
+// cells pays off
+fn sparse(c: &mut Criterion) {
+    let mut board = LifeBoard::from(Term { w: 2000, h: 2000 }).with_topology(Topology::Torus);
+    board.clear();
+    let glider = Pattern::named("glider").expect("Unable to find the glider");
+    for i in 0..10 {
+        board.stamp(
+            &glider,
+            CellLocation {
+                r: i * 190,
+                c: i * 170,
+            },
+            StampMode::Or,
+            Edges::Reject,
+        );
+    }
+
+    let mut group = c.benchmark_group("gliders 2000x2000");
+    group.bench_function("naive", |b| {
+        let mut board = board.clone();
+        b.iter(|| black_box(&mut board).step())
+    });
+    group.bench_function("sparse", |b| {
+        let mut universe =
+            SparseLife::from_board(&board).expect("Unable to start the sparse engine");
+        b.iter(|| black_box(&mut universe).step())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, step, neighbours, sparse);
+criterion_main!(benches);
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(pattern) = gol::Pattern::from_plaintext(text) {
+        let _ = pattern.to_string();
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(pattern) = gol::Pattern::from_rle(text) {
+        let read = gol::Pattern::from_rle(&pattern.to_rle()).expect("Unable to read written RLE");
+        let mut cells = read.cells;
+        let mut expected = pattern.cells;
+        cells.sort_by_key(|l| (l.r, l.c));
+        expected.sort_by_key(|l| (l.r, l.c));
+        assert_eq!(cells, expected);
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(rule) = text.parse::<gol::Rule>() {
+        assert_eq!(rule.to_string().parse::<gol::Rule>(), Ok(rule));
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    if let Ok(board) = gol::LifeBoard::decode(bytes) {
+        assert_eq!(
+            gol::LifeBoard::decode(&board.encode()).map(|b| b.encode()),
+            Ok(board.encode())
+        );
+    }
+});
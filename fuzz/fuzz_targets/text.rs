@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// Whichever parser the text looks like it's for
+fuzz_target!(|text: &str| {
+    if let Ok(pattern) = gol::Pattern::from_text(text) {
+        let _ = pattern.to_string();
+    }
+});
@@ -0,0 +1,72 @@
+use super::cells::CellLocation;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Heading {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub fn left(self) -> Heading {
+        match self {
+            Heading::North => Heading::West,
+            Heading::East => Heading::North,
+            Heading::South => Heading::East,
+            Heading::West => Heading::South,
+        }
+    }
+
+    pub fn right(self) -> Heading {
+        match self {
+            Heading::North => Heading::East,
+            Heading::East => Heading::South,
+            Heading::South => Heading::West,
+            Heading::West => Heading::North,
+        }
+    }
+
+    pub fn offset(self) -> CellLocation {
+        match self {
+            Heading::North => CellLocation { r: -1, c: 0 },
+            Heading::East => CellLocation { r: 0, c: 1 },
+            Heading::South => CellLocation { r: 1, c: 0 },
+            Heading::West => CellLocation { r: 0, c: -1 },
+        }
+    }
+
+    // The order they're saved in
+    pub fn from_index(i: u8) -> Option<Heading> {
+        [Heading::North, Heading::East, Heading::South, Heading::West]
+            .get(i as usize)
+            .copied()
+    }
+}
+
+impl std::fmt::Display for Heading {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let h = match self {
+            Heading::North => "north",
+            Heading::East => "east",
+            Heading::South => "south",
+            Heading::West => "west",
+        };
+        write!(f, "{}", h)
+    }
+}
+
+// A Langton's Ant. It turns right on a dead cell and left on a live one,
+// flips the cell it's on and moves forward a cell.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Ant {
+    pub location: CellLocation,
+    pub heading: Heading,
+}
+
+impl std::fmt::Display for Ant {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ant at {} heading {}", self.location, self.heading)
+    }
+}
@@ -0,0 +1,49 @@
+use super::life::LifeBoard;
+
+// A cellular automaton of your own, run with LifeBoard::step_with. The
+// board keeps its generation count and history itself, so the step only
+// has to change the cells, e.g. with LifeBoard::update_cells. Boards
+// stepped this way still draw, rewind, save and load like any other.
+pub trait Automaton {
+    fn step(&self, board: &mut LifeBoard);
+}
+
+impl<F: Fn(&mut LifeBoard)> Automaton for F {
+    fn step(&self, board: &mut LifeBoard) {
+        self(board)
+    }
+}
+
+// Which cellular automaton the board runs, Life covers every Bx/Sy rule
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Ca {
+    #[default]
+    Life,
+    WireWorld,
+    // Langton's Ant
+    Ant,
+}
+
+impl std::str::FromStr for Ca {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "life" => Ok(Ca::Life),
+            "wireworld" => Ok(Ca::WireWorld),
+            "ant" => Ok(Ca::Ant),
+            _ => Err(format!("Unknown automaton {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Ca {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let ca = match self {
+            Ca::Life => "life",
+            Ca::WireWorld => "wireworld",
+            Ca::Ant => "ant",
+        };
+        write!(f, "{}", ca)
+    }
+}
@@ -0,0 +1,95 @@
+use super::cells::CellLocation;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 64.0;
+
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pub zoom: f32,
+    pub x: f32,
+    pub y: f32,
+    // Odd rows are drawn half a cell to the right, for hexagonal boards
+    pub offset_rows: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new(10.0)
+    }
+}
+
+impl std::fmt::Display for Camera {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "(x {:.1}, y {:.1}, zoom {:.2})",
+            self.x, self.y, self.zoom
+        )
+    }
+}
+
+impl Camera {
+    pub fn new(zoom: f32) -> Camera {
+        Camera {
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            x: 0.0,
+            y: 0.0,
+            offset_rows: false,
+        }
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.zoom.ceil().max(1.0) as u32
+    }
+
+    fn row_offset(&self, r: i32) -> f32 {
+        if self.offset_rows && r.rem_euclid(2) == 1 {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    pub fn to_screen(&self, location: &CellLocation) -> (i32, i32) {
+        let c = location.c as f32 + self.row_offset(location.r);
+        (
+            ((c - self.x) * self.zoom).floor() as i32,
+            ((location.r as f32 - self.y) * self.zoom).floor() as i32,
+        )
+    }
+
+    // Somewhere part way across a cell, rows and columns being fractional
+    pub fn point_to_screen(&self, r: f32, c: f32) -> (i32, i32) {
+        (
+            ((c - self.x) * self.zoom).floor() as i32,
+            ((r - self.y) * self.zoom).floor() as i32,
+        )
+    }
+
+    pub fn to_board(&self, x: i32, y: i32) -> CellLocation {
+        let r = (y as f32 / self.zoom + self.y).floor() as i32;
+        CellLocation {
+            r,
+            c: (x as f32 / self.zoom + self.x - self.row_offset(r)).floor() as i32,
+        }
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.x += dx / self.zoom;
+        self.y += dy / self.zoom;
+    }
+
+    pub fn zoom_at(&mut self, factor: f32, x: i32, y: i32) {
+        let (board_x, board_y) = (x as f32 / self.zoom + self.x, y as f32 / self.zoom + self.y);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.x = board_x - x as f32 / self.zoom;
+        self.y = board_y - y as f32 / self.zoom;
+    }
+
+    pub fn fit(&mut self, board_w: i32, board_h: i32, view_w: u32, view_h: u32) {
+        let zoom = (view_w as f32 / board_w as f32).min(view_h as f32 / board_h as f32);
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.x = (board_w as f32 - view_w as f32 / self.zoom) / 2.0;
+        self.y = (board_h as f32 - view_h as f32 / self.zoom) / 2.0;
+    }
+}
@@ -0,0 +1,223 @@
+use super::cells::CellLocation;
+use super::life::{Edges, LifeBoard, StampMode, Term};
+use super::pattern::Pattern;
+use super::rule::Rule;
+use std::path::{Path, PathBuf};
+
+const CATALOG_HEADER: &str = "# gol pattern catalog 1";
+const MAX_DETECTED_PERIOD: i32 = 64;
+const MAX_DETECTED_AREA: i64 = 200 * 200;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatalogEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub rule: Rule,
+    pub period: Option<u64>,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl CatalogEntry {
+    pub fn from_pattern(path: &Path, pattern: &Pattern) -> CatalogEntry {
+        let name = pattern.name.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .map_or("unnamed".to_string(), |s| s.to_string_lossy().to_string())
+        });
+        let rule = pattern.rule.clone().unwrap_or_default();
+        let period = std::iter::once(&name)
+            .chain(pattern.comments.iter())
+            .find_map(|text| period_in(text))
+            .or_else(|| detect_period(pattern, &rule));
+        CatalogEntry {
+            path: path.to_path_buf(),
+            name,
+            rule,
+            period,
+            width: pattern.width,
+            height: pattern.height,
+        }
+    }
+
+    // term must already be lower case. pN only matches the period, anything
+    // else matches part of the name, rule or path.
+    fn matches(&self, term: &str) -> bool {
+        if let Some(period) = period_term(term) {
+            return self.period == Some(period);
+        }
+        self.name.to_lowercase().contains(term)
+            || self.rule.to_string().to_lowercase() == term
+            || self.path.to_string_lossy().to_lowercase().contains(term)
+    }
+}
+
+impl std::fmt::Display for CatalogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let period = self.period.map_or("p?".to_string(), |p| format!("p{}", p));
+        write!(
+            f,
+            "{} ({}, {}, {}x{}) {}",
+            self.name,
+            self.rule,
+            period,
+            self.width,
+            self.height,
+            self.path.display()
+        )
+    }
+}
+
+fn period_term(term: &str) -> Option<u64> {
+    term.strip_prefix('p')
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|n| n.parse().ok())
+}
+
+// Picks up periods written in names and comments like "p30 glider gun" or
+// "period 3"
+fn period_in(text: &str) -> Option<u64> {
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        period_term(word).or_else(|| {
+            if *word == "period" {
+                words.get(i + 1).and_then(|n| n.parse().ok())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+fn live_shape(board: &LifeBoard) -> Vec<(i32, i32)> {
+    let live: Vec<CellLocation> = board.iter_live().collect();
+    let top = live.iter().map(|l| l.r).min().unwrap_or(0);
+    let left = live.iter().map(|l| l.c).min().unwrap_or(0);
+    live.iter().map(|l| (l.r - top, l.c - left)).collect()
+}
+
+// Runs the pattern until it comes back to its starting shape, wherever it
+// has moved to, so oscillators and spaceships both get a period
+fn detect_period(pattern: &Pattern, rule: &Rule) -> Option<u64> {
+    if pattern.cells.is_empty() || pattern.width as i64 * pattern.height as i64 > MAX_DETECTED_AREA
+    {
+        return None;
+    }
+
+    let margin = MAX_DETECTED_PERIOD;
+    let mut board = LifeBoard::from(Term {
+        w: pattern.width + 2 * margin,
+        h: pattern.height + 2 * margin,
+    })
+    .with_rule(rule.clone());
+    board.clear();
+    board.stamp(
+        pattern,
+        CellLocation {
+            r: margin,
+            c: margin,
+        },
+        StampMode::Or,
+        Edges::Reject,
+    )?;
+
+    let start = live_shape(&board);
+    (1..=MAX_DETECTED_PERIOD as u64).find(|_| {
+        board.step();
+        live_shape(&board) == start
+    })
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    pub entries: Vec<CatalogEntry>,
+}
+
+impl Catalog {
+    // Every pattern file under dir, files that can't be read as a pattern
+    // are left out
+    pub fn build(dir: &Path) -> std::io::Result<Catalog> {
+        let mut catalog = Catalog::default();
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
+            paths.sort();
+            for path in paths {
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if let Ok(pattern) = Pattern::load(&path) {
+                    catalog
+                        .entries
+                        .push(CatalogEntry::from_pattern(&path, &pattern));
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let clean = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+        let mut text = format!("{}\n", CATALOG_HEADER);
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                clean(&entry.path.to_string_lossy()),
+                clean(&entry.name),
+                entry.rule,
+                entry.period.map_or("-".to_string(), |p| p.to_string()),
+                entry.width,
+                entry.height
+            ));
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, text)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Catalog> {
+        let text = std::fs::read_to_string(path)?;
+        let invalid = |line: usize| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Line {} of {} is not a catalog entry", line, path.display()),
+            )
+        };
+
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, l)| l) != Some(CATALOG_HEADER) {
+            return Err(invalid(1));
+        }
+        let mut catalog = Catalog::default();
+        for (n, line) in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let entry = match fields[..] {
+                [path, name, rule, period, width, height] => CatalogEntry {
+                    path: PathBuf::from(path),
+                    name: name.to_string(),
+                    rule: rule.parse().map_err(|_| invalid(n + 1))?,
+                    period: period.parse().ok(),
+                    width: width.parse().map_err(|_| invalid(n + 1))?,
+                    height: height.parse().map_err(|_| invalid(n + 1))?,
+                },
+                _ => return Err(invalid(n + 1)),
+            };
+            catalog.entries.push(entry);
+        }
+        Ok(catalog)
+    }
+
+    // Entries matching every word of the query, e.g. "p30 gun"
+    pub fn search(&self, query: &str) -> Vec<&CatalogEntry> {
+        let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+        self.entries
+            .iter()
+            .filter(|entry| terms.iter().all(|term| entry.matches(term)))
+            .collect()
+    }
+}
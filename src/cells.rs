@@ -2,40 +2,35 @@ use rand::{
     distributions::{Distribution, Standard},
     Rng,
 };
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Default)]
 pub enum CellState {
     Alive,
+    // How far through the decay of a Generations rule, from 1
+    Dying(u8),
+    // A WireWorld conductor, dead but able to carry electrons
+    Wire,
+    #[default]
     Dead,
 }
 
-impl Default for CellState {
-    fn default() -> CellState {
-        CellState::Dead
-    }
-}
-
 impl std::fmt::Display for CellState {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let c = match self {
             CellState::Alive => "*",
+            CellState::Dying(_) => "+",
+            CellState::Wire => "#",
             CellState::Dead => " ",
         };
         write!(f, "{}", c)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct CellLocation {
     pub r: i32,
     pub c: i32,
 }
 
-impl Default for CellLocation {
-    fn default() -> Self {
-        CellLocation { r: 0, c: 0 }
-    }
-}
-
 impl Distribution<CellState> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CellState {
         match rng.gen_range(0, 2) {
@@ -52,28 +47,19 @@ impl std::fmt::Display for CellLocation {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Cell {
     pub state: CellState,
     pub location: CellLocation,
 }
 
-impl Default for Cell {
-    fn default() -> Self {
-        Cell {
-            state: CellState::default(),
-            location: CellLocation::default(),
-        }
-    }
-}
-
 impl std::fmt::Display for Cell {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.state)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Direction {
     TopLeft,
     TopMiddle,
@@ -0,0 +1,532 @@
+use super::cells::{CellLocation, CellState};
+use super::life::{LifeBoard, Neighbourhood, Topology};
+use super::pattern::Pattern;
+use super::rule::Rule;
+use super::sparse::SparseLife;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Longer than any period in the dictionary
+const MAX_PERIOD: u64 = 64;
+
+// Objects from soups in B3/S23, most common first, as RLE
+const KNOWN: [(&str, &str); 24] = [
+    ("block", "2o$2o!"),
+    ("blinker", "3o!"),
+    ("beehive", "b2o$o2bo$b2o!"),
+    ("glider", "bo$2bo$3o!"),
+    ("loaf", "b2o$o2bo$bobo$2bo!"),
+    ("boat", "2o$obo$bo!"),
+    ("pond", "b2o$o2bo$o2bo$b2o!"),
+    ("tub", "bo$obo$bo!"),
+    ("ship", "2o$obo$b2o!"),
+    ("beacon", "2o$2o$2b2o$2b2o!"),
+    ("toad", "b3o$3o!"),
+    ("long boat", "2o$obo$bobo$2bo!"),
+    ("barge", "bo$obo$bobo$2bo!"),
+    ("mango", "b2o$o2bo$bo2bo$2b2o!"),
+    ("eater", "2o$obo$2bo$2b2o!"),
+    ("long barge", "bo$obo$bobo$2bobo$3bo!"),
+    ("aircraft carrier", "2o$o2bo$2b2o!"),
+    ("snake", "2obo$ob2o!"),
+    ("integral sign", "2o$obo$2bo$2bobo$3b2o!"),
+    ("pulsar", "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!"),
+    ("pentadecathlon", "2bo4bo$2ob4ob2o$2bo4bo!"),
+    ("lightweight spaceship", "bo2bo$o$o3bo$4o!"),
+    ("middleweight spaceship", "3bo$bo3bo$o$o4bo$5o!"),
+    ("heavyweight spaceship", "3b2o$bo4bo$o$o5bo$6o!"),
+];
+
+// Cells relative to the top left of the smallest rectangle around them,
+// sorted so equal shapes are equal
+pub(crate) type Shape = Vec<(i32, i32)>;
+
+pub(crate) fn shape(cells: &[CellLocation]) -> Shape {
+    let top = cells.iter().map(|c| c.r).min().unwrap_or(0);
+    let left = cells.iter().map(|c| c.c).min().unwrap_or(0);
+    let mut shape: Shape = cells.iter().map(|c| (c.r - top, c.c - left)).collect();
+    shape.sort_unstable();
+    shape
+}
+
+// The cells every way round they can be turned or flipped
+fn orientations(cells: &[CellLocation]) -> Vec<Vec<CellLocation>> {
+    // Which way up each axis goes and whether rows and columns swap
+    let mut turns = Vec::new();
+    for dr in [1, -1] {
+        for dc in [1, -1] {
+            for swap in [false, true] {
+                turns.push((dr, dc, swap));
+            }
+        }
+    }
+    turns
+        .iter()
+        .map(|(dr, dc, swap)| {
+            cells
+                .iter()
+                .map(|cell| {
+                    let (r, c) = (cell.r * dr, cell.c * dc);
+                    if *swap {
+                        CellLocation { r: c, c: r }
+                    } else {
+                        CellLocation { r, c }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The same shape whichever way round it's turned or flipped
+fn canonical(cells: &[CellLocation]) -> Shape {
+    orientations(cells)
+        .iter()
+        .map(|turned| shape(turned))
+        .min()
+        .unwrap_or_default()
+}
+
+// Catagolue only encodes objects that fit in this
+const MAX_APGCODE_SIZE: i32 = 40;
+
+// A shape in extended Wechsler format, the part of an apgcode after the
+// underscore. The shape is cut into strips five rows deep, separated by z,
+// and each column of a strip is one character with the top row as its
+// lowest bit. Runs of empty columns are shortened to w, x or y and a count,
+// and are left off the end of a strip.
+fn wechsler(shape: &Shape) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let height = shape.iter().map(|(r, _)| r + 1).max().unwrap_or(0);
+    let width = shape.iter().map(|(_, c)| c + 1).max().unwrap_or(0);
+    let cells: HashSet<&(i32, i32)> = shape.iter().collect();
+    let mut code = String::new();
+    for strip in 0..(height + 4) / 5 {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut empty = 0;
+        for c in 0..width {
+            let column = (0..5)
+                .filter(|row| cells.contains(&(strip * 5 + row, c)))
+                .fold(0, |bits, row| bits | 1 << row);
+            if column == 0 {
+                empty += 1;
+                continue;
+            }
+            match empty {
+                0 => {}
+                1 => code.push('0'),
+                2 => code.push('w'),
+                3 => code.push('x'),
+                n => {
+                    code.push('y');
+                    code.push(DIGITS[n - 4] as char);
+                }
+            }
+            empty = 0;
+            code.push(DIGITS[column] as char);
+        }
+    }
+    code
+}
+
+// The live cells split into groups, cells no more than reach apart in
+// either direction being in the same group
+pub(crate) fn groups(cells: &HashSet<CellLocation>, reach: i32) -> Vec<Vec<CellLocation>> {
+    let mut unseen = cells.clone();
+    let mut groups = Vec::new();
+    while let Some(start) = unseen.iter().next().cloned() {
+        unseen.remove(&start);
+        let mut group = vec![start];
+        let mut i = 0;
+        while i < group.len() {
+            let cell = group[i].clone();
+            for dr in -reach..=reach {
+                for dc in -reach..=reach {
+                    let near = CellLocation {
+                        r: cell.r + dr,
+                        c: cell.c + dc,
+                    };
+                    if unseen.remove(&near) {
+                        group.push(near);
+                    }
+                }
+            }
+            i += 1;
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+// An object in a census, by name if it's in the dictionary and otherwise by
+// its size and how it carries on with nothing else around it
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Object {
+    Named(&'static str),
+    StillLife(usize),
+    // The size and period
+    Oscillator(usize, u64),
+    Spaceship(usize, u64),
+    // It dies, grows or didn't come back round in MAX_PERIOD generations
+    Unsettled(usize),
+}
+
+// An apgcode sorts ahead of another if it's shorter
+fn shortest(a: String, b: String) -> String {
+    if (b.len(), &b) < (a.len(), &a) {
+        b
+    } else {
+        a
+    }
+}
+
+impl Object {
+    pub fn is_named(&self) -> bool {
+        matches!(self, Object::Named(_))
+    }
+}
+
+impl std::fmt::Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Object::Named(name) => write!(f, "{}", name),
+            Object::StillLife(size) => write!(f, "{}-cell still life", size),
+            Object::Oscillator(size, period) => write!(f, "{}-cell p{} oscillator", size, period),
+            Object::Spaceship(size, period) => write!(f, "{}-cell c/{} spaceship", size, period),
+            Object::Unsettled(size) => write!(f, "{}-cell unsettled object", size),
+        }
+    }
+}
+
+// How many of each object there are on a settled board. The same objects
+// are counted by their apgcodes too, as Catagolue does, where objects not in
+// the dictionary of the same size and behaviour can still be told apart.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Census {
+    pub counts: BTreeMap<Object, usize>,
+    pub apgcodes: BTreeMap<String, usize>,
+}
+
+impl Census {
+    pub fn add(&mut self, other: &Census) {
+        for (object, count) in &other.counts {
+            *self.counts.entry(object.clone()).or_insert(0) += count;
+        }
+        for (code, count) in &other.apgcodes {
+            *self.apgcodes.entry(code.clone()).or_insert(0) += count;
+        }
+    }
+
+    // One apgcode and its count a line, most common first, as in the
+    // census table of a Catagolue haul
+    pub fn apgcode_table(&self) -> String {
+        let mut codes: Vec<(&String, &usize)> = self.apgcodes.iter().collect();
+        codes.sort_by_key(|(code, count)| (std::cmp::Reverse(**count), *code));
+        codes
+            .iter()
+            .map(|(code, count)| format!("{} {}\n", code, count))
+            .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    // Anything moving or oscillating that isn't in the dictionary
+    pub fn has_unnamed_oscillators(&self) -> bool {
+        self.counts
+            .keys()
+            .any(|o| matches!(o, Object::Oscillator(..) | Object::Spaceship(..)))
+    }
+}
+
+// Most common first
+impl std::fmt::Display for Census {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut counts: Vec<(String, usize)> = self
+            .counts
+            .iter()
+            .map(|(object, count)| (object.to_string(), *count))
+            .collect();
+        counts.sort_by_key(|(name, count)| (std::cmp::Reverse(*count), name.clone()));
+        let width = counts.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, count) in counts {
+            writeln!(f, "{:width$}  {}", name, count, width = width)?;
+        }
+        Ok(())
+    }
+}
+
+// Takes censuses of boards under one rule. Only B3/S23 has a dictionary,
+// objects under any other rule are all described by what they do.
+pub struct Recogniser {
+    rule: Rule,
+    // Every phase of everything in the dictionary
+    known: HashMap<Shape, &'static str>,
+    apgcodes: HashMap<&'static str, String>,
+}
+
+impl Recogniser {
+    pub fn new(rule: Rule) -> Result<Recogniser, String> {
+        // Stepping objects on their own needs an infinite plane with nothing
+        // born out of nowhere
+        SparseLife::new(0, 0, Topology::Infinite, rule.clone())
+            .map_err(|e| format!("Unable to take a census: {}", e))?;
+        let mut recogniser = Recogniser {
+            rule,
+            known: HashMap::new(),
+            apgcodes: HashMap::new(),
+        };
+        if recogniser.rule != Rule::default() {
+            return Ok(recogniser);
+        }
+        for (name, rle) in KNOWN.iter() {
+            let pattern = Pattern::from_rle(rle).expect("Unable to parse a known object");
+            let mut universe = recogniser.universe(&pattern.cells);
+            for _ in 0..MAX_PERIOD {
+                let cells: Vec<CellLocation> = universe.live_cells().cloned().collect();
+                recogniser.known.entry(canonical(&cells)).or_insert(name);
+                universe.step();
+            }
+            let behaviour = recogniser.behaviour(&pattern.cells);
+            let code = recogniser.apgcode(&pattern.cells, &behaviour);
+            recogniser.apgcodes.insert(name, code);
+        }
+        Ok(recogniser)
+    }
+
+    fn universe(&self, cells: &[CellLocation]) -> SparseLife {
+        let mut universe = SparseLife::new(0, 0, Topology::Infinite, self.rule.clone())
+            .expect("The rule was checked when the recogniser was made");
+        for cell in cells {
+            universe.set_state(cell, CellState::Alive);
+        }
+        universe
+    }
+
+    // Steps the object alone until it's back to the same shape, giving how
+    // many generations that took and how far it's moved
+    pub(crate) fn period(
+        &self,
+        cells: &[CellLocation],
+        max_period: u64,
+    ) -> Option<(u64, CellLocation)> {
+        let start = shape(cells);
+        let corner = |cells: &[CellLocation]| CellLocation {
+            r: cells.iter().map(|c| c.r).min().unwrap_or(0),
+            c: cells.iter().map(|c| c.c).min().unwrap_or(0),
+        };
+        let home = corner(cells);
+        let mut universe = self.universe(cells);
+        for generation in 1..=max_period {
+            universe.step();
+            let now: Vec<CellLocation> = universe.live_cells().cloned().collect();
+            if now.is_empty() || now.len() > 4 * cells.len() {
+                return None;
+            }
+            if shape(&now) == start {
+                let moved = corner(&now);
+                return Some((
+                    generation,
+                    CellLocation {
+                        r: moved.r - home.r,
+                        c: moved.c - home.c,
+                    },
+                ));
+            }
+        }
+        None
+    }
+
+    fn behaviour(&self, cells: &[CellLocation]) -> Object {
+        let size = cells.len();
+        match self.period(cells, MAX_PERIOD) {
+            Some((1, moved)) if moved == CellLocation::default() => Object::StillLife(size),
+            Some((period, moved)) if moved == CellLocation::default() => {
+                Object::Oscillator(size, period)
+            }
+            Some((period, _)) => Object::Spaceship(size, period),
+            None => Object::Unsettled(size),
+        }
+    }
+
+    // The apgcode prefix says what the object does, the rest is the shape
+    // of whichever phase and way round gives the shortest code
+    fn apgcode(&self, cells: &[CellLocation], behaviour: &Object) -> String {
+        let (kind, period) = match behaviour {
+            Object::StillLife(size) => ("s", *size as u64),
+            Object::Oscillator(_, period) => ("p", *period),
+            Object::Spaceship(_, period) => ("q", *period),
+            Object::Named(_) | Object::Unsettled(_) => return "PATHOLOGICAL".to_string(),
+        };
+        let mut universe = self.universe(cells);
+        let mut best = None;
+        for _ in 0..period.min(MAX_PERIOD) {
+            let now: Vec<CellLocation> = universe.live_cells().cloned().collect();
+            for turned in orientations(&now) {
+                let shape = shape(&turned);
+                let fits = shape
+                    .iter()
+                    .all(|(r, c)| *r < MAX_APGCODE_SIZE && *c < MAX_APGCODE_SIZE);
+                if fits {
+                    let code = wechsler(&shape);
+                    best = Some(best.map_or(code.clone(), |b| shortest(b, code)));
+                }
+            }
+            universe.step();
+        }
+        match (kind, best) {
+            ("s", Some(code)) => format!("xs{}_{}", cells.len(), code),
+            (kind, Some(code)) => format!("x{}{}_{}", kind, period, code),
+            ("s", None) => format!("ov_s{}", cells.len()),
+            (kind, None) => format!("ov_{}{}", kind, period),
+        }
+    }
+
+    fn identify(&self, cells: &[CellLocation]) -> (Object, String) {
+        match self.known.get(&canonical(cells)) {
+            Some(name) => (Object::Named(name), self.apgcodes[name].clone()),
+            None => {
+                let behaviour = self.behaviour(cells);
+                let code = self.apgcode(cells, &behaviour);
+                (behaviour, code)
+            }
+        }
+    }
+
+    // Whether the pieces carry on the same stepped together as apart, in
+    // which case they're separate objects that happen to be close
+    fn are_apart(&self, pieces: &[Vec<CellLocation>]) -> bool {
+        let all: Vec<CellLocation> = pieces.iter().flatten().cloned().collect();
+        let mut together = self.universe(&all);
+        let mut apart: Vec<SparseLife> = pieces.iter().map(|p| self.universe(p)).collect();
+        (0..MAX_PERIOD).all(|_| {
+            together.step();
+            apart.iter_mut().for_each(SparseLife::step);
+            let separately: HashSet<&CellLocation> =
+                apart.iter().flat_map(|u| u.live_cells()).collect();
+            together.population() as usize == separately.len()
+                && together.live_cells().all(|c| separately.contains(c))
+        })
+    }
+
+    // Cells within two of each other can affect the same cell so are
+    // counted as one object, unless they're pieces that are in the
+    // dictionary and don't touch each other as they go
+    pub fn census(&self, board: &LifeBoard) -> Result<Census, String> {
+        if board.neighbourhood() != Neighbourhood::Moore {
+            return Err(format!(
+                "Unable to take a census of a {} neighbourhood",
+                board.neighbourhood()
+            ));
+        }
+        let live: HashSet<CellLocation> = board.iter_live().collect();
+        let mut census = Census::default();
+        for group in groups(&live, 2) {
+            let pieces = groups(&group.iter().cloned().collect(), 1);
+            let objects: Vec<(Object, String)> = if !self.known.contains_key(&canonical(&group))
+                && pieces.len() > 1
+                && pieces
+                    .iter()
+                    .all(|p| self.known.contains_key(&canonical(p)))
+                && self.are_apart(&pieces)
+            {
+                pieces.iter().map(|p| self.identify(p)).collect()
+            } else {
+                vec![self.identify(&group)]
+            };
+            for (object, code) in objects {
+                *census.counts.entry(object).or_insert(0) += 1;
+                *census.apgcodes.entry(code).or_insert(0) += 1;
+            }
+        }
+        Ok(census)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::{Edges, StampMode, Term};
+
+    #[test]
+    fn objects_are_counted_by_name_or_by_what_they_do() {
+        let mut board = LifeBoard::from(Term { w: 40, h: 30 });
+        board.clear();
+        let place = |board: &mut LifeBoard, rle: &str, r, c| {
+            let pattern = Pattern::from_rle(rle).expect("Unable to parse the object");
+            board.stamp(
+                &pattern,
+                CellLocation { r, c },
+                StampMode::Or,
+                Edges::Reject,
+            );
+        };
+        // Two blocks with a cell between them are still two blocks
+        place(&mut board, "2o$2o!", 1, 1);
+        place(&mut board, "2o$2o!", 1, 4);
+        // A beehive on its side and a blinker standing up
+        place(&mut board, "bo$obo$obo$bo!", 6, 1);
+        place(&mut board, "o$o$o!", 6, 10);
+        place(&mut board, "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!", 14, 1);
+        // Not in the dictionary, two ships joined at the corner
+        place(&mut board, "2o$obo$b2o$3b2o$3bobo$4b2o!", 14, 30);
+
+        let census = Recogniser::new(Rule::default())
+            .expect("Unable to make a recogniser")
+            .census(&board)
+            .expect("Unable to take the census");
+        let expected: BTreeMap<Object, usize> = vec![
+            (Object::Named("block"), 2),
+            (Object::Named("beehive"), 1),
+            (Object::Named("blinker"), 1),
+            (Object::Named("pulsar"), 1),
+            (Object::StillLife(12), 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(census.counts, expected);
+        assert_eq!(census.total(), 6);
+        assert!(!census.has_unnamed_oscillators());
+        assert_eq!(
+            census.to_string().lines().next(),
+            Some("block               2")
+        );
+        assert_eq!(census.apgcodes.len(), 5);
+        assert_eq!(
+            census.apgcode_table().lines().take(2).collect::<Vec<_>>(),
+            ["xs4_33 2", "xp2_7 1"]
+        );
+        assert_eq!(
+            census
+                .apgcodes
+                .get("xp3_co9nas0san9oczgoldlo0oldlogz1047210127401"),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn apgcodes_match_catagolue() {
+        let recogniser = Recogniser::new(Rule::default()).expect("Unable to make a recogniser");
+        for (name, code) in [
+            ("glider", "xq4_153"),
+            ("beehive", "xs6_696"),
+            ("loaf", "xs7_2596"),
+            ("boat", "xs5_253"),
+            ("toad", "xp2_7e"),
+            ("beacon", "xp2_318c"),
+            ("pentadecathlon", "xp15_4r4z4r4"),
+            ("lightweight spaceship", "xq4_6frc"),
+        ] {
+            assert_eq!(recogniser.apgcodes[name], code, "{} is wrong", name);
+        }
+        let cells = |rle| Pattern::from_rle(rle).expect("Unable to parse").cells;
+        // A still life that isn't in the dictionary, the long ship
+        let long_ship = cells("2o$obo$bobo$2b2o!");
+        assert_eq!(
+            recogniser.apgcode(&long_ship, &recogniser.behaviour(&long_ship)),
+            "xs8_35ac"
+        );
+    }
+}
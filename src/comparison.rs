@@ -0,0 +1,99 @@
+use super::error::GameOfLifeError;
+use super::life::LifeBoard;
+use super::settings::GUISettings;
+use super::simulation::Simulation;
+use super::tracks::Tracks;
+
+// A second board beside the first, started from the same cells with
+// another rule or engine and kept at the same generation, to show where the
+// two part ways. Only the first is edited, recorded and stopped when stable.
+pub struct Comparison {
+    pub sim: Simulation,
+    // The first generation the boards weren't the same
+    parted: Option<u64>,
+}
+
+impl Comparison {
+    pub fn new(
+        first_board: &LifeBoard,
+        settings: &GUISettings,
+    ) -> Result<Option<Comparison>, GameOfLifeError> {
+        if settings.compare_rule.is_none() && settings.compare_engine.is_none() {
+            return Ok(None);
+        }
+        let settings = GUISettings {
+            engine: settings.compare_engine.unwrap_or(settings.engine),
+            record_path: None,
+            repro_path: None,
+            stats_path: None,
+            stop_on_stable: false,
+            // Kept the same as the first's, if its rule has ships
+            tracks: Tracks::Off,
+            ..settings.clone()
+        };
+        let board = match &settings.compare_rule {
+            Some(rule) => first_board.clone().with_rule(rule.clone()),
+            None => first_board.clone(),
+        };
+        Ok(Some(Comparison {
+            sim: Simulation::new(board, &settings)?,
+            parted: None,
+        }))
+    }
+
+    // Steps or goes back to the first board's generation, drawn the same way
+    pub fn keep_up(&mut self, first: &Simulation) -> Result<(), String> {
+        while self.sim.generation > first.generation && self.sim.back()? {}
+        while self.sim.generation < first.generation {
+            self.sim.forward()?;
+        }
+        self.sim.theme = first.theme;
+        self.sim.fade = first.fade;
+        self.sim.by_age = first.by_age;
+        self.sim.grey_settled = first.grey_settled;
+        if self.sim.tracks != first.tracks && self.sim.set_tracks(first.tracks).is_err() {
+            self.sim.tracks = Tracks::Off;
+        }
+        if self.sim.heatmap.is_some() != first.heatmap.is_some() {
+            self.sim.toggle_heatmap();
+        }
+        if self.sim.history_length != first.history_length {
+            self.sim.set_history_length(first.history_length);
+        }
+
+        let is_same = first.board.width() == self.sim.board.width()
+            && first.board.height() == self.sim.board.height()
+            && first.board.changed_cells(&self.sim.board).next().is_none();
+        if !is_same && self.parted.is_none() && self.sim.generation == first.generation {
+            self.parted = Some(first.generation);
+        }
+        Ok(())
+    }
+
+    // The window's font has no | so its HUD is written tersely
+    pub fn hud(&self) -> String {
+        format!(
+            " Beside {} {} Pop {} {}",
+            self.sim.board.rule(),
+            self.sim.engine(),
+            self.sim.board.population(),
+            match self.parted {
+                None => "Same".to_string(),
+                Some(generation) => format!("Parted {}", generation),
+            }
+        )
+    }
+
+    pub fn status(&self) -> String {
+        format!(
+            " | Beside: {} on {}, population {}, {}",
+            self.sim.board.rule(),
+            self.sim.engine(),
+            self.sim.board.population(),
+            match self.parted {
+                None => "the same".to_string(),
+                Some(generation) => format!("parted at generation {}", generation),
+            }
+        )
+    }
+}
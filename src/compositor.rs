@@ -0,0 +1,334 @@
+use super::cells::{CellLocation, CellState};
+use super::fade::{age_intensity, wire_colour, Colony, ColorScheme, Fade, ANT_COLOUR};
+use super::font;
+use super::life::LifeBoard;
+use std::collections::VecDeque;
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, fill: Rgb) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![fill; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Rgb> {
+        self.index(x, y).map(|i| self.pixels[i])
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    // Anything off the image is clipped
+    pub fn blend(&mut self, x: i32, y: i32, colour: Rgb, alpha: f32) {
+        if let Some(i) = self.index(x, y) {
+            let pixel = &mut self.pixels[i];
+            for (channel, new) in pixel.iter_mut().zip(colour.iter()) {
+                *channel = (*channel as f32 * (1.0 - alpha) + *new as f32 * alpha).round() as u8;
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: u32, h: u32, colour: Rgb, alpha: f32) {
+        for py in y..y + h as i32 {
+            for px in x..x + w as i32 {
+                self.blend(px, py, colour, alpha);
+            }
+        }
+    }
+
+    pub fn draw_text(&mut self, x: i32, y: i32, scale: u32, text: &str, colour: Rgb, alpha: f32) {
+        for (px, py) in font::text_pixels(text) {
+            self.fill_rect(
+                x + px * scale as i32,
+                y + py * scale as i32,
+                scale,
+                scale,
+                colour,
+                alpha,
+            );
+        }
+    }
+
+    pub fn draw_image(&mut self, x: i32, y: i32, image: &Image, alpha: f32) {
+        for iy in 0..image.height as i32 {
+            for ix in 0..image.width as i32 {
+                if let Some(colour) = image.get(ix, iy) {
+                    self.blend(x + ix, y + iy, colour, alpha);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layer {
+    Board,
+    Trails,
+    Overlay,
+    Annotations,
+    Watermark,
+}
+
+// Older trails are drawn further behind the screen, so the history can be
+// seen in depth through red/cyan glasses or by free viewing a stereo pair
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Stereo {
+    #[default]
+    Off,
+    Anaglyph,
+    SideBySide,
+}
+
+impl std::str::FromStr for Stereo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Stereo::Off),
+            "anaglyph" => Ok(Stereo::Anaglyph),
+            "side-by-side" => Ok(Stereo::SideBySide),
+            _ => Err(format!("Unknown stereo mode {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Stereo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Stereo::Off => "off",
+            Stereo::Anaglyph => "anaglyph",
+            Stereo::SideBySide => "side-by-side",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    pub location: CellLocation,
+    pub text: String,
+}
+
+// Draws boards the same way for every image export, layer by layer from
+// the background up: trails, the board, annotations, the overlay text in
+// the top left then the watermark in the bottom corners.
+#[derive(Clone, Debug)]
+pub struct Compositor {
+    pub cell_size: u32,
+    pub text_scale: u32,
+    pub background: Rgb,
+    pub alive: Rgb,
+    // Fades trails through these colours rather than blending alive into
+    // the background
+    pub fade: Option<Fade>,
+    // Colours the board by how long each cell has been alive
+    pub by_age: bool,
+    pub text: Rgb,
+    pub overlay: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub logo: Option<Image>,
+    pub stereo: Stereo,
+    // How many pixels further apart the two eyes see a trail for each
+    // generation it is behind the board
+    pub parallax: u32,
+    hidden: Vec<Layer>,
+}
+
+impl Default for Compositor {
+    fn default() -> Self {
+        Compositor {
+            cell_size: 4,
+            text_scale: 2,
+            background: [0, 0, 0],
+            alive: [255, 0, 0],
+            fade: None,
+            by_age: false,
+            text: [255, 255, 255],
+            overlay: None,
+            annotations: Vec::new(),
+            logo: None,
+            stereo: Stereo::default(),
+            parallax: 2,
+            hidden: Vec::new(),
+        }
+    }
+}
+
+impl Compositor {
+    pub fn show(&mut self, layer: Layer, visible: bool) {
+        self.hidden.retain(|l| *l != layer);
+        if !visible {
+            self.hidden.push(layer);
+        }
+    }
+
+    pub fn is_visible(&self, layer: Layer) -> bool {
+        !self.hidden.contains(&layer)
+    }
+
+    // The size of the images composed from boards this size
+    pub fn image_size(&self, board: &LifeBoard) -> (u32, u32) {
+        let (w, h) = (
+            board.width() as u32 * self.cell_size,
+            board.height() as u32 * self.cell_size,
+        );
+        match self.stereo {
+            Stereo::SideBySide => (w * 2, h),
+            Stereo::Off | Stereo::Anaglyph => (w, h),
+        }
+    }
+
+    // history runs oldest to newest, the newest being the board itself
+    pub fn compose(&self, history: &VecDeque<LifeBoard>, generation: u64) -> Image {
+        match self.stereo {
+            Stereo::Off => self.compose_eye(history, generation, 0),
+            Stereo::Anaglyph => {
+                let left = self.compose_eye(history, generation, -1);
+                let right = self.compose_eye(history, generation, 1);
+                let brightness = |p: Rgb| p.iter().copied().max().unwrap_or(0);
+                Image {
+                    pixels: left
+                        .pixels
+                        .iter()
+                        .zip(right.pixels.iter())
+                        .map(|(l, r)| [brightness(*l), brightness(*r), brightness(*r)])
+                        .collect(),
+                    ..left
+                }
+            }
+            Stereo::SideBySide => {
+                let left = self.compose_eye(history, generation, -1);
+                let right = self.compose_eye(history, generation, 1);
+                let mut image = Image::new(left.width * 2, left.height, self.background);
+                image.draw_image(0, 0, &left, 1.0);
+                image.draw_image(left.width as i32, 0, &right, 1.0);
+                image
+            }
+        }
+    }
+
+    // eye is -1 for the left, 1 for the right and 0 for a flat image.
+    // Trails move apart from the board they led to in the two eyes so they
+    // seem to sink into the screen.
+    fn compose_eye(&self, history: &VecDeque<LifeBoard>, generation: u64, eye: i32) -> Image {
+        let (w, h) = history
+            .back()
+            .map_or((0, 0), |b| (b.width() as u32, b.height() as u32));
+        let mut image = Image::new(w * self.cell_size, h * self.cell_size, self.background);
+
+        let age_incr = 1.0 / history.len() as f32;
+        for (i, board) in history.iter().enumerate() {
+            let is_latest = i + 1 == history.len();
+            let layer = if is_latest {
+                Layer::Board
+            } else {
+                Layer::Trails
+            };
+            // Electrons leave tails already, trails would hide the circuit
+            if !self.is_visible(layer) || (board.is_wired() && !is_latest) {
+                continue;
+            }
+
+            let alpha = if is_latest {
+                1.0
+            } else {
+                age_incr * (i + 1) as f32
+            };
+            let age = (history.len() - 1 - i) as i32;
+            let shift = eye * age * self.parallax as i32;
+            for cell in board.iter_cells().filter(|c| c.state != CellState::Dead) {
+                let alpha = alpha * board.rule().brightness(&cell.state);
+                let by_age = (is_latest && self.by_age && cell.state == CellState::Alive)
+                    .then(|| age_intensity(board.age(&cell.location)));
+                let colony = board
+                    .colony(&cell.location)
+                    .filter(|_| is_latest && board.colonies() > 1);
+                let (colour, alpha) = match (colony, by_age, self.fade) {
+                    _ if board.is_wired() => (wire_colour(&cell.state), 1.0),
+                    (Some(colony), by_age, _) => {
+                        (Colony(colony).colour(by_age.unwrap_or(1.0)), 1.0)
+                    }
+                    (None, Some(by_age), fade) => (fade.unwrap_or_default().colour(by_age), 1.0),
+                    (None, None, Some(fade)) => (fade.colour(alpha), 1.0),
+                    (None, None, None) => (self.alive, alpha),
+                };
+                image.fill_rect(
+                    cell.location.c * self.cell_size as i32 + shift,
+                    cell.location.r * self.cell_size as i32,
+                    self.cell_size,
+                    self.cell_size,
+                    colour,
+                    alpha,
+                );
+            }
+        }
+
+        if let (true, Some(board)) = (self.is_visible(Layer::Board), history.back()) {
+            for ant in board.ants() {
+                image.fill_rect(
+                    ant.location.c * self.cell_size as i32,
+                    ant.location.r * self.cell_size as i32,
+                    self.cell_size,
+                    self.cell_size,
+                    ANT_COLOUR,
+                    1.0,
+                );
+            }
+        }
+
+        let margin = self.text_scale as i32;
+        if self.is_visible(Layer::Annotations) {
+            for annotation in &self.annotations {
+                image.draw_text(
+                    annotation.location.c * self.cell_size as i32,
+                    annotation.location.r * self.cell_size as i32,
+                    self.text_scale,
+                    &annotation.text,
+                    self.text,
+                    1.0,
+                );
+            }
+        }
+
+        if let (true, Some(text)) = (self.is_visible(Layer::Overlay), &self.overlay) {
+            image.draw_text(margin, margin, self.text_scale, text, self.text, 1.0);
+        }
+
+        if self.is_visible(Layer::Watermark) {
+            let text = format!("Gen {}", generation);
+            let text_height = (font::GLYPH_HEIGHT * self.text_scale as i32) as u32;
+            image.draw_text(
+                margin,
+                image.height as i32 - text_height as i32 - margin,
+                self.text_scale,
+                &text,
+                self.text,
+                0.6,
+            );
+            if let Some(logo) = &self.logo {
+                image.draw_image(
+                    image.width as i32 - logo.width as i32 - margin,
+                    image.height as i32 - logo.height as i32 - margin,
+                    logo,
+                    0.6,
+                );
+            }
+        }
+        image
+    }
+}
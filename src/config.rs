@@ -0,0 +1,246 @@
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigValue {
+    Text(String),
+    Number(String),
+    Bool(bool),
+    List(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    fn parse(text: &str) -> Result<ConfigValue, String> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            return inner
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(ConfigValue::parse)
+                .collect::<Result<_, _>>()
+                .map(ConfigValue::List);
+        }
+        if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            return unescape(inner).map(ConfigValue::Text);
+        }
+        if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+            return Ok(ConfigValue::Text(inner.to_string()));
+        }
+        match text {
+            "true" => Ok(ConfigValue::Bool(true)),
+            "false" => Ok(ConfigValue::Bool(false)),
+            _ if !text.is_empty()
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || "+-._eE".contains(c)) =>
+            {
+                Ok(ConfigValue::Number(text.replace('_', "")))
+            }
+            _ => Err(format!("{} isn't a string, number, boolean or list", text)),
+        }
+    }
+
+    // What it is on the command line, lists are one argument per item
+    fn args(&self) -> Vec<String> {
+        match self {
+            ConfigValue::Text(text) | ConfigValue::Number(text) => vec![text.clone()],
+            ConfigValue::Bool(b) => vec![b.to_string()],
+            ConfigValue::List(items) => items.iter().flat_map(ConfigValue::args).collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigValue::Text(text) => write!(f, "{:?}", text),
+            ConfigValue::Number(n) => write!(f, "{}", n),
+            ConfigValue::Bool(b) => write!(f, "{}", b),
+            ConfigValue::List(items) => {
+                let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            other => return Err(format!("Unknown escape \\{}", other.unwrap_or(' '))),
+        }
+    }
+    Ok(out)
+}
+
+// A # starts a comment unless it's inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') => return &line[..i],
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => {}
+        }
+    }
+    line
+}
+
+// The settings file, the plain key = value part of TOML with the keys
+// named after the long command line options, e.g.
+//
+//     rule = "B36/S23"
+//     bwidth = 120
+//     adaptive-speed = [5, 60]
+//     colour-by-age = true
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub entries: Vec<(String, ConfigValue)>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut entries: Vec<(String, ConfigValue)> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(format!("Line {}: tables aren't supported", n + 1));
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Line {}: expected key = value", n + 1))?;
+            let key = key.trim().to_string();
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(format!("Line {}: {} is set twice", n + 1, key));
+            }
+            let value = ConfigValue::parse(value).map_err(|e| format!("Line {}: {}", n + 1, e))?;
+            entries.push((key, value));
+        }
+        Ok(Config { entries })
+    }
+
+    // No file is the same as an empty one
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Unable to read {}: {}", path.display(), e)),
+        }
+    }
+
+    // The entries as command line arguments, skipping the keys is_given
+    // says are already on the command line. A false flag is left out
+    // altogether.
+    pub fn args(&self, is_given: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut args = Vec::new();
+        for (key, value) in self.entries.iter().filter(|(key, _)| !is_given(key)) {
+            match value {
+                ConfigValue::Bool(false) => {}
+                ConfigValue::Bool(true) => args.push(format!("--{}", key)),
+                ConfigValue::List(_) => {
+                    args.push(format!("--{}", key));
+                    args.extend(value.args());
+                }
+                ConfigValue::Text(text) | ConfigValue::Number(text) => {
+                    args.push(format!("--{}={}", key, text))
+                }
+            }
+        }
+        args
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (key, value) in &self.entries {
+            writeln!(f, "{} = {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_each_kind_of_value() {
+        let config = Config::parse(
+            "# Caves\n\
+             rule = \"B678/S345678\" # a comment\n\
+             \n\
+             bwidth = 1_20\n\
+             adaptive-speed = [5, 60.5]\n\
+             colour-by-age = true\n\
+             fade = 'ember'\n",
+        )
+        .expect("Unable to parse the config");
+        assert_eq!(
+            config.entries,
+            vec![
+                (
+                    "rule".to_string(),
+                    ConfigValue::Text("B678/S345678".to_string())
+                ),
+                ("bwidth".to_string(), ConfigValue::Number("120".to_string())),
+                (
+                    "adaptive-speed".to_string(),
+                    ConfigValue::List(vec![
+                        ConfigValue::Number("5".to_string()),
+                        ConfigValue::Number("60.5".to_string())
+                    ])
+                ),
+                ("colour-by-age".to_string(), ConfigValue::Bool(true)),
+                ("fade".to_string(), ConfigValue::Text("ember".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_options_are_left_out_of_the_args() {
+        let config = Config::parse(
+            "rule = \"B36/S23\"\nseed = 4\nadaptive-speed = [5, 60]\nverbose = false",
+        )
+        .expect("Unable to parse the config");
+        assert_eq!(
+            config.args(|key| key == "seed"),
+            ["--rule=B36/S23", "--adaptive-speed", "5", "60"]
+        );
+    }
+
+    #[test]
+    fn written_config_reads_back_the_same() {
+        let config = Config::parse(
+            "rule = \"B3/S23\"\nfps = 12.5\nadaptive-speed = [1, 2]\nstop-on-stable = true",
+        )
+        .expect("Unable to parse the config");
+        assert_eq!(Config::parse(&config.to_string()), Ok(config));
+    }
+
+    #[test]
+    fn rejects_what_it_cant_read() {
+        for text in [
+            "[board]",
+            "rule",
+            "rule = B3/S23",
+            "seed = 1\nseed = 2",
+            "rule = \"\\q\"",
+        ] {
+            assert!(Config::parse(text).is_err(), "{} parsed", text);
+        }
+    }
+}
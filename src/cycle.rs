@@ -0,0 +1,53 @@
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cycle {
+    pub period: u64,
+    // The first generation the board was in the cycle
+    pub since: u64,
+}
+
+impl std::fmt::Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.period {
+            1 => write!(f, "still life from generation {}", self.since),
+            p => write!(f, "period {} oscillator from generation {}", p, self.since),
+        }
+    }
+}
+
+// Remembers the fingerprints of the last window boards and spots when one
+// comes round again, so only cycles up to window generations long are found
+pub struct CycleDetector {
+    window: usize,
+    seen: HashMap<u64, u64>,
+    recent: VecDeque<u64>,
+}
+
+impl CycleDetector {
+    pub fn new(window: usize) -> CycleDetector {
+        CycleDetector {
+            window: window.max(1),
+            seen: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn observe(&mut self, fingerprint: u64, generation: u64) -> Option<Cycle> {
+        if let Some(earlier) = self.seen.get(&fingerprint) {
+            return Some(Cycle {
+                period: generation - earlier,
+                since: *earlier,
+            });
+        }
+
+        self.seen.insert(fingerprint, generation);
+        self.recent.push_back(fingerprint);
+        if self.recent.len() > self.window {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,140 @@
+use super::cells::{CellLocation, CellState};
+#[cfg(feature = "gpu")]
+use super::gpu::GpuLife;
+use super::hashlife::HashLife;
+use super::life::LifeBoard;
+use super::sparse::SparseLife;
+
+// How the board is stepped, by the board itself or by one of the engines
+// below that keep a Universe of their own
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Engine {
+    #[default]
+    Naive,
+    HashLife,
+    Sparse,
+    // Only in builds with the gpu feature
+    Gpu,
+}
+
+impl std::str::FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "naive" => Ok(Engine::Naive),
+            "hashlife" => Ok(Engine::HashLife),
+            "sparse" => Ok(Engine::Sparse),
+            "gpu" => Ok(Engine::Gpu),
+            _ => Err(format!("Unknown engine {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let e = match self {
+            Engine::Naive => "naive",
+            Engine::HashLife => "hashlife",
+            Engine::Sparse => "sparse",
+            Engine::Gpu => "gpu",
+        };
+        write!(f, "{}", e)
+    }
+}
+
+// The engines that keep their own copy of the cells, the board is written
+// from them after each generation
+pub enum Universe {
+    HashLife(HashLife),
+    Sparse(SparseLife),
+    #[cfg(feature = "gpu")]
+    Gpu(GpuLife),
+}
+
+impl Universe {
+    pub fn set_state(&mut self, location: &CellLocation, state: CellState) {
+        match self {
+            Universe::HashLife(universe) => universe.set_state(location, state),
+            Universe::Sparse(universe) => universe.set_state(location, state),
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => universe.set_state(location, state),
+        }
+    }
+
+    pub fn insert_cells(&mut self, cells: &[CellLocation]) {
+        match self {
+            Universe::HashLife(universe) => universe.insert_cells(cells),
+            Universe::Sparse(universe) => {
+                for cell in cells {
+                    universe.set_state(cell, CellState::Alive);
+                }
+            }
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => {
+                for cell in cells {
+                    universe.set_state(cell, CellState::Alive);
+                }
+            }
+        }
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        match self {
+            Universe::HashLife(universe) => universe.advance(generations),
+            Universe::Sparse(universe) => universe.advance(generations),
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => universe.advance(generations),
+        }
+    }
+
+    pub fn population(&self) -> u64 {
+        match self {
+            Universe::HashLife(universe) => universe.population(),
+            Universe::Sparse(universe) => universe.population(),
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => universe.population(),
+        }
+    }
+
+    // Throws away every cell for the live cells of board, seen with its top
+    // left at origin
+    pub fn reset(&mut self, board: &LifeBoard, origin: &CellLocation) -> Result<(), String> {
+        match self {
+            Universe::HashLife(universe) => *universe = HashLife::new(board.rule().clone())?,
+            Universe::Sparse(universe) => {
+                *universe = SparseLife::new(
+                    board.width(),
+                    board.height(),
+                    board.topology(),
+                    board.rule().clone(),
+                )?
+            }
+            // Keeps hold of the GPU and sends the whole board up at once
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => return universe.reset(board, origin),
+        }
+        for location in board.iter_live() {
+            self.set_state(&(&location + origin), CellState::Alive);
+        }
+        Ok(())
+    }
+
+    pub fn live_cells(&self) -> std::collections::HashSet<CellLocation> {
+        match self {
+            Universe::HashLife(universe) => universe.live_cells().into_iter().collect(),
+            Universe::Sparse(universe) => universe.live_cells().cloned().collect(),
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => universe.live_cells().into_iter().collect(),
+        }
+    }
+
+    pub fn write_window(&self, board: &mut LifeBoard, origin: &CellLocation) {
+        match self {
+            Universe::HashLife(universe) => universe.write_window(board, origin),
+            Universe::Sparse(universe) => universe.write_window(board, origin),
+            #[cfg(feature = "gpu")]
+            Universe::Gpu(universe) => universe.write_window(board, origin),
+        }
+    }
+}
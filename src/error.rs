@@ -0,0 +1,43 @@
+use thiserror::Error;
+
+// What stops the game from starting or running, main prints it and exits
+#[derive(Debug, Error)]
+pub enum GameOfLifeError {
+    #[error("Unable to work out the size of the terminal, set it with --bwidth and --bheight")]
+    TerminalSize,
+    #[error("Unable to {action}: {message}")]
+    Sdl {
+        action: &'static str,
+        message: String,
+    },
+    #[error("Unable to {action}: {source}")]
+    Io {
+        action: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{0}")]
+    Pattern(String),
+    #[error("{0}")]
+    Repro(String),
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    Engine(String),
+    #[error("This build has no {0}")]
+    Unsupported(&'static str),
+}
+
+impl GameOfLifeError {
+    // For map_err, e.g. canvas.fill_rect(rect).map_err(GameOfLifeError::sdl("draw a cell"))
+    pub fn sdl<E: std::fmt::Display>(action: &'static str) -> impl FnOnce(E) -> GameOfLifeError {
+        move |e| GameOfLifeError::Sdl {
+            action,
+            message: e.to_string(),
+        }
+    }
+
+    pub fn io(action: &'static str) -> impl FnOnce(std::io::Error) -> GameOfLifeError {
+        move |source| GameOfLifeError::Io { action, source }
+    }
+}
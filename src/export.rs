@@ -0,0 +1,347 @@
+use super::cells::CellState;
+#[cfg(any(feature = "record", feature = "snapshot"))]
+use super::compositor::Image;
+#[cfg(feature = "record")]
+use super::compositor::Rgb;
+use super::life::LifeBoard;
+use std::io::Write;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TileIds {
+    pub alive: u32,
+    pub dead: u32,
+}
+
+impl Default for TileIds {
+    fn default() -> Self {
+        TileIds { alive: 1, dead: 0 }
+    }
+}
+
+fn tile_ids(board: &LifeBoard, ids: &TileIds) -> Vec<Vec<u32>> {
+    (0..board.height())
+        .map(|r| {
+            (0..board.width())
+                .map(|c| match board.get(r, c) {
+                    Some(CellState::Alive) => ids.alive,
+                    _ => ids.dead,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn write_csv<W: Write>(board: &LifeBoard, ids: &TileIds, out: &mut W) -> std::io::Result<()> {
+    for row in tile_ids(board, ids) {
+        let row: Vec<String> = row.iter().map(|id| id.to_string()).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+pub fn write_tiled_json<W: Write>(
+    board: &LifeBoard,
+    ids: &TileIds,
+    out: &mut W,
+) -> std::io::Result<()> {
+    let data: Vec<String> = tile_ids(board, ids)
+        .iter()
+        .flatten()
+        .map(|id| id.to_string())
+        .collect();
+
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"type\": \"map\",")?;
+    writeln!(out, "  \"version\": \"1.10\",")?;
+    writeln!(out, "  \"orientation\": \"orthogonal\",")?;
+    writeln!(out, "  \"renderorder\": \"right-down\",")?;
+    writeln!(out, "  \"infinite\": false,")?;
+    writeln!(out, "  \"width\": {},", board.width())?;
+    writeln!(out, "  \"height\": {},", board.height())?;
+    writeln!(out, "  \"tilewidth\": 1,")?;
+    writeln!(out, "  \"tileheight\": 1,")?;
+    writeln!(out, "  \"nextlayerid\": 2,")?;
+    writeln!(out, "  \"nextobjectid\": 1,")?;
+    writeln!(out, "  \"tilesets\": [],")?;
+    writeln!(out, "  \"layers\": [")?;
+    writeln!(out, "    {{")?;
+    writeln!(out, "      \"id\": 1,")?;
+    writeln!(out, "      \"name\": \"cells\",")?;
+    writeln!(out, "      \"type\": \"tilelayer\",")?;
+    writeln!(out, "      \"x\": 0,")?;
+    writeln!(out, "      \"y\": 0,")?;
+    writeln!(out, "      \"width\": {},", board.width())?;
+    writeln!(out, "      \"height\": {},", board.height())?;
+    writeln!(out, "      \"opacity\": 1,")?;
+    writeln!(out, "      \"visible\": true,")?;
+    writeln!(out, "      \"data\": [{}]", data.join(", "))?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+pub fn write_tmx<W: Write>(board: &LifeBoard, ids: &TileIds, out: &mut W) -> std::io::Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<map version=\"1.10\" orientation=\"orthogonal\" renderorder=\"right-down\" \
+         width=\"{w}\" height=\"{h}\" tilewidth=\"1\" tileheight=\"1\" infinite=\"0\" \
+         nextlayerid=\"2\" nextobjectid=\"1\">",
+        w = board.width(),
+        h = board.height()
+    )?;
+    writeln!(
+        out,
+        " <layer id=\"1\" name=\"cells\" width=\"{}\" height=\"{}\">",
+        board.width(),
+        board.height()
+    )?;
+    writeln!(out, "  <data encoding=\"csv\">")?;
+    let rows: Vec<String> = tile_ids(board, ids)
+        .iter()
+        .map(|row| {
+            let row: Vec<String> = row.iter().map(|id| id.to_string()).collect();
+            row.join(",")
+        })
+        .collect();
+    writeln!(out, "{}", rows.join(",\n"))?;
+    writeln!(out, "  </data>")?;
+    writeln!(out, " </layer>")?;
+    writeln!(out, "</map>")
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MeshSettings {
+    pub cell_size: f32,
+    pub height: f32,
+    pub base: f32,
+}
+
+impl Default for MeshSettings {
+    fn default() -> Self {
+        MeshSettings {
+            cell_size: 1.0,
+            height: 1.0,
+            base: 0.0,
+        }
+    }
+}
+
+type Quad = [[f32; 3]; 4];
+
+// Treats the board as a heightfield so that neighbouring live cells share
+// walls and the result is a closed surface a slicer will accept.
+fn heightfield(board: &LifeBoard, settings: &MeshSettings) -> Vec<Quad> {
+    let (w, h) = (board.width(), board.height());
+    let height_of = |r: i32, c: i32| match board.get(r, c) {
+        Some(CellState::Alive) => settings.base + settings.height,
+        Some(CellState::Dying(_)) | Some(CellState::Wire) | Some(CellState::Dead) => settings.base,
+        None => 0.0,
+    };
+    let x = |c: i32| c as f32 * settings.cell_size;
+    let y = |r: i32| (h - r) as f32 * settings.cell_size;
+
+    let mut quads = Vec::new();
+    for r in 0..h {
+        for c in 0..w {
+            let z = height_of(r, c);
+            if z <= 0.0 {
+                continue;
+            }
+            let (x0, x1, y0, y1) = (x(c), x(c + 1), y(r + 1), y(r));
+            quads.push([[x0, y0, z], [x1, y0, z], [x1, y1, z], [x0, y1, z]]);
+            quads.push([[x0, y0, 0.0], [x0, y1, 0.0], [x1, y1, 0.0], [x1, y0, 0.0]]);
+        }
+    }
+
+    for r in 0..h {
+        for c in -1..w {
+            let (left, right) = (height_of(r, c), height_of(r, c + 1));
+            let (xw, y0, y1) = (x(c + 1), y(r + 1), y(r));
+            if left > right {
+                quads.push([
+                    [xw, y0, right],
+                    [xw, y1, right],
+                    [xw, y1, left],
+                    [xw, y0, left],
+                ]);
+            } else if right > left {
+                quads.push([
+                    [xw, y0, left],
+                    [xw, y0, right],
+                    [xw, y1, right],
+                    [xw, y1, left],
+                ]);
+            }
+        }
+    }
+
+    for r in -1..h {
+        for c in 0..w {
+            let (above, below) = (height_of(r, c), height_of(r + 1, c));
+            let (yw, x0, x1) = (y(r + 1), x(c), x(c + 1));
+            if above > below {
+                quads.push([
+                    [x0, yw, below],
+                    [x1, yw, below],
+                    [x1, yw, above],
+                    [x0, yw, above],
+                ]);
+            } else if below > above {
+                quads.push([
+                    [x0, yw, above],
+                    [x0, yw, below],
+                    [x1, yw, below],
+                    [x1, yw, above],
+                ]);
+            }
+        }
+    }
+    quads
+}
+
+fn normal(q: &Quad) -> [f32; 3] {
+    let u = [q[1][0] - q[0][0], q[1][1] - q[0][1], q[1][2] - q[0][2]];
+    let v = [q[2][0] - q[0][0], q[2][1] - q[0][1], q[2][2] - q[0][2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    [n[0] / len, n[1] / len, n[2] / len]
+}
+
+pub fn write_stl<W: Write>(
+    board: &LifeBoard,
+    settings: &MeshSettings,
+    out: &mut W,
+) -> std::io::Result<()> {
+    writeln!(out, "solid life")?;
+    for q in heightfield(board, settings) {
+        let n = normal(&q);
+        for t in &[[0, 1, 2], [0, 2, 3]] {
+            writeln!(out, "  facet normal {} {} {}", n[0], n[1], n[2])?;
+            writeln!(out, "    outer loop")?;
+            for i in t {
+                writeln!(out, "      vertex {} {} {}", q[*i][0], q[*i][1], q[*i][2])?;
+            }
+            writeln!(out, "    endloop")?;
+            writeln!(out, "  endfacet")?;
+        }
+    }
+    writeln!(out, "endsolid life")
+}
+
+pub fn write_obj<W: Write>(
+    board: &LifeBoard,
+    settings: &MeshSettings,
+    out: &mut W,
+) -> std::io::Result<()> {
+    let quads = heightfield(board, settings);
+    for q in &quads {
+        for v in q {
+            writeln!(out, "v {} {} {}", v[0], v[1], v[2])?;
+        }
+    }
+    for i in 0..quads.len() {
+        let first = i * 4 + 1;
+        writeln!(out, "f {} {} {} {}", first, first + 1, first + 2, first + 3)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "record")]
+fn gif_error(e: gif::EncodingError) -> std::io::Error {
+    std::io::Error::other(e)
+}
+
+// Writes compositor images as frames of an endlessly looping GIF. Frames
+// with 256 colours or fewer are written exactly, anything else is
+// quantised.
+#[cfg(feature = "record")]
+pub struct GifRecorder<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u16,
+    height: u16,
+    delay: u16,
+}
+
+#[cfg(feature = "record")]
+impl<W: Write> GifRecorder<W> {
+    pub fn new(out: W, width: u32, height: u32, frame_ms: u32) -> std::io::Result<Self> {
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{}x{} is too big for a GIF", width, height),
+            ));
+        }
+        let (width, height) = (width as u16, height as u16);
+
+        let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(gif_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(gif_error)?;
+        Ok(GifRecorder {
+            encoder,
+            width,
+            height,
+            delay: (frame_ms / 10).max(1) as u16,
+        })
+    }
+
+    pub fn push(&mut self, image: &Image) -> std::io::Result<()> {
+        if image.width != self.width as u32 || image.height != self.height as u32 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Frame is {}x{} but the GIF is {}x{}",
+                    image.width, image.height, self.width, self.height
+                ),
+            ));
+        }
+
+        let mut palette: Vec<Rgb> = Vec::new();
+        let mut indices = Vec::with_capacity(image.pixels.len());
+        for pixel in &image.pixels {
+            let index = match palette.iter().position(|p| p == pixel) {
+                Some(index) => index,
+                None => {
+                    palette.push(*pixel);
+                    palette.len() - 1
+                }
+            };
+            if palette.len() > 256 {
+                break;
+            }
+            indices.push(index as u8);
+        }
+
+        let mut frame = if palette.len() <= 256 {
+            gif::Frame {
+                width: self.width,
+                height: self.height,
+                buffer: std::borrow::Cow::Owned(indices),
+                palette: Some(palette.iter().flatten().copied().collect()),
+                ..gif::Frame::default()
+            }
+        } else {
+            let rgb: Vec<u8> = image.pixels.iter().flatten().copied().collect();
+            gif::Frame::from_rgb_speed(self.width, self.height, &rgb, 10)
+        };
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame).map_err(gif_error)
+    }
+}
+
+#[cfg(feature = "snapshot")]
+pub fn write_png<W: Write>(image: &Image, out: W) -> std::io::Result<()> {
+    let mut encoder = png::Encoder::new(out, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+    let data: Vec<u8> = image.pixels.iter().flatten().copied().collect();
+    writer
+        .write_image_data(&data)
+        .map_err(std::io::Error::other)
+}
@@ -0,0 +1,153 @@
+use super::cells::CellState;
+use super::compositor::Rgb;
+use super::life::MAX_COLONIES;
+
+// Cells this many generations old or older are drawn as dim as they get
+const AGE_SPAN: u16 = 100;
+const DIMMEST_AGE: f32 = 0.2;
+const COLONY_COLOURS: [Rgb; MAX_COLONIES as usize] =
+    [[255, 0, 0], [0, 160, 255], [255, 220, 0], [0, 220, 90]];
+// Golly's colours for electron heads, tails and conductors
+const HEAD: Rgb = [0, 128, 255];
+const TAIL: Rgb = [255, 64, 0];
+const CONDUCTOR: Rgb = [255, 200, 0];
+pub const ANT_COLOUR: Rgb = [0, 255, 0];
+const VIRIDIS: [Rgb; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+// How bright a cell is drawn goes from just above 0 for the oldest trail to
+// 1 for the board itself
+pub trait ColorScheme {
+    fn colour(&self, intensity: f32) -> Rgb;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Fade {
+    // A red board over grey trails, as it has always been drawn
+    #[default]
+    Red,
+    Blue,
+    Grayscale,
+    // Black through red and yellow to white
+    Heatmap,
+    // Purple through teal to yellow
+    Viridis,
+}
+
+impl Fade {
+    // For cycling through them at runtime
+    pub fn next(self) -> Fade {
+        match self {
+            Fade::Red => Fade::Blue,
+            Fade::Blue => Fade::Grayscale,
+            Fade::Grayscale => Fade::Heatmap,
+            Fade::Heatmap => Fade::Viridis,
+            Fade::Viridis => Fade::Red,
+        }
+    }
+}
+
+// Newborn cells are the brightest and they dim as they get older
+pub fn age_intensity(age: u16) -> f32 {
+    let age = age.min(AGE_SPAN) as f32 / AGE_SPAN as f32;
+    1.0 - age * (1.0 - DIMMEST_AGE)
+}
+
+// WireWorld cells are drawn by state alone, they have no age worth showing
+pub fn wire_colour(state: &CellState) -> Rgb {
+    match state {
+        CellState::Alive => HEAD,
+        CellState::Dying(_) => TAIL,
+        CellState::Wire => CONDUCTOR,
+        CellState::Dead => [0, 0, 0],
+    }
+}
+
+fn viridis(intensity: f32) -> Rgb {
+    let position = intensity.clamp(0.0, 1.0) * (VIRIDIS.len() - 1) as f32;
+    let low = (position as usize).min(VIRIDIS.len() - 2);
+    let t = position - low as f32;
+    let mut colour = [0; 3];
+    for (channel, (a, b)) in colour
+        .iter_mut()
+        .zip(VIRIDIS[low].iter().zip(VIRIDIS[low + 1].iter()))
+    {
+        *channel = (*a as f32 + (*b as f32 - *a as f32) * t).round() as u8;
+    }
+    colour
+}
+
+fn channel(intensity: f32) -> u8 {
+    (255.0 * intensity.clamp(0.0, 1.0)) as u8
+}
+
+// A single colour dimmed by intensity
+impl ColorScheme for Rgb {
+    fn colour(&self, intensity: f32) -> Rgb {
+        let mut colour = *self;
+        for channel in colour.iter_mut() {
+            *channel = (*channel as f32 * intensity.clamp(0.0, 1.0)) as u8;
+        }
+        colour
+    }
+}
+
+impl ColorScheme for Fade {
+    fn colour(&self, intensity: f32) -> Rgb {
+        let i = channel(intensity);
+        match self {
+            Fade::Red if i == 255 => [255, 0, 0],
+            Fade::Red | Fade::Grayscale => [i, i, i],
+            Fade::Blue => [i / 4, i / 2, i],
+            Fade::Heatmap => [
+                channel(intensity * 3.0),
+                channel(intensity * 3.0 - 1.0),
+                channel(intensity * 3.0 - 2.0),
+            ],
+            Fade::Viridis => viridis(intensity),
+        }
+    }
+}
+
+// The live cells of each colony in their own colour, dimmed by intensity
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Colony(pub u8);
+
+impl ColorScheme for Colony {
+    fn colour(&self, intensity: f32) -> Rgb {
+        COLONY_COLOURS[self.0 as usize % COLONY_COLOURS.len()].colour(intensity)
+    }
+}
+
+impl std::str::FromStr for Fade {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "red" => Ok(Fade::Red),
+            "blue" => Ok(Fade::Blue),
+            "grayscale" | "greyscale" => Ok(Fade::Grayscale),
+            "heatmap" => Ok(Fade::Heatmap),
+            "viridis" => Ok(Fade::Viridis),
+            _ => Err(format!("Unknown fade {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Fade {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Fade::Red => "red",
+            Fade::Blue => "blue",
+            Fade::Grayscale => "grayscale",
+            Fade::Heatmap => "heatmap",
+            Fade::Viridis => "viridis",
+        };
+        write!(f, "{}", s)
+    }
+}
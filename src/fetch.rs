@@ -0,0 +1,55 @@
+use super::pattern::Pattern;
+use std::path::Path;
+
+const ARCHIVE_URL: &str = "https://conwaylife.com/patterns";
+const TIMEOUT_SECS: &str = "20";
+
+// LifeWiki names its pattern files in lowercase with the spaces and
+// punctuation left out, e.g. "Gosper glider gun" is gosperglidergun.rle
+pub fn archive_name(name: &str) -> Result<String, String> {
+    let file: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if file.is_empty() {
+        Err(format!("{} isn't a pattern name", name))
+    } else {
+        Ok(file)
+    }
+}
+
+// The RLE for a pattern from the LifeWiki archive, kept in cache_dir so
+// each pattern is only downloaded once. The download is done with curl so
+// the build doesn't need a TLS library.
+pub fn fetch(name: &str, cache_dir: &Path) -> Result<Pattern, String> {
+    let file = archive_name(name)?;
+    let cached = cache_dir.join(format!("{}.rle", file));
+    if let Ok(text) = std::fs::read_to_string(&cached) {
+        return Pattern::from_rle(&text);
+    }
+
+    let url = format!("{}/{}.rle", ARCHIVE_URL, file);
+    let output = std::process::Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", TIMEOUT_SECS])
+        .arg(&url)
+        .output()
+        .map_err(|e| format!("Unable to run curl to fetch {}: {}", url, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Unable to fetch {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let text = String::from_utf8(output.stdout)
+        .map_err(|_| format!("{} isn't a text pattern file", url))?;
+    // Don't cache anything that won't load
+    let pattern = Pattern::from_rle(&text)?;
+
+    std::fs::create_dir_all(cache_dir)
+        .and_then(|_| std::fs::write(&cached, &text))
+        .map_err(|e| format!("Unable to cache {}: {}", cached.display(), e))?;
+    Ok(pattern)
+}
@@ -0,0 +1,77 @@
+pub const GLYPH_WIDTH: i32 = 3;
+pub const GLYPH_HEIGHT: i32 = 5;
+
+// Each row is three bits with the leftmost pixel in the highest bit.
+// Letters are upper case only, lower case is drawn as upper case.
+pub fn glyph(c: char) -> Option<[u8; 5]> {
+    let rows = match c.to_ascii_uppercase() {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 1, 1, 1],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        'A' => [2, 5, 7, 5, 5],
+        'B' => [6, 5, 6, 5, 6],
+        'C' => [3, 4, 4, 4, 3],
+        'D' => [6, 5, 5, 5, 6],
+        'E' => [7, 4, 6, 4, 7],
+        'F' => [7, 4, 6, 4, 4],
+        'G' => [3, 4, 5, 5, 3],
+        'H' => [5, 5, 7, 5, 5],
+        'I' => [7, 2, 2, 2, 7],
+        'J' => [1, 1, 1, 5, 2],
+        'K' => [5, 5, 6, 5, 5],
+        'L' => [4, 4, 4, 4, 7],
+        'M' => [5, 7, 7, 5, 5],
+        'N' => [6, 5, 5, 5, 5],
+        'O' => [2, 5, 5, 5, 2],
+        'P' => [6, 5, 6, 4, 4],
+        'Q' => [2, 5, 5, 6, 3],
+        'R' => [6, 5, 6, 5, 5],
+        'S' => [3, 4, 2, 1, 6],
+        'T' => [7, 2, 2, 2, 2],
+        'U' => [5, 5, 5, 5, 7],
+        'V' => [5, 5, 5, 5, 2],
+        'W' => [5, 5, 7, 7, 5],
+        'X' => [5, 5, 2, 5, 5],
+        'Y' => [5, 5, 2, 2, 2],
+        'Z' => [7, 1, 2, 4, 7],
+        ' ' => [0, 0, 0, 0, 0],
+        '/' => [1, 1, 2, 4, 4],
+        ':' => [0, 2, 0, 2, 0],
+        '.' => [0, 0, 0, 0, 2],
+        '-' => [0, 0, 7, 0, 0],
+        '+' => [0, 2, 7, 2, 0],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+// How many pixels wide text is, with one blank column between glyphs
+pub fn text_width(text: &str) -> i32 {
+    let n = text.chars().count() as i32;
+    if n == 0 {
+        0
+    } else {
+        n * (GLYPH_WIDTH + 1) - 1
+    }
+}
+
+// The (x, y) of every lit pixel when text is drawn with its top left at
+// the origin. Characters without a glyph are left blank.
+pub fn text_pixels(text: &str) -> impl Iterator<Item = (i32, i32)> + '_ {
+    text.chars().enumerate().flat_map(|(i, c)| {
+        let rows = glyph(c).unwrap_or([0; 5]);
+        let left = i as i32 * (GLYPH_WIDTH + 1);
+        (0..GLYPH_HEIGHT).flat_map(move |y| {
+            (0..GLYPH_WIDTH)
+                .filter(move |x| rows[y as usize] >> (GLYPH_WIDTH - 1 - x) & 1 == 1)
+                .map(move |x| (left + x, y))
+        })
+    })
+}
@@ -0,0 +1,471 @@
+use super::cells::{CellLocation, CellState};
+use super::life::{LifeBoard, Neighbourhood, Topology};
+use super::rule::Rule;
+use wgpu::util::DeviceExt;
+
+// Enough cells to each workgroup that the GPU isn't left waiting on the
+// edges of the board
+const WORKGROUP_EDGE: u32 = 8;
+// Generations sent off in one go, so a long jump doesn't build one huge
+// list of commands
+const GENERATIONS_PER_SUBMIT: u64 = 256;
+
+// One u32 a cell, with the birth and survival rules as bitmasks of the
+// neighbour counts. Off the edge of a bounded board is dead.
+const SHADER: &str = "
+struct Params {
+    width: u32,
+    height: u32,
+    torus: u32,
+    birth: u32,
+    survive: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> current: array<u32>;
+@group(0) @binding(2) var<storage, read_write> next: array<u32>;
+
+fn alive(r: i32, c: i32) -> u32 {
+    let h = i32(params.height);
+    let w = i32(params.width);
+    if params.torus == 1u {
+        return current[u32((r + h) % h) * params.width + u32((c + w) % w)];
+    }
+    if r < 0 || c < 0 || r >= h || c >= w {
+        return 0u;
+    }
+    return current[u32(r) * params.width + u32(c)];
+}
+
+@compute @workgroup_size(8, 8)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let r = i32(id.y);
+    let c = i32(id.x);
+    var neighbours = 0u;
+    for (var dr = -1; dr <= 1; dr++) {
+        for (var dc = -1; dc <= 1; dc++) {
+            if dr != 0 || dc != 0 {
+                neighbours += alive(r + dr, c + dc);
+            }
+        }
+    }
+    let cell = id.y * params.width + id.x;
+    let rule = select(params.birth, params.survive, current[cell] == 1u);
+    next[cell] = (rule >> neighbours) & 1u;
+}
+";
+
+// The board lives on the GPU in two buffers, each generation is stepped
+// from one into the other by a compute shader, and the cells only come
+// back when they're asked for to show a frame. Only finite boards of two
+// state rules can be run.
+pub struct GpuLife {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    width: i32,
+    height: i32,
+    cells: [wgpu::Buffer; 2],
+    // Steps cells[0] into cells[1] and cells[1] into cells[0]
+    bind_groups: [wgpu::BindGroup; 2],
+    // Where the cells are copied to be read back
+    readback: wgpu::Buffer,
+    // Which of cells has the current generation
+    current: usize,
+    generation: u64,
+}
+
+impl GpuLife {
+    pub fn new(width: i32, height: i32, topology: Topology, rule: Rule) -> Result<Self, String> {
+        if topology == Topology::Infinite {
+            return Err("The GPU engine cannot run an infinite board".to_string());
+        }
+        if rule.is_generations() {
+            return Err(format!(
+                "The GPU engine cannot run {} as it has dying states",
+                rule
+            ));
+        }
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .map_err(|e| format!("Unable to find a GPU: {}", e))?;
+        if !adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        {
+            return Err(format!(
+                "{} cannot run compute shaders",
+                adapter.get_info().name
+            ));
+        }
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("gol"),
+            required_limits: adapter.limits(),
+            ..wgpu::DeviceDescriptor::default()
+        }))
+        .map_err(|e| format!("Unable to use the GPU: {}", e))?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("step"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("step"),
+            layout: None,
+            module: &module,
+            entry_point: Some("step"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let (cells, bind_groups, readback) =
+            buffers(&device, &pipeline, width, height, topology, &rule)?;
+        Ok(GpuLife {
+            device,
+            queue,
+            pipeline,
+            width,
+            height,
+            cells,
+            bind_groups,
+            readback,
+            current: 0,
+            generation: 0,
+        })
+    }
+
+    pub fn from_board(board: &LifeBoard) -> Result<Self, String> {
+        if board.neighbourhood() != Neighbourhood::Moore {
+            return Err(format!(
+                "The GPU engine cannot run a {} neighbourhood",
+                board.neighbourhood()
+            ));
+        }
+        if !board.ants().is_empty() {
+            return Err("The GPU engine cannot run Langton's Ant".to_string());
+        }
+        if board.colonies() > 1 {
+            return Err("The GPU engine cannot run more than one colony".to_string());
+        }
+        let mut universe = GpuLife::new(
+            board.width(),
+            board.height(),
+            board.topology(),
+            board.rule().clone(),
+        )?;
+        universe.upload(board, &CellLocation::default());
+        Ok(universe)
+    }
+
+    // Throws away every cell for the live cells of board, seen with its top
+    // left at origin. The GPU is kept, only the buffers are made again.
+    pub fn reset(&mut self, board: &LifeBoard, origin: &CellLocation) -> Result<(), String> {
+        if board.topology() == Topology::Infinite {
+            return Err("The GPU engine cannot run an infinite board".to_string());
+        }
+        let (cells, bind_groups, readback) = buffers(
+            &self.device,
+            &self.pipeline,
+            board.width(),
+            board.height(),
+            board.topology(),
+            board.rule(),
+        )?;
+        self.width = board.width();
+        self.height = board.height();
+        self.cells = cells;
+        self.bind_groups = bind_groups;
+        self.readback = readback;
+        self.current = 0;
+        self.upload(board, origin);
+        Ok(())
+    }
+
+    fn upload(&mut self, board: &LifeBoard, origin: &CellLocation) {
+        let mut cells = vec![0u32; (self.width * self.height) as usize];
+        for location in board.iter_live() {
+            if let Some(index) = self.index(&(&location + origin)) {
+                cells[index] = 1;
+            }
+        }
+        self.queue
+            .write_buffer(&self.cells[self.current], 0, &to_bytes(&cells));
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn index(&self, location: &CellLocation) -> Option<usize> {
+        if location.r < 0 || location.c < 0 || location.r >= self.height || location.c >= self.width
+        {
+            return None;
+        }
+        Some((location.r * self.width + location.c) as usize)
+    }
+
+    // Cells off the board are ignored
+    pub fn set_state(&mut self, location: &CellLocation, state: CellState) {
+        let index = match self.index(location) {
+            Some(index) => index,
+            None => return,
+        };
+        let alive = u32::from(state == CellState::Alive);
+        self.queue.write_buffer(
+            &self.cells[self.current],
+            (index * 4) as wgpu::BufferAddress,
+            &alive.to_le_bytes(),
+        );
+    }
+
+    pub fn step(&mut self) {
+        self.advance(1);
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        let workgroups = |cells: i32| (cells as u32).div_ceil(WORKGROUP_EDGE);
+        let mut left = generations;
+        while left > 0 {
+            let batch = left.min(GENERATIONS_PER_SUBMIT);
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_pipeline(&self.pipeline);
+                for _ in 0..batch {
+                    pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+                    pass.dispatch_workgroups(workgroups(self.width), workgroups(self.height), 1);
+                    self.current = 1 - self.current;
+                }
+            }
+            self.queue.submit(Some(encoder.finish()));
+            left -= batch;
+        }
+        self.generation += generations;
+    }
+
+    // Waits for the generations sent off to be done and copies the cells
+    // back from the GPU
+    fn read(&self) -> Vec<u32> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&self.cells[self.current], 0, &self.readback, 0, None);
+        self.queue.submit(Some(encoder.finish()));
+        self.readback.map_async(wgpu::MapMode::Read, .., |_| {});
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("Lost the GPU reading the board back");
+        let cells = {
+            let view = self
+                .readback
+                .get_mapped_range(..)
+                .expect("Unable to read the board back from the GPU");
+            view.chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        };
+        self.readback.unmap();
+        cells
+    }
+
+    pub fn population(&self) -> u64 {
+        self.read().iter().map(|c| u64::from(*c)).sum()
+    }
+
+    pub fn live_cells(&self) -> Vec<CellLocation> {
+        self.read()
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive == 1)
+            .map(|(i, _)| CellLocation {
+                r: i as i32 / self.width,
+                c: i as i32 % self.width,
+            })
+            .collect()
+    }
+
+    pub fn write_to(&self, board: &mut LifeBoard) {
+        self.write_window(board, &CellLocation::default());
+    }
+
+    // Fills board with the cells in view when its top left is at origin
+    pub fn write_window(&self, board: &mut LifeBoard, origin: &CellLocation) {
+        board.clear();
+        for location in self.live_cells() {
+            board.set_state(
+                &CellLocation {
+                    r: location.r - origin.r,
+                    c: location.c - origin.c,
+                },
+                CellState::Alive,
+            );
+        }
+    }
+}
+
+fn to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+type Buffers = ([wgpu::Buffer; 2], [wgpu::BindGroup; 2], wgpu::Buffer);
+
+// The rule and the size of the board, both generations of cells and
+// somewhere to read them back to
+fn buffers(
+    device: &wgpu::Device,
+    pipeline: &wgpu::ComputePipeline,
+    width: i32,
+    height: i32,
+    topology: Topology,
+    rule: &Rule,
+) -> Result<Buffers, String> {
+    let size = (width.max(1) as u64) * (height.max(1) as u64) * 4;
+    if size > device.limits().max_storage_buffer_binding_size {
+        return Err(format!(
+            "A {}x{} board is too big for the GPU",
+            width, height
+        ));
+    }
+    let mask = |counts: &dyn Fn(u8) -> bool| {
+        (0..=8u8)
+            .filter(|n| counts(*n))
+            .fold(0u32, |mask, n| mask | 1 << n)
+    };
+    // Uniforms are read in blocks of 16 bytes
+    let params = [
+        width as u32,
+        height as u32,
+        u32::from(topology == Topology::Torus),
+        mask(&|n| rule.is_born(n)),
+        mask(&|n| rule.survives(n)),
+        0,
+        0,
+        0,
+    ];
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("params"),
+        contents: &to_bytes(&params),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let generation = |label| {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    };
+    let cells = [generation("cells"), generation("next cells")];
+    let layout = pipeline.get_bind_group_layout(0);
+    let bind_group = |from: &wgpu::Buffer, to: &wgpu::Buffer| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: from.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: to.as_entire_binding(),
+                },
+            ],
+        })
+    };
+    let bind_groups = [
+        bind_group(&cells[0], &cells[1]),
+        bind_group(&cells[1], &cells[0]),
+    ];
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    Ok((cells, bind_groups, readback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::Term;
+
+    // Not every machine has a GPU, or a driver that can run compute shaders,
+    // so these only run when asked for with --ignored, and fail without one
+
+    fn universe(board: &LifeBoard) -> GpuLife {
+        GpuLife::from_board(board).expect("Unable to start the GPU engine")
+    }
+
+    #[test]
+    #[ignore = "needs a GPU that can run compute shaders"]
+    fn the_gpu_steps_the_same_as_the_board() {
+        for topology in [Topology::Bounded, Topology::Torus] {
+            for rule in ["B3/S23", "B36/S23", "B2/S"] {
+                let mut board = LifeBoard::from(Term { w: 37, h: 23 })
+                    .with_topology(topology)
+                    .with_rule(rule.parse().expect("Unable to parse the rule"));
+                board.randomize_with_seed(7, 0.4);
+                let mut universe = universe(&board);
+                let mut written = board.clone();
+                for generation in 1..=20 {
+                    board.step();
+                    universe.step();
+                    universe.write_to(&mut written);
+                    assert_eq!(
+                        written.iter_live().collect::<Vec<_>>(),
+                        board.iter_live().collect::<Vec<_>>(),
+                        "{} {} differs at generation {}",
+                        topology,
+                        rule,
+                        generation
+                    );
+                }
+                universe.advance(30);
+                for _ in 0..30 {
+                    board.step();
+                }
+                assert_eq!(universe.population(), board.population() as u64);
+                assert_eq!(universe.generation(), 50);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU that can run compute shaders"]
+    fn the_gpu_engine_takes_edits_and_resets() {
+        let mut board = LifeBoard::from(Term { w: 10, h: 10 });
+        board.clear();
+        let mut universe = universe(&board);
+        assert_eq!(universe.population(), 0);
+        for c in 3..6 {
+            universe.set_state(&CellLocation { r: 4, c }, CellState::Alive);
+        }
+        universe.set_state(&CellLocation { r: 40, c: 4 }, CellState::Alive);
+        universe.step();
+        let standing: Vec<CellLocation> = (3..6).map(|r| CellLocation { r, c: 4 }).collect();
+        let mut live = universe.live_cells();
+        live.sort_by_key(|l| (l.r, l.c));
+        assert_eq!(live, standing);
+
+        let bigger = LifeBoard::from(Term { w: 30, h: 12 });
+        universe
+            .reset(&bigger, &CellLocation::default())
+            .expect("Unable to reset the GPU engine");
+        assert_eq!(universe.population(), bigger.population() as u64);
+        let infinite = LifeBoard::from(Term { w: 30, h: 12 }).with_topology(Topology::Infinite);
+        assert!(universe.reset(&infinite, &CellLocation::default()).is_err());
+    }
+}
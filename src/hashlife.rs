@@ -0,0 +1,495 @@
+use super::cells::{CellLocation, CellState};
+use super::life::{LifeBoard, Neighbourhood};
+use super::rule::Rule;
+use std::collections::HashMap;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+const GC_THRESHOLD: usize = 1 << 22;
+
+#[derive(Clone, Copy)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+    population: u64,
+}
+
+// Gosper's algorithm over a hash-consed quadtree. Children are ordered
+// nw, ne, sw, se and the universe is an unbounded plane, so the board
+// passed in is only ever a window onto it.
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    centre: (i64, i64),
+    generation: u64,
+}
+
+impl HashLife {
+    pub fn new(rule: Rule) -> Result<Self, String> {
+        if rule.is_born(0) {
+            return Err(format!("HashLife cannot run {} as it has B0", rule));
+        }
+        if rule.is_generations() {
+            return Err(format!(
+                "HashLife cannot run {} as it has dying states",
+                rule
+            ));
+        }
+
+        let mut universe = HashLife {
+            rule,
+            nodes: vec![
+                Node {
+                    level: 0,
+                    children: [DEAD; 4],
+                    population: 0,
+                },
+                Node {
+                    level: 0,
+                    children: [DEAD; 4],
+                    population: 1,
+                },
+            ],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            centre: (0, 0),
+            generation: 0,
+        };
+        universe.root = universe.empty_node(3);
+        universe.centre = (4, 4);
+        Ok(universe)
+    }
+
+    pub fn from_board(board: &LifeBoard) -> Result<Self, String> {
+        if board.neighbourhood() != Neighbourhood::Moore {
+            return Err(format!(
+                "HashLife cannot run a {} neighbourhood",
+                board.neighbourhood()
+            ));
+        }
+        if !board.ants().is_empty() {
+            return Err("HashLife cannot run Langton's Ant".to_string());
+        }
+        if board.colonies() > 1 {
+            return Err("HashLife cannot run more than one colony".to_string());
+        }
+        let mut universe = HashLife::new(board.rule().clone())?;
+        for location in board.iter_live() {
+            universe.set_state(&location, CellState::Alive);
+        }
+        Ok(universe)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        for j in 0..64 {
+            if generations >> j & 1 == 1 {
+                self.step_pow2(j);
+            }
+        }
+        if self.nodes.len() > GC_THRESHOLD {
+            self.collect_garbage();
+        }
+    }
+
+    pub fn set_state(&mut self, location: &CellLocation, state: CellState) {
+        let (x, y) = (location.c as i64, location.r as i64);
+        while !self.contains(x, y) {
+            self.expand();
+        }
+
+        let half = 1 << (self.level(self.root) - 1);
+        let (left, top) = (self.centre.0 - half, self.centre.1 - half);
+        self.root = self.with_state(self.root, x - left, y - top, state == CellState::Alive);
+    }
+
+    // Sets all of cells alive at once, which unlike set_state doesn't
+    // leave a node behind for every cell
+    pub fn insert_cells(&mut self, cells: &[CellLocation]) {
+        for cell in cells {
+            while !self.contains(cell.c as i64, cell.r as i64) {
+                self.expand();
+            }
+        }
+
+        let level = self.level(self.root);
+        let half = 1 << (level - 1);
+        let (left, top) = (self.centre.0 - half, self.centre.1 - half);
+        let mut points: Vec<(i64, i64)> = cells
+            .iter()
+            .map(|cell| (cell.c as i64 - left, cell.r as i64 - top))
+            .collect();
+        let added = self.build(level, &mut points);
+        self.root = self.union(self.root, added);
+    }
+
+    // Every live cell on the plane, however far out
+    pub fn live_cells(&self) -> Vec<CellLocation> {
+        let half = 1 << (self.level(self.root) - 1);
+        let (left, top) = (self.centre.0 - half, self.centre.1 - half);
+        let mut live = Vec::new();
+        self.collect_live(self.root, left, top, &mut live);
+        live.into_iter()
+            .map(|(x, y)| CellLocation {
+                r: y as i32,
+                c: x as i32,
+            })
+            .collect()
+    }
+
+    pub fn write_to(&self, board: &mut LifeBoard) {
+        self.write_window(board, &CellLocation::default());
+    }
+
+    // Fills board with the cells in view when its top left is at origin
+    pub fn write_window(&self, board: &mut LifeBoard, origin: &CellLocation) {
+        for r in 0..board.height() {
+            for c in 0..board.width() {
+                board.set_state(&CellLocation { r, c }, CellState::Dead);
+            }
+        }
+
+        let half = 1 << (self.level(self.root) - 1);
+        let mut live = Vec::new();
+        let window = (
+            origin.c as i64,
+            origin.r as i64,
+            origin.c as i64 + board.width() as i64,
+            origin.r as i64 + board.height() as i64,
+        );
+        self.collect_live_in(
+            self.root,
+            self.centre.0 - half,
+            self.centre.1 - half,
+            window,
+            &mut live,
+        );
+        for (x, y) in live {
+            let (x, y) = (x - origin.c as i64, y - origin.r as i64);
+            if x >= 0 && y >= 0 && x < board.width() as i64 && y < board.height() as i64 {
+                board.set_state(
+                    &CellLocation {
+                        r: y as i32,
+                        c: x as i32,
+                    },
+                    CellState::Alive,
+                );
+            }
+        }
+    }
+
+    fn level(&self, node: NodeId) -> u8 {
+        self.nodes[node as usize].level
+    }
+
+    fn children(&self, node: NodeId) -> [NodeId; 4] {
+        self.nodes[node as usize].children
+    }
+
+    fn population_of(&self, node: NodeId) -> u64 {
+        self.nodes[node as usize].population
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(id) = self.index.get(&children) {
+            return *id;
+        }
+
+        let node = Node {
+            level: self.level(children[0]) + 1,
+            children,
+            population: children.iter().map(|c| self.population_of(*c)).sum(),
+        };
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty_node(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = self.empty[self.empty.len() - 1];
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = 1 << (self.level(self.root) - 1);
+        x >= self.centre.0 - half
+            && x < self.centre.0 + half
+            && y >= self.centre.1 - half
+            && y < self.centre.1 + half
+    }
+
+    fn expand(&mut self) {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let e = self.empty_node(self.level(nw));
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.root = self.join([nw, ne, sw, se]);
+    }
+
+    fn with_state(&mut self, node: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let level = self.level(node);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+
+        let half = 1 << (level - 1);
+        let mut children = self.children(node);
+        let quadrant = (if y >= half { 2 } else { 0 }) + (if x >= half { 1 } else { 0 });
+        children[quadrant] = self.with_state(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+
+    fn collect_live(&self, node: NodeId, left: i64, top: i64, live: &mut Vec<(i64, i64)>) {
+        if self.population_of(node) == 0 {
+            return;
+        }
+        let level = self.level(node);
+        if level == 0 {
+            live.push((left, top));
+            return;
+        }
+
+        let half = 1 << (level - 1);
+        let [nw, ne, sw, se] = self.children(node);
+        self.collect_live(nw, left, top, live);
+        self.collect_live(ne, left + half, top, live);
+        self.collect_live(sw, left, top + half, live);
+        self.collect_live(se, left + half, top + half, live);
+    }
+
+    // The same but only the cells from left, top up to but not including
+    // right, bottom, skipping the parts of the tree outside
+    fn collect_live_in(
+        &self,
+        node: NodeId,
+        left: i64,
+        top: i64,
+        window: (i64, i64, i64, i64),
+        live: &mut Vec<(i64, i64)>,
+    ) {
+        let size = 1 << self.level(node);
+        let (w_left, w_top, w_right, w_bottom) = window;
+        if self.population_of(node) == 0
+            || left >= w_right
+            || top >= w_bottom
+            || left + size <= w_left
+            || top + size <= w_top
+        {
+            return;
+        }
+        if size == 1 {
+            live.push((left, top));
+            return;
+        }
+
+        let half = size / 2;
+        let [nw, ne, sw, se] = self.children(node);
+        self.collect_live_in(nw, left, top, window, live);
+        self.collect_live_in(ne, left + half, top, window, live);
+        self.collect_live_in(sw, left, top + half, window, live);
+        self.collect_live_in(se, left + half, top + half, window, live);
+    }
+
+    // A level node with the points alive, relative to its top left
+    fn build(&mut self, level: u8, points: &mut [(i64, i64)]) -> NodeId {
+        if points.is_empty() {
+            return self.empty_node(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1 << (level - 1);
+        let quadrant = |(x, y): &(i64, i64)| (*y >= half) as usize * 2 + (*x >= half) as usize;
+        points.sort_unstable_by_key(quadrant);
+        let mut children = [DEAD; 4];
+        let mut rest = points;
+        for (q, child) in children.iter_mut().enumerate() {
+            let n = rest.iter().take_while(|p| quadrant(p) == q).count();
+            let (mine, others) = rest.split_at_mut(n);
+            for (x, y) in mine.iter_mut() {
+                *x %= half;
+                *y %= half;
+            }
+            *child = self.build(level - 1, mine);
+            rest = others;
+        }
+        self.join(children)
+    }
+
+    // Every cell alive in either of two nodes of the same level
+    fn union(&mut self, a: NodeId, b: NodeId) -> NodeId {
+        if self.population_of(a) == 0 {
+            return b;
+        }
+        if self.population_of(b) == 0 || a == b || self.level(a) == 0 {
+            return a;
+        }
+
+        let (a, b) = (self.children(a), self.children(b));
+        let mut children = [DEAD; 4];
+        for (child, (a, b)) in children.iter_mut().zip(a.iter().zip(b.iter())) {
+            *child = self.union(*a, *b);
+        }
+        self.join(children)
+    }
+
+    fn centre(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        self.join([
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        ])
+    }
+
+    fn centre_horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let (w, e) = (self.children(w), self.children(e));
+        self.join([w[1], e[0], w[3], e[2]])
+    }
+
+    fn centre_vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let (n, s) = (self.children(n), self.children(s));
+        self.join([n[2], n[3], s[0], s[1]])
+    }
+
+    fn is_padded(&mut self) -> bool {
+        let inner = self.centre(self.root);
+        let inner = self.centre(inner);
+        self.population_of(inner) == self.population_of(self.root)
+    }
+
+    fn step_pow2(&mut self, j: u8) {
+        while self.level(self.root) < j + 3 || !self.is_padded() {
+            self.expand();
+        }
+
+        self.root = self.step(self.root, j);
+        self.generation += 1 << j;
+    }
+
+    fn step_base(&mut self, node: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        let quadrants = self.children(node);
+        for (q, quadrant) in quadrants.iter().enumerate() {
+            for (i, cell) in self.children(*quadrant).iter().enumerate() {
+                let y = (q / 2) * 2 + i / 2;
+                let x = (q % 2) * 2 + i % 2;
+                grid[y][x] = *cell == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (y, x) = (1 + i / 2, 1 + i % 2);
+            let mut neighbours = 0;
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    if (dy, dx) != (1, 1) && grid[y + dy - 1][x + dx - 1] {
+                        neighbours += 1;
+                    }
+                }
+            }
+
+            let alive = if grid[y][x] {
+                self.rule.survives(neighbours)
+            } else {
+                self.rule.is_born(neighbours)
+            };
+            if alive {
+                *cell = ALIVE;
+            }
+        }
+        self.join(next)
+    }
+
+    // Advances a level k node by 2^j generations, returning its centre as
+    // a level k - 1 node. Requires j <= k - 2.
+    fn step(&mut self, node: NodeId, j: u8) -> NodeId {
+        let level = self.level(node);
+        if self.population_of(node) == 0 {
+            return self.empty_node(level - 1);
+        }
+        if let Some(result) = self.results.get(&(node, j)) {
+            return *result;
+        }
+
+        let result = if level == 2 {
+            self.step_base(node)
+        } else {
+            let [nw, ne, sw, se] = self.children(node);
+            let n01 = self.centre_horizontal(nw, ne);
+            let n10 = self.centre_vertical(nw, sw);
+            let n11 = self.centre(node);
+            let n12 = self.centre_vertical(ne, se);
+            let n21 = self.centre_horizontal(sw, se);
+            let nine = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+
+            let mut parts = [DEAD; 9];
+            for (part, n) in parts.iter_mut().zip(nine.iter()) {
+                *part = if j + 2 == level {
+                    self.step(*n, level - 3)
+                } else {
+                    self.centre(*n)
+                };
+            }
+
+            let next_j = if j + 2 == level { level - 3 } else { j };
+            let corners = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+            let mut quadrants = [DEAD; 4];
+            for (quadrant, corner) in quadrants.iter_mut().zip(corners.iter()) {
+                let combined = self.join([
+                    parts[corner[0]],
+                    parts[corner[1]],
+                    parts[corner[2]],
+                    parts[corner[3]],
+                ]);
+                *quadrant = self.step(combined, next_j);
+            }
+            self.join(quadrants)
+        };
+
+        self.results.insert((node, j), result);
+        result
+    }
+
+    fn collect_garbage(&mut self) {
+        let half = 1 << (self.level(self.root) - 1);
+        let (left, top) = (self.centre.0 - half, self.centre.1 - half);
+        let level = self.level(self.root);
+        let mut live = Vec::new();
+        self.collect_live(self.root, left, top, &mut live);
+
+        self.nodes.truncate(2);
+        self.index.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+        self.root = self.empty_node(level);
+        for (x, y) in live {
+            self.root = self.with_state(self.root, x - left, y - top, true);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::cells::CellLocation;
+use super::life::LifeBoard;
+use std::collections::VecDeque;
+
+// How many generations out of the last so many each cell has been alive in,
+// like a long exposure of the board. Gun lanes and oscillators show up hot
+// and the places nothing ever happens stay cold.
+#[derive(Clone, Debug)]
+pub struct Heatmap {
+    generations: usize,
+    width: i32,
+    height: i32,
+    counts: Vec<u32>,
+    // Which cells were alive in each generation counted, oldest first
+    past: VecDeque<Vec<bool>>,
+}
+
+impl Heatmap {
+    pub fn new(generations: usize) -> Heatmap {
+        Heatmap {
+            generations: generations.max(1),
+            width: 0,
+            height: 0,
+            counts: Vec::new(),
+            past: VecDeque::new(),
+        }
+    }
+
+    pub fn generations(&self) -> usize {
+        self.generations
+    }
+
+    // Counting starts again if the board's changed size
+    pub fn observe(&mut self, board: &LifeBoard) {
+        if (board.width(), board.height()) != (self.width, self.height) {
+            self.width = board.width();
+            self.height = board.height();
+            self.counts = vec![0; (self.width * self.height) as usize];
+            self.past.clear();
+        }
+        let mut alive = vec![false; self.counts.len()];
+        for location in board.iter_live() {
+            let i = (location.r * self.width + location.c) as usize;
+            alive[i] = true;
+            self.counts[i] += 1;
+        }
+        self.past.push_back(alive);
+        if self.past.len() > self.generations {
+            let oldest = self.past.pop_front().expect("There's more than one");
+            for (count, was_alive) in self.counts.iter_mut().zip(oldest) {
+                *count -= was_alive as u32;
+            }
+        }
+    }
+
+    // The fraction of the generations counted so far that the cell was
+    // alive in
+    pub fn heat(&self, location: &CellLocation) -> f32 {
+        let (r, c) = (location.r, location.c);
+        if self.past.is_empty() || !(0..self.height).contains(&r) || !(0..self.width).contains(&c) {
+            return 0.0;
+        }
+        self.counts[(r * self.width + c) as usize] as f32 / self.past.len() as f32
+    }
+
+    // Every cell that's been alive at all, with its heat
+    pub fn cells(&self) -> impl Iterator<Item = (CellLocation, f32)> + '_ {
+        let width = self.width.max(1);
+        let generations = self.past.len().max(1) as f32;
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(move |(i, count)| {
+                let i = i as i32;
+                (
+                    CellLocation {
+                        r: i / width,
+                        c: i % width,
+                    },
+                    *count as f32 / generations,
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::{Edges, StampMode, Term};
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn only_the_last_generations_are_counted() {
+        let mut board = LifeBoard::from(Term { w: 5, h: 5 });
+        board.clear();
+        let blinker = Pattern::from_rle("3o!").expect("Unable to parse the blinker");
+        board.stamp(
+            &blinker,
+            CellLocation { r: 2, c: 1 },
+            StampMode::Or,
+            Edges::Reject,
+        );
+        let mut heatmap = Heatmap::new(4);
+        for _ in 0..6 {
+            heatmap.observe(&board);
+            board.step();
+        }
+        let at = |r, c| CellLocation { r, c };
+        assert_eq!(heatmap.heat(&at(2, 2)), 1.0);
+        assert_eq!(heatmap.heat(&at(2, 1)), 0.5);
+        assert_eq!(heatmap.heat(&at(1, 2)), 0.5);
+        assert_eq!(heatmap.heat(&at(0, 0)), 0.0);
+        assert_eq!(heatmap.heat(&at(9, 9)), 0.0);
+        assert_eq!(heatmap.cells().count(), 5);
+
+        // A board of another size starts the count again
+        let mut wider = LifeBoard::from(Term { w: 8, h: 3 });
+        wider.clear();
+        heatmap.observe(&wider);
+        assert_eq!(heatmap.heat(&at(2, 2)), 0.0);
+        assert_eq!(heatmap.cells().count(), 0);
+    }
+}
@@ -0,0 +1,304 @@
+// What the keys do in the window and the terminal front end. The arrow keys,
+// Home and the number keys that pick stamps aren't in here, they move
+// around the board or pick from a list rather than doing one thing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Quit,
+    Pause,
+    Step,
+    Faster,
+    Slower,
+    GreySettled,
+    Grid,
+    Tracks,
+    Heatmap,
+    ShorterTrails,
+    LongerTrails,
+    ColourByAge,
+    Fade,
+    Theme,
+    Chaos,
+    Save,
+    Snapshot,
+    Hud,
+    Rotate,
+    Mirror,
+    Flip,
+    DropStamp,
+    Select,
+    // Only while selecting, so they can share keys with the others
+    Copy,
+    Erase,
+    Paste,
+    // RLE through the system clipboard
+    CopyRle,
+    PasteRle,
+    // Edits made by hand
+    Undo,
+    Redo,
+    // The whole board at once
+    Clear,
+    Reseed,
+    Invert,
+    Grow,
+    Shrink,
+    // A run of generations without drawing each one
+    Skip,
+}
+
+// Each action and the keys it starts out on. Keys are named the way
+// Keymap::action is asked for them: one character for a printable key,
+// capitals being the shifted letters, otherwise the lowercase name of the
+// key without spaces, e.g. space, escape or keypad+. Holding Shift puts
+// shift+ in front of a named key and holding Ctrl puts ctrl+ in front of
+// that.
+const DEFAULT_KEYS: [(Action, &[&str]); 36] = [
+    (Action::Quit, &["escape", "q"]),
+    (Action::Pause, &["space"]),
+    (Action::Step, &["n", "."]),
+    (Action::Faster, &["+", "=", "keypad+"]),
+    (Action::Slower, &["-", "keypad-"]),
+    (Action::GreySettled, &["g"]),
+    (Action::Grid, &["G"]),
+    (Action::Tracks, &["T"]),
+    (Action::Heatmap, &["H"]),
+    (Action::ShorterTrails, &["["]),
+    (Action::LongerTrails, &["]"]),
+    (Action::ColourByAge, &["a"]),
+    (Action::Fade, &["k"]),
+    (Action::Theme, &["t"]),
+    (Action::Chaos, &["c"]),
+    (Action::Save, &["s"]),
+    (Action::Snapshot, &["p"]),
+    (Action::Hud, &["h"]),
+    (Action::Rotate, &["r"]),
+    (Action::Mirror, &["f"]),
+    (Action::Flip, &["F"]),
+    (Action::DropStamp, &["0"]),
+    (Action::Select, &["m"]),
+    (Action::Copy, &["c"]),
+    (Action::Erase, &["x"]),
+    (Action::Paste, &["v"]),
+    (Action::CopyRle, &["ctrl+c"]),
+    (Action::PasteRle, &["ctrl+v"]),
+    (Action::Undo, &["ctrl+z"]),
+    (Action::Redo, &["ctrl+y"]),
+    (Action::Clear, &["C"]),
+    (Action::Reseed, &["R"]),
+    (Action::Invert, &["I"]),
+    // The window can't tell } from ] so it has Ctrl instead, the terminal
+    // sees Ctrl+[ as escape
+    (Action::Grow, &["}", "ctrl+]"]),
+    (Action::Shrink, &["{", "ctrl+["]),
+    (Action::Skip, &["shift+right"]),
+];
+
+impl Action {
+    pub fn all() -> impl Iterator<Item = Action> {
+        DEFAULT_KEYS.iter().map(|(action, _)| *action)
+    }
+
+    pub fn is_for_selection(&self) -> bool {
+        matches!(self, Action::Copy | Action::Erase | Action::Paste)
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        Action::all()
+            .find(|action| action.to_string() == name)
+            .ok_or_else(|| format!("Unknown action {}", s))
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Action::Quit => "quit",
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::GreySettled => "grey-settled",
+            Action::Grid => "grid",
+            Action::Tracks => "tracks",
+            Action::Heatmap => "heatmap",
+            Action::ShorterTrails => "shorter-trails",
+            Action::LongerTrails => "longer-trails",
+            Action::ColourByAge => "colour-by-age",
+            Action::Fade => "fade",
+            Action::Theme => "theme",
+            Action::Chaos => "chaos",
+            Action::Save => "save",
+            Action::Snapshot => "snapshot",
+            Action::Hud => "hud",
+            Action::Rotate => "rotate",
+            Action::Mirror => "mirror",
+            Action::Flip => "flip",
+            Action::DropStamp => "drop-stamp",
+            Action::Select => "select",
+            Action::Copy => "copy",
+            Action::Erase => "erase",
+            Action::Paste => "paste",
+            Action::CopyRle => "copy-rle",
+            Action::PasteRle => "paste-rle",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Clear => "clear",
+            Action::Reseed => "reseed",
+            Action::Invert => "invert",
+            Action::Grow => "grow",
+            Action::Shrink => "shrink",
+            Action::Skip => "skip",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Single characters keep their case so shifted letters can be told apart,
+// anything longer is a key's name and isn't fussy about it
+fn key_name(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_string()
+    } else {
+        key.chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+}
+
+// Actions and the keys they're bound to, written action=key and separated
+// by spaces, e.g. "pause=p step=space faster==" where the last binds the =
+// key
+pub fn parse_bindings(s: &str) -> Result<Vec<(Action, String)>, String> {
+    s.split_whitespace()
+        .map(|binding| {
+            let (action, key) = binding
+                .split_once('=')
+                .ok_or_else(|| format!("{} isn't in the form action=key", binding))?;
+            if key.is_empty() {
+                return Err(format!("{} has no key", binding));
+            }
+            Ok((action.parse()?, key_name(key)))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    keys: Vec<(Action, Vec<String>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            keys: DEFAULT_KEYS
+                .iter()
+                .map(|(action, keys)| (*action, keys.iter().map(|k| k.to_string()).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    // The defaults with every action in bindings moved to the keys it's
+    // given there instead. A key can only do one thing so it's taken off
+    // whatever it did before, unless only one of them is for selections.
+    pub fn with_bindings(bindings: &[(Action, String)]) -> Keymap {
+        let mut keymap = Keymap::default();
+        for (action, keys) in keymap.keys.iter_mut() {
+            if bindings.iter().any(|(bound, _)| bound == action) {
+                keys.clear();
+            }
+        }
+        for (action, key) in bindings {
+            for (other, keys) in keymap.keys.iter_mut() {
+                if other == action {
+                    keys.push(key.clone());
+                } else if other.is_for_selection() == action.is_for_selection() {
+                    keys.retain(|k| k != key);
+                }
+            }
+        }
+        keymap
+    }
+
+    // While selecting the key's selection action comes first. A named key
+    // held with Shift does what it does without unless something's bound to
+    // it shifted.
+    pub fn action(&self, key: &str, is_selecting: bool) -> Option<Action> {
+        let key = key_name(key);
+        let bound_to = |key: &String| -> Vec<Action> {
+            self.keys
+                .iter()
+                .filter(|(_, keys)| keys.contains(key))
+                .map(|(action, _)| *action)
+                .collect()
+        };
+        let mut bound = bound_to(&key);
+        if bound.is_empty() && key.contains("shift+") {
+            bound = bound_to(&key.replacen("shift+", "", 1));
+        }
+        bound
+            .iter()
+            .find(|action| is_selecting && action.is_for_selection())
+            .or_else(|| bound.iter().find(|action| !action.is_for_selection()))
+            .copied()
+    }
+
+    pub fn keys(&self, action: Action) -> &[String] {
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_moves_the_key_off_its_old_action() {
+        let bindings = parse_bindings("pause=p pause=Space  step=G faster== slower=Keypad-")
+            .expect("Unable to parse the bindings");
+        let keymap = Keymap::with_bindings(&bindings);
+        assert_eq!(keymap.action("p", false), Some(Action::Pause));
+        assert_eq!(keymap.action("SPACE", false), Some(Action::Pause));
+        assert_eq!(keymap.keys(Action::Snapshot), &[] as &[String]);
+        assert_eq!(keymap.action("G", false), Some(Action::Step));
+        assert_eq!(keymap.action("g", false), Some(Action::GreySettled));
+        assert_eq!(keymap.action("n", false), None);
+        assert_eq!(keymap.keys(Action::Faster), ["="]);
+        assert_eq!(keymap.action("+", false), None);
+        assert_eq!(keymap.action("Keypad -", false), Some(Action::Slower));
+        assert_eq!(keymap.action("c", false), Some(Action::Chaos));
+        assert_eq!(keymap.action("c", true), Some(Action::Copy));
+        assert_eq!(keymap.action("x", false), None);
+        assert_eq!(keymap.action("Ctrl+C", false), Some(Action::CopyRle));
+
+        let keymap = Keymap::with_bindings(&[(Action::Paste, "c".to_string())]);
+        assert_eq!(keymap.action("c", true), Some(Action::Paste));
+        assert_eq!(keymap.action("c", false), Some(Action::Chaos));
+        assert_eq!(keymap.keys(Action::Copy), &[] as &[String]);
+        for bindings in ["pause", "pause=", "jump=j", "pause=p step"] {
+            assert!(parse_bindings(bindings).is_err(), "{} parsed", bindings);
+        }
+    }
+
+    #[test]
+    fn shifted_keys_fall_back_to_what_they_do_unshifted() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action("Shift+Right", false), Some(Action::Skip));
+        assert_eq!(keymap.action("right", false), None);
+        assert_eq!(keymap.action("shift+space", false), Some(Action::Pause));
+        assert_eq!(keymap.action("ctrl+shift+z", false), Some(Action::Undo));
+        let keymap = Keymap::with_bindings(&[(Action::Step, "shift+space".to_string())]);
+        assert_eq!(keymap.action("shift+space", false), Some(Action::Step));
+        assert_eq!(keymap.action("space", false), Some(Action::Pause));
+    }
+}
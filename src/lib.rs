@@ -0,0 +1,75 @@
+pub mod ant;
+pub mod automaton;
+pub mod camera;
+pub mod catalog;
+pub mod cells;
+pub mod census;
+pub mod comparison;
+pub mod compositor;
+pub mod config;
+pub mod cycle;
+pub mod engine;
+pub mod error;
+pub mod export;
+pub mod fade;
+#[cfg(feature = "online")]
+pub mod fetch;
+pub mod font;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod hashlife;
+pub mod heatmap;
+pub mod keymap;
+pub mod life;
+pub mod pattern;
+pub mod platform;
+pub mod preset;
+pub mod repro;
+pub mod rule;
+pub mod settings;
+pub mod simulation;
+pub mod soup;
+pub mod sparse;
+pub mod stats;
+pub mod theme;
+pub mod tracks;
+pub mod undo;
+pub mod verify;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use ant::{Ant, Heading};
+pub use automaton::{Automaton, Ca};
+pub use camera::Camera;
+pub use catalog::{Catalog, CatalogEntry};
+pub use cells::{Cell, CellLocation, CellState};
+pub use census::{Census, Object, Recogniser};
+pub use comparison::Comparison;
+pub use compositor::{Compositor, Layer, Stereo};
+pub use config::{Config, ConfigValue};
+pub use cycle::{Cycle, CycleDetector};
+pub use engine::{Engine, Universe};
+pub use error::GameOfLifeError;
+pub use fade::{age_intensity, wire_colour, Colony, ColorScheme, Fade, ANT_COLOUR};
+#[cfg(feature = "gpu")]
+pub use gpu::GpuLife;
+pub use hashlife::HashLife;
+pub use heatmap::Heatmap;
+pub use keymap::{Action, Keymap};
+pub use life::{
+    Anchor, Edges, LifeBoard, Neighbourhood, OutOfBounds, StampMode, Term, Topology, MAX_COLONIES,
+};
+pub use pattern::Pattern;
+pub use platform::Paths;
+pub use preset::{generate_cave, Preset};
+pub use repro::{Repro, ReproEnd, ReproSettings, ReproWriter};
+pub use rule::Rule;
+pub use settings::{GUISettings, Renderer};
+pub use simulation::{Simulation, MAX_HISTORY};
+pub use soup::{Haul, Soup, SoupSearch};
+pub use sparse::SparseLife;
+pub use stats::StatsWriter;
+pub use theme::{Grid, Theme, MIN_GRID_CELL, THEMES};
+pub use tracks::{ShipTracker, Track, Tracks};
+pub use undo::UndoStack;
+pub use verify::Reference;
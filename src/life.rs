@@ -1,19 +1,32 @@
+use super::ant::{Ant, Heading};
+use super::automaton::Automaton;
 use super::cells::*;
-use rand::{thread_rng, Rng};
+use super::error::GameOfLifeError;
+use super::pattern::Pattern;
+use super::rule::Rule;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Term {
     pub w: i32,
     pub h: i32,
 }
 
-impl Default for Term {
-    fn default() -> Self {
-        let (w, h) = term_size::dimensions().expect("Unable to get term dimensions");
-        Term {
-            w: (w - 2) as i32,
-            h: (h - 2) as i32,
-        }
+impl Term {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_terminal() -> Result<Term, GameOfLifeError> {
+        let (w, h) = term_size::dimensions().ok_or(GameOfLifeError::TerminalSize)?;
+        Ok(Term {
+            w: w.saturating_sub(2) as i32,
+            h: h.saturating_sub(2) as i32,
+        })
+    }
+
+    // A browser has no terminal to measure
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_terminal() -> Result<Term, GameOfLifeError> {
+        Err(GameOfLifeError::TerminalSize)
     }
 }
 
@@ -23,18 +36,275 @@ impl std::fmt::Display for Term {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Topology {
+    #[default]
+    Bounded,
+    Torus,
+    // Only the sparse and HashLife engines can run an infinite plane, the
+    // board is then a view onto it
+    Infinite,
+}
+
+impl std::str::FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bounded" => Ok(Topology::Bounded),
+            "torus" => Ok(Topology::Torus),
+            "infinite" => Ok(Topology::Infinite),
+            _ => Err(format!("Unknown topology {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let t = match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+            Topology::Infinite => "infinite",
+        };
+        write!(f, "{}", t)
+    }
+}
+
+// Which part of a board stays put when it's resized, the rest of it grows
+// or is cut off
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    #[default]
+    Centre,
+}
+
+impl Anchor {
+    // Where the top left of a board the size of from ends up on one the
+    // size of to
+    pub fn offset(&self, from: &Term, to: &Term) -> CellLocation {
+        let (dr, dc) = (to.h - from.h, to.w - from.w);
+        let (r, c) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::TopRight => (0, dc),
+            Anchor::BottomLeft => (dr, 0),
+            Anchor::BottomRight => (dr, dc),
+            Anchor::Centre => (dr / 2, dc / 2),
+        };
+        CellLocation { r, c }
+    }
+}
+
+impl std::str::FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top-left" => Ok(Anchor::TopLeft),
+            "top-right" => Ok(Anchor::TopRight),
+            "bottom-left" => Ok(Anchor::BottomLeft),
+            "bottom-right" => Ok(Anchor::BottomRight),
+            "centre" | "center" => Ok(Anchor::Centre),
+            _ => Err(format!("Unknown anchor {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Anchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let a = match self {
+            Anchor::TopLeft => "top-left",
+            Anchor::TopRight => "top-right",
+            Anchor::BottomLeft => "bottom-left",
+            Anchor::BottomRight => "bottom-right",
+            Anchor::Centre => "centre",
+        };
+        write!(f, "{}", a)
+    }
+}
+
+// How a stamped pattern's cells go with the ones already on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StampMode {
+    // Everything inside the pattern's bounding box takes its state, the
+    // cells it doesn't have included, which die
+    Overwrite,
+    // Only the pattern's own cells are set, the rest are left as they are
+    #[default]
+    Or,
+    // Each of the pattern's cells is set unless it's already in that
+    // state, when it dies instead
+    Xor,
+}
+
+// What becomes of a stamped pattern that doesn't fit on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Edges {
+    // The part off the board is left out
+    Clip,
+    // Nothing is stamped
+    #[default]
+    Reject,
+    // The part off one edge comes back on the other, as on a torus
+    Wrap,
+}
+
+// A cell asked for by row and column that isn't on the board
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{location} isn't on the {width}x{height} board")]
+pub struct OutOfBounds {
+    pub location: CellLocation,
+    pub width: i32,
+    pub height: i32,
+}
+
+// Every stage of dying, for indexing to hand out a reference to
+static DYING: [CellState; 256] = {
+    const DEAD: CellState = CellState::Dead;
+    let mut stages = [DEAD; 256];
+    let mut stage = 0;
+    while stage < 256 {
+        stages[stage] = CellState::Dying(stage as u8);
+        stage += 1;
+    }
+    stages
+};
+
+// Which cells count as neighbours. Hexagonal boards are stored as offset
+// rows, each odd row sitting half a cell to the right of the even rows, so
+// a torus needs an even number of rows to join up without a seam.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Neighbourhood {
+    #[default]
+    Moore,
+    VonNeumann,
+    Hexagonal,
+}
+
+const MOORE: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const VON_NEUMANN: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const HEX_EVEN_ROW: [(i32, i32); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD_ROW: [(i32, i32); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+impl Neighbourhood {
+    // The offsets of the neighbours of a cell in row r
+    pub(crate) fn offsets(&self, r: i32) -> &'static [(i32, i32)] {
+        match self {
+            Neighbourhood::Moore => &MOORE,
+            Neighbourhood::VonNeumann => &VON_NEUMANN,
+            Neighbourhood::Hexagonal if r.rem_euclid(2) == 1 => &HEX_ODD_ROW,
+            Neighbourhood::Hexagonal => &HEX_EVEN_ROW,
+        }
+    }
+
+    // Golly's suffixes, used to keep the neighbourhood with the rule in
+    // saved boards
+    fn suffix(&self) -> &'static str {
+        match self {
+            Neighbourhood::Moore => "",
+            Neighbourhood::VonNeumann => "V",
+            Neighbourhood::Hexagonal => "H",
+        }
+    }
+}
+
+impl std::str::FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "moore" => Ok(Neighbourhood::Moore),
+            "von-neumann" => Ok(Neighbourhood::VonNeumann),
+            "hexagonal" => Ok(Neighbourhood::Hexagonal),
+            _ => Err(format!("Unknown neighbourhood {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let n = match self {
+            Neighbourhood::Moore => "moore",
+            Neighbourhood::VonNeumann => "von-neumann",
+            Neighbourhood::Hexagonal => "hexagonal",
+        };
+        write!(f, "{}", n)
+    }
+}
+
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD_WORDS: usize = 1024;
+
+const HISTORY_BITS: usize = 32;
+pub const MAX_COLONIES: u8 = 4;
+const SAVE_MAGIC: &[u8] = b"GOL1";
+const WIRES_SECTION: u8 = b'W';
+const ANTS_SECTION: u8 = b'A';
+const MAX_SAVED_EDGE: u32 = 1 << 16;
+
+fn column_bit(c: i32) -> (usize, u64) {
+    (c as usize / 64, 1 << (c as usize % 64))
+}
+
+// The buffer the next generation is written into before being swapped in.
+// It's never worth copying so clones of a board start without one.
+#[derive(Default)]
+struct Scratch(Vec<u64>);
+
+impl Clone for Scratch {
+    fn clone(&self) -> Self {
+        Scratch::default()
+    }
+}
+
 #[derive(Clone)]
 pub struct LifeBoard {
     iteration: usize,
-    pub cells: Vec<Vec<Cell>>,
     dimensions: Term,
+    topology: Topology,
+    neighbourhood: Neighbourhood,
+    rule: Rule,
+    words_per_row: usize,
+    bits: Vec<u64>,
+    // The dying state of each cell under a Generations rule, 0 if it
+    // isn't dying. Empty for life-like rules.
+    decay: Vec<u8>,
+    // Which colony each live cell belongs to when there's more than one,
+    // as in Immigration. Empty for a single colony.
+    colonies: u8,
+    colony: Vec<u8>,
+    // The conductors of a WireWorld board, laid out like bits, where the
+    // only cells that can be born. Empty for every other board.
+    wires: Vec<u64>,
+    // With any ants the board is Langton's Ant and the rule isn't used
+    ants: Vec<Ant>,
+    // Bit n of each cell is whether it was alive n generations ago. This
+    // and the ages are only kept while track_history is on.
+    history: bool,
+    cell_history: Vec<u32>,
+    // How many generations in a row each cell has been recorded alive
+    ages: Vec<u16>,
+    recorded: usize,
+    scratch: Scratch,
 }
 
 impl std::fmt::Display for LifeBoard {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for row in &self.cells {
+        for row in self.iter_rows() {
             for cell in row {
-                write!(f, "{}", cell)?;
+                write!(f, "{}", cell.state)?;
             }
             writeln!(f)?;
         }
@@ -42,128 +312,2017 @@ impl std::fmt::Display for LifeBoard {
     }
 }
 
+// The cells are kept as bits, so indexing the board hands out one of the
+// states above rather than a reference into it. There's no IndexMut for the
+// same reason, set or set_state change a cell. Panics for a cell that isn't
+// on the board, like indexing a slice.
+impl std::ops::Index<&CellLocation> for LifeBoard {
+    type Output = CellState;
+
+    fn index(&self, location: &CellLocation) -> &CellState {
+        match self.state(location) {
+            Some(CellState::Alive) => &CellState::Alive,
+            Some(CellState::Dying(stage)) => &DYING[stage as usize],
+            Some(CellState::Wire) => &CellState::Wire,
+            Some(CellState::Dead) => &CellState::Dead,
+            None => panic!(
+                "{}",
+                OutOfBounds {
+                    location: location.clone(),
+                    width: self.dimensions.w,
+                    height: self.dimensions.h,
+                }
+            ),
+        }
+    }
+}
+
+impl std::ops::Index<CellLocation> for LifeBoard {
+    type Output = CellState;
+
+    fn index(&self, location: CellLocation) -> &CellState {
+        &self[&location]
+    }
+}
+
 impl From<Term> for LifeBoard {
     fn from(t: Term) -> LifeBoard {
-        let mut board = LifeBoard {
-            cells: vec![vec![Cell::default(); t.w as usize]; t.h as usize],
+        let mut board = LifeBoard::empty(t);
+        board.randomise();
+        board
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.offset < n {
+            return Err(format!(
+                "Board data is truncated, wanted {} bytes at byte {}",
+                n, self.offset
+            ));
+        }
+        let taken = &self.bytes[self.offset..self.offset + n];
+        self.offset += n;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut word = [0; 4];
+        word.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(word))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let mut word = [0; 8];
+        word.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(word))
+    }
+}
+
+impl LifeBoard {
+    fn empty(t: Term) -> LifeBoard {
+        let words_per_row = (t.w as usize).div_ceil(64).max(1);
+        LifeBoard {
+            bits: vec![0; words_per_row * t.h as usize],
+            words_per_row,
+            decay: Vec::new(),
+            colonies: 1,
+            colony: Vec::new(),
+            wires: Vec::new(),
+            ants: Vec::new(),
             dimensions: t,
             iteration: 0,
+            topology: Topology::default(),
+            neighbourhood: Neighbourhood::default(),
+            rule: Rule::default(),
+            history: false,
+            cell_history: Vec::new(),
+            ages: Vec::new(),
+            recorded: 0,
+            scratch: Scratch::default(),
+        }
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.decay = if rule.is_generations() {
+            vec![0; (self.dimensions.w * self.dimensions.h) as usize]
+        } else {
+            Vec::new()
         };
+        self.rule = rule;
+        self
+    }
 
-        board.assign_locations();
-        board
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.neighbourhood = neighbourhood;
+        self
     }
-}
 
-impl LifeBoard {
-    #[allow(unused)]
-    fn insert_oscillator(&mut self, top_left: CellLocation) -> Option<CellLocation> {
-        let thingy_width = 3;
-        let thingy_height = 3;
+    // Newborn cells join the colony most of their parents belong to
+    pub fn with_colonies(mut self, colonies: u8) -> Self {
+        self.colonies = colonies.clamp(1, MAX_COLONIES);
+        self.colony = if self.colonies > 1 {
+            vec![0; (self.dimensions.w * self.dimensions.h) as usize]
+        } else {
+            Vec::new()
+        };
+        self
+    }
 
-        if top_left.c + thingy_width >= self.dimensions.w
-            || top_left.r + thingy_height >= self.dimensions.h
-        {
+    // Starts with no conductors, with the WireWorld rule cells are only
+    // born on them
+    pub fn with_wires(mut self) -> Self {
+        self.wires = vec![0; self.bits.len()];
+        self
+    }
+
+    pub fn is_wired(&self) -> bool {
+        !self.wires.is_empty()
+    }
+
+    // Ants off the board are dropped. They move in the order given, so
+    // where two meet the first flips the cell before the second reads it.
+    pub fn with_ants(mut self, ants: Vec<Ant>) -> Self {
+        self.ants = ants
+            .into_iter()
+            .filter(|ant| self.contains(&ant.location))
+            .collect();
+        self
+    }
+
+    pub fn ants(&self) -> &[Ant] {
+        &self.ants
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn colonies(&self) -> u8 {
+        self.colonies
+    }
+
+    // None for a dead cell or one off the board
+    pub fn colony(&self, location: &CellLocation) -> Option<u8> {
+        if !self.contains(location) || !self.is_alive(location.r, location.c) {
             return None;
         }
+        Some(self.colony.get(self.index(location)).copied().unwrap_or(0))
+    }
+
+    pub fn set_colony(&mut self, location: &CellLocation, colony: u8) {
+        if self.contains(location) {
+            let i = self.index(location);
+            let last = self.colonies - 1;
+            if let Some(c) = self.colony.get_mut(i) {
+                *c = colony.min(last);
+            }
+        }
+    }
 
-        self.cells[top_left.r as usize][top_left.c as usize + 1].state = CellState::Alive;
-        self.cells[top_left.r as usize + 1][top_left.c as usize + 1].state = CellState::Alive;
-        self.cells[top_left.r as usize + 2][top_left.c as usize + 1].state = CellState::Alive;
-        Some(top_left)
+    // How many live cells each colony has
+    pub fn colony_populations(&self) -> Vec<usize> {
+        let mut populations = vec![0; self.colonies as usize];
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                let location = CellLocation { r, c };
+                if let Some(colony) = self.colony(&location) {
+                    populations[colony as usize] += 1;
+                }
+            }
+        }
+        populations
     }
-    #[allow(unused)]
-    fn get_random_location(&self) -> CellLocation {
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    pub fn population(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // Cells born and cells that died going from previous to this board,
+    // both boards must be the same size
+    pub fn births_and_deaths(&self, previous: &LifeBoard) -> (usize, usize) {
+        self.bits.iter().zip(previous.bits.iter()).fold(
+            (0, 0),
+            |(births, deaths), (now, before)| {
+                (
+                    births + (now & !before).count_ones() as usize,
+                    deaths + (before & !now).count_ones() as usize,
+                )
+            },
+        )
+    }
+
+    // Cells whose state or colony differs between previous and this board,
+    // in row order. Unchanged words of the bitset are skipped 64 cells at a
+    // time unless the board has dying states or colonies to compare too.
+    // Both boards must be the same size.
+    pub fn changed_cells<'a>(
+        &'a self,
+        previous: &'a LifeBoard,
+    ) -> impl Iterator<Item = CellLocation> + 'a {
+        let has_cell_data = !self.decay.is_empty() || !self.colony.is_empty();
+        (0..self.dimensions.h).flat_map(move |r| {
+            (0..self.words_per_row).flat_map(move |word| {
+                let i = r as usize * self.words_per_row + word;
+                let first = word as i32 * 64;
+                let width = (self.dimensions.w - first).min(64);
+                let mut changed = self.bits[i] ^ previous.bits[i];
+                if let (Some(now), Some(before)) = (self.wires.get(i), previous.wires.get(i)) {
+                    changed |= now ^ before;
+                }
+                if has_cell_data {
+                    for bit in 0..width {
+                        let j = self.index(&CellLocation { r, c: first + bit });
+                        if self.decay.get(j) != previous.decay.get(j)
+                            || self.colony.get(j) != previous.colony.get(j)
+                        {
+                            changed |= 1 << bit;
+                        }
+                    }
+                }
+                std::iter::from_fn(move || {
+                    let bit = changed.trailing_zeros() as i32;
+                    if bit >= width {
+                        return None;
+                    }
+                    changed &= changed - 1;
+                    Some(CellLocation { r, c: first + bit })
+                })
+            })
+        })
+    }
+
+    // Equal boards have equal fingerprints. Only good within one run as
+    // the std hasher can change between builds.
+    pub fn fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.dimensions.w.hash(&mut hasher);
+        self.dimensions.h.hash(&mut hasher);
+        self.bits.hash(&mut hasher);
+        self.decay.hash(&mut hasher);
+        self.colony.hash(&mut hasher);
+        self.wires.hash(&mut hasher);
+        self.ants.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn width(&self) -> i32 {
+        self.dimensions.w
+    }
+
+    pub fn height(&self) -> i32 {
+        self.dimensions.h
+    }
+
+    pub fn state(&self, location: &CellLocation) -> Option<CellState> {
+        if !self.contains(location) {
+            return None;
+        }
+        if self.is_alive(location.r, location.c) {
+            return Some(CellState::Alive);
+        }
+        match self.decay.get(self.index(location)) {
+            Some(stage) if *stage > 0 => Some(CellState::Dying(*stage)),
+            _ if self.has_wire(location.r, location.c) => Some(CellState::Wire),
+            _ => Some(CellState::Dead),
+        }
+    }
+
+    pub fn get(&self, r: i32, c: i32) -> Option<CellState> {
+        self.state(&CellLocation { r, c })
+    }
+
+    // set_state by row and column, which says if the cell's off the board
+    // rather than ignoring it
+    pub fn set(&mut self, r: i32, c: i32, state: CellState) -> Result<(), OutOfBounds> {
+        let location = CellLocation { r, c };
+        if !self.contains(&location) {
+            return Err(OutOfBounds {
+                location,
+                width: self.dimensions.w,
+                height: self.dimensions.h,
+            });
+        }
+        self.set_state(&location, state);
+        Ok(())
+    }
+
+    // Dying states past the end of the rule's decay are clamped to its
+    // last, and ignored altogether by life-like rules. On a wired board
+    // anything but Dead lays a conductor under the cell, elsewhere Wire is
+    // the same as Dead.
+    pub fn set_state(&mut self, location: &CellLocation, state: CellState) {
+        if !self.contains(location) {
+            return;
+        }
+        self.set_alive(location.r, location.c, state == CellState::Alive);
+        let i = self.index(location);
+        let last_stage = self.rule.states().saturating_sub(2);
+        if let Some(stage) = self.decay.get_mut(i) {
+            *stage = match state {
+                CellState::Dying(stage) => stage.clamp(1, last_stage),
+                CellState::Alive | CellState::Wire | CellState::Dead => 0,
+            };
+        }
+        if self.is_wired() {
+            let (word, mask) = self.bit(location.r, location.c);
+            if state == CellState::Dead {
+                self.wires[word] &= !mask;
+            } else {
+                self.wires[word] |= mask;
+            }
+        }
+        if let (false, Some(colony)) = (
+            self.is_alive(location.r, location.c),
+            self.colony.get_mut(i),
+        ) {
+            *colony = 0;
+        }
+    }
+
+    fn index(&self, location: &CellLocation) -> usize {
+        (location.r * self.dimensions.w + location.c) as usize
+    }
+
+    // Every cell in reading order, with its state
+    pub fn iter_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.iter_rows().flatten()
+    }
+
+    // The cells a row at a time, top to bottom
+    pub fn iter_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Cell> + '_> + '_ {
+        (0..self.dimensions.h).map(move |r| self.row(r))
+    }
+
+    // Where the live cells are, in reading order. Only the words with
+    // something alive in them are looked into, so a sparse board is quick.
+    pub fn iter_live(&self) -> impl Iterator<Item = CellLocation> + '_ {
+        (0..self.dimensions.h).flat_map(move |r| self.live_in_row(r))
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn par_iter_cells(&self) -> impl rayon::iter::ParallelIterator<Item = Cell> + '_ {
+        use rayon::prelude::*;
+        (0..self.dimensions.h)
+            .into_par_iter()
+            .flat_map_iter(move |r| self.row(r))
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn par_iter_rows(
+        &self,
+    ) -> impl rayon::iter::IndexedParallelIterator<Item = impl Iterator<Item = Cell> + Send + '_> + '_
+    {
+        use rayon::prelude::*;
+        (0..self.dimensions.h)
+            .into_par_iter()
+            .map(move |r| self.row(r))
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn par_iter_live(&self) -> impl rayon::iter::ParallelIterator<Item = CellLocation> + '_ {
+        use rayon::prelude::*;
+        (0..self.dimensions.h)
+            .into_par_iter()
+            .flat_map_iter(move |r| self.live_in_row(r))
+    }
+
+    fn row(&self, r: i32) -> impl Iterator<Item = Cell> + Send + '_ {
+        (0..self.dimensions.w).map(move |c| {
+            let location = CellLocation { r, c };
+            Cell {
+                state: self.state(&location).unwrap_or_default(),
+                location,
+            }
+        })
+    }
+
+    fn live_in_row(&self, r: i32) -> impl Iterator<Item = CellLocation> + Send + '_ {
+        let start = r as usize * self.words_per_row;
+        self.bits[start..start + self.words_per_row]
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let bit = word.trailing_zeros() as i32;
+                    word &= word - 1;
+                    Some(CellLocation {
+                        r,
+                        c: i as i32 * 64 + bit,
+                    })
+                })
+            })
+    }
+
+    fn contains(&self, location: &CellLocation) -> bool {
+        location.r >= 0
+            && location.c >= 0
+            && location.r < self.dimensions.h
+            && location.c < self.dimensions.w
+    }
+
+    fn bit(&self, r: i32, c: i32) -> (usize, u64) {
+        let (word, mask) = column_bit(c);
+        (r as usize * self.words_per_row + word, mask)
+    }
+
+    fn is_alive(&self, r: i32, c: i32) -> bool {
+        let (word, mask) = self.bit(r, c);
+        self.bits[word] & mask != 0
+    }
+
+    fn has_wire(&self, r: i32, c: i32) -> bool {
+        let (word, mask) = self.bit(r, c);
+        self.wires.get(word).is_some_and(|w| w & mask != 0)
+    }
+
+    fn set_alive(&mut self, r: i32, c: i32, alive: bool) {
+        let (word, mask) = self.bit(r, c);
+        if alive {
+            self.bits[word] |= mask;
+        } else {
+            self.bits[word] &= !mask;
+        }
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.encode())
+    }
+
+    pub fn load(path: &std::path::Path) -> std::io::Result<LifeBoard> {
+        let bytes = std::fs::read(path)?;
+        LifeBoard::decode(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let rule = format!("{}{}", self.rule, self.neighbourhood.suffix());
+        let mut bytes = SAVE_MAGIC.to_vec();
+        bytes.extend_from_slice(&(self.dimensions.w as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.dimensions.h as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.iteration as u64).to_le_bytes());
+        bytes.push(match self.topology {
+            Topology::Bounded => 0,
+            Topology::Torus => 1,
+            Topology::Infinite => 2,
+        });
+        bytes.push(rule.len() as u8);
+        bytes.extend_from_slice(rule.as_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.decay);
+        // Added after the rest so older saves still load, each starting with
+        // a byte saying what it is
+        if self.colonies > 1 {
+            bytes.push(self.colonies);
+            bytes.extend_from_slice(&self.colony);
+        }
+        if self.is_wired() {
+            bytes.push(WIRES_SECTION);
+            for word in &self.wires {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        if !self.ants.is_empty() {
+            bytes.push(ANTS_SECTION);
+            bytes.extend_from_slice(&(self.ants.len() as u32).to_le_bytes());
+            for ant in &self.ants {
+                bytes.extend_from_slice(&(ant.location.r as u32).to_le_bytes());
+                bytes.extend_from_slice(&(ant.location.c as u32).to_le_bytes());
+                bytes.push(ant.heading as u8);
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<LifeBoard, String> {
+        let mut reader = ByteReader { bytes, offset: 0 };
+
+        if reader.take(SAVE_MAGIC.len())? != SAVE_MAGIC {
+            return Err("Not a saved board".to_string());
+        }
+        let w = reader.u32()?;
+        let h = reader.u32()?;
+        if w == 0 || h == 0 || w > MAX_SAVED_EDGE || h > MAX_SAVED_EDGE {
+            return Err(format!("Board size {}x{} is not supported", w, h));
+        }
+        let iteration = reader.u64()? as usize;
+        let topology = match reader.take(1)?[0] {
+            0 => Topology::Bounded,
+            1 => Topology::Torus,
+            2 => Topology::Infinite,
+            t => {
+                return Err(format!(
+                    "Unknown topology {} at byte {}",
+                    t,
+                    reader.offset - 1
+                ))
+            }
+        };
+        let rule_len = reader.take(1)?[0] as usize;
+        let rule = std::str::from_utf8(reader.take(rule_len)?)
+            .map_err(|_| "Rule is not valid text".to_string())?;
+        let (rule, neighbourhood) = match rule.strip_suffix('V') {
+            Some(rule) => (rule, Neighbourhood::VonNeumann),
+            None => match rule.strip_suffix('H') {
+                Some(rule) => (rule, Neighbourhood::Hexagonal),
+                None => (rule, Neighbourhood::Moore),
+            },
+        };
+        let rule: Rule = rule.parse()?;
+
+        let t = Term {
+            w: w as i32,
+            h: h as i32,
+        };
+        // Checked before anything's allocated, so a header claiming a huge
+        // board with nothing after it is turned away
+        let too_big = || format!("Board size {}x{} is not supported", w, h);
+        let area = (w as usize).checked_mul(h as usize).ok_or_else(too_big)?;
+        let words = (w as usize)
+            .div_ceil(64)
+            .checked_mul(h as usize)
+            .ok_or_else(too_big)?;
+        let decay = if rule.is_generations() { area } else { 0 };
+        let cells = words
+            .checked_mul(8)
+            .and_then(|bytes| bytes.checked_add(decay))
+            .ok_or_else(too_big)?;
+        if reader.bytes.len() - reader.offset < cells {
+            return Err(format!(
+                "Expected {} bytes of cells for a {} board but found {}",
+                cells,
+                t,
+                reader.bytes.len() - reader.offset
+            ));
+        }
+
+        let mut board = LifeBoard::empty(t)
+            .with_topology(topology)
+            .with_neighbourhood(neighbourhood)
+            .with_rule(rule);
+        board.iteration = iteration;
+        for word in board.bits.iter_mut() {
+            *word = reader.u64()?;
+        }
+        let last_stage = board.rule.states().saturating_sub(2);
+        for (stage, byte) in board.decay.iter_mut().zip(reader.take(decay)?) {
+            *stage = (*byte).min(last_stage);
+        }
+        while reader.offset < reader.bytes.len() {
+            match reader.take(1)?[0] {
+                WIRES_SECTION => {
+                    board = board.with_wires();
+                    for word in board.wires.iter_mut() {
+                        *word = reader.u64()?;
+                    }
+                }
+                ANTS_SECTION => {
+                    let mut ants = Vec::new();
+                    for _ in 0..reader.u32()? {
+                        let location = CellLocation {
+                            r: reader.u32()? as i32,
+                            c: reader.u32()? as i32,
+                        };
+                        let heading = reader.take(1)?[0];
+                        let heading = Heading::from_index(heading)
+                            .ok_or_else(|| format!("Unknown heading {}", heading))?;
+                        ants.push(Ant { location, heading });
+                    }
+                    board = board.with_ants(ants);
+                }
+                colonies @ 2..=MAX_COLONIES => {
+                    board = board.with_colonies(colonies);
+                    for (colony, byte) in board.colony.iter_mut().zip(reader.take(area)?) {
+                        *colony = (*byte).min(colonies - 1);
+                    }
+                }
+                section => {
+                    return Err(format!(
+                        "Unknown section {} at byte {}",
+                        section,
+                        reader.offset - 1
+                    ))
+                }
+            }
+        }
+
+        let spare = board.words_per_row * 64 - board.dimensions.w as usize;
+        if spare > 0 {
+            let last_word = u64::MAX >> spare;
+            for row in board.bits.chunks_mut(board.words_per_row) {
+                row[row.len() - 1] &= last_word;
+            }
+            for row in board.wires.chunks_mut(board.words_per_row) {
+                row[row.len() - 1] &= last_word;
+            }
+        }
+        Ok(board)
+    }
+
+    // Keeping every cell's history and age is a pass over the whole board
+    // each generation, so they're only kept while something's drawn from
+    // them. Turning it on starts them again, turning it off drops them.
+    pub fn track_history(&mut self, on: bool) {
+        if on == self.history {
+            return;
+        }
+        let area = if on {
+            (self.dimensions.w * self.dimensions.h) as usize
+        } else {
+            0
+        };
+        self.history = on;
+        self.cell_history = vec![0; area];
+        self.ages = vec![0; area];
+        self.recorded = 0;
+    }
+
+    pub fn is_tracking_history(&self) -> bool {
+        self.history
+    }
+
+    // Shifts every cell's current state into its history while it's being
+    // tracked. step() does this itself, other engines writing into the
+    // board should call it once per generation.
+    pub fn record_history(&mut self) {
+        if !self.history {
+            return;
+        }
+
+        let w = self.dimensions.w;
+        for r in 0..self.dimensions.h {
+            for c in 0..w {
+                let alive = self.is_alive(r, c) as u32;
+                let i = (r * w + c) as usize;
+                let history = &mut self.cell_history[i];
+                *history = *history << 1 | alive;
+                let age = &mut self.ages[i];
+                *age = if alive == 1 { age.saturating_add(1) } else { 0 };
+            }
+        }
+        self.recorded = (self.recorded + 1).min(HISTORY_BITS);
+    }
+
+    // 0 for a dead cell or one that hasn't been alive for a whole
+    // generation yet, e.g. one painted on since the last step
+    pub fn age(&self, location: &CellLocation) -> u16 {
+        if !self.contains(location) || !self.is_alive(location.r, location.c) {
+            return 0;
+        }
+        self.ages
+            .get((location.r * self.dimensions.w + location.c) as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Whether each cell, in row order, has repeated with a period of at
+    // most k for the last 2k generations. Nothing counts as periodic until
+    // that many generations have been recorded, and k is capped at half of
+    // the history kept.
+    pub fn locally_periodic(&self, k: usize) -> Vec<bool> {
+        let k = k.clamp(1, HISTORY_BITS / 2);
+        if self.recorded < 2 * k {
+            return vec![false; (self.dimensions.w * self.dimensions.h) as usize];
+        }
+
+        self.cell_history
+            .iter()
+            .map(|history| {
+                (1..=k).any(|p| {
+                    let mask = (1u64 << (2 * k - p)) - 1;
+                    let repeats = (*history ^ (*history >> p)) as u64;
+                    repeats & mask == 0
+                })
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        for word in self.bits.iter_mut() {
+            *word = 0;
+        }
+        for stage in self.decay.iter_mut() {
+            *stage = 0;
+        }
+        for word in self.wires.iter_mut() {
+            *word = 0;
+        }
+        for colony in self.colony.iter_mut() {
+            *colony = 0;
+        }
+    }
+
+    // How far resized moves the cells. Hexagonal rows only move by two so
+    // they keep their stagger.
+    pub fn resize_offset(&self, size: &Term, anchor: Anchor) -> CellLocation {
+        let mut shift = anchor.offset(&self.dimensions, size);
+        if self.neighbourhood == Neighbourhood::Hexagonal {
+            shift.r -= shift.r.rem_euclid(2);
+        }
+        shift
+    }
+
+    // The same board at a new size, its cells kept where anchor says and
+    // dropped if they no longer fit. The ages and history behind them start
+    // again.
+    pub fn resized(&self, size: Term, anchor: Anchor) -> LifeBoard {
+        let shift = self.resize_offset(&size, anchor);
+        self.moved(size, |location| location + &shift, |heading| heading)
+    }
+
+    // The same board turned or flipped, its cells, conductors, colonies and
+    // ants all going together. As with resizing the ages and history start
+    // again. A hexagonal board's rows are staggered, so only a square grid
+    // keeps each cell's neighbours through a quarter turn.
+    pub fn rotate90(&self) -> LifeBoard {
+        let h = self.dimensions.h;
+        self.moved(
+            Term {
+                w: h,
+                h: self.dimensions.w,
+            },
+            |l| CellLocation {
+                r: l.c,
+                c: h - 1 - l.r,
+            },
+            Heading::right,
+        )
+    }
+
+    pub fn rotate180(&self) -> LifeBoard {
+        let (w, h) = (self.dimensions.w, self.dimensions.h);
+        self.moved(
+            self.dimensions.clone(),
+            |l| CellLocation {
+                r: h - 1 - l.r,
+                c: w - 1 - l.c,
+            },
+            |heading| heading.right().right(),
+        )
+    }
+
+    pub fn rotate270(&self) -> LifeBoard {
+        let w = self.dimensions.w;
+        self.moved(
+            Term {
+                w: self.dimensions.h,
+                h: w,
+            },
+            |l| CellLocation {
+                r: w - 1 - l.c,
+                c: l.r,
+            },
+            Heading::left,
+        )
+    }
+
+    pub fn flip_horizontal(&self) -> LifeBoard {
+        let w = self.dimensions.w;
+        self.moved(
+            self.dimensions.clone(),
+            |l| CellLocation {
+                r: l.r,
+                c: w - 1 - l.c,
+            },
+            |heading| match heading {
+                Heading::East => Heading::West,
+                Heading::West => Heading::East,
+                _ => heading,
+            },
+        )
+    }
+
+    pub fn flip_vertical(&self) -> LifeBoard {
+        let h = self.dimensions.h;
+        self.moved(
+            self.dimensions.clone(),
+            |l| CellLocation {
+                r: h - 1 - l.r,
+                c: l.c,
+            },
+            |heading| match heading {
+                Heading::North => Heading::South,
+                Heading::South => Heading::North,
+                _ => heading,
+            },
+        )
+    }
+
+    // A board of the given size with everything on this one moved to where
+    // location says, and anything that lands off it dropped
+    fn moved(
+        &self,
+        size: Term,
+        location: impl Fn(&CellLocation) -> CellLocation,
+        heading: impl Fn(Heading) -> Heading,
+    ) -> LifeBoard {
+        let mut board = LifeBoard::empty(size)
+            .with_topology(self.topology)
+            .with_neighbourhood(self.neighbourhood)
+            .with_rule(self.rule.clone())
+            .with_colonies(self.colonies);
+        if self.is_wired() {
+            board = board.with_wires();
+        }
+        board.iteration = self.iteration;
+        board.track_history(self.history);
+        for cell in self.iter_cells() {
+            let to = location(&cell.location);
+            board.set_state(&to, cell.state);
+            if let Some(colony) = self.colony(&cell.location) {
+                board.set_colony(&to, colony);
+            }
+        }
+        let ants = self
+            .ants
+            .iter()
+            .map(|ant| Ant {
+                location: location(&ant.location),
+                heading: heading(ant.heading),
+            })
+            .collect();
+        board.with_ants(ants)
+    }
+
+    // Every live cell dies and every other one comes alive, conductors
+    // stay where they are
+    pub fn invert(&mut self) {
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                let is_alive = self.is_alive(r, c);
+                self.set_alive(r, c, !is_alive);
+            }
+        }
+        for stage in self.decay.iter_mut() {
+            *stage = 0;
+        }
+        for colony in self.colony.iter_mut() {
+            *colony = 0;
+        }
+    }
+
+    // Puts a pattern on the board with its top left at the given cell.
+    // None, with nothing changed, if it's rejected for not fitting.
+    pub fn stamp(
+        &mut self,
+        pattern: &Pattern,
+        at: CellLocation,
+        mode: StampMode,
+        edges: Edges,
+    ) -> Option<CellLocation> {
+        let (w, h) = (self.dimensions.w, self.dimensions.h);
+        let fits =
+            at.c >= 0 && at.r >= 0 && at.c + pattern.width <= w && at.r + pattern.height <= h;
+        if !fits && edges == Edges::Reject {
+            return None;
+        }
+        let place = |cell: &CellLocation| {
+            let to = &at + cell;
+            match edges {
+                Edges::Wrap => Some(CellLocation {
+                    r: to.r.rem_euclid(h),
+                    c: to.c.rem_euclid(w),
+                }),
+                Edges::Clip | Edges::Reject => {
+                    Some(to).filter(|l| (0..h).contains(&l.r) && (0..w).contains(&l.c))
+                }
+            }
+        };
+
+        if mode == StampMode::Overwrite {
+            for r in 0..pattern.height {
+                for c in 0..pattern.width {
+                    if let Some(to) = place(&CellLocation { r, c }) {
+                        self.set_state(&to, CellState::Dead);
+                    }
+                }
+            }
+        }
+        for (cell, state) in pattern.states() {
+            if let Some(to) = place(cell) {
+                let state = match mode {
+                    StampMode::Xor if self.state(&to).as_ref() == Some(&state) => CellState::Dead,
+                    _ => state,
+                };
+                self.set_state(&to, state);
+            }
+        }
+        Some(at)
+    }
+
+    // The cells between two corners, inclusive and either way round, as a
+    // pattern that stamp puts back the same. The part off the
+    // board is left out. Only live cells come with it, and on a wired board
+    // the conductors and electron tails.
+    pub fn copy_region(&self, corner: &CellLocation, other: &CellLocation) -> Pattern {
+        let top = corner.r.min(other.r).max(0);
+        let left = corner.c.min(other.c).max(0);
+        let bottom = corner.r.max(other.r).min(self.dimensions.h - 1);
+        let right = corner.c.max(other.c).min(self.dimensions.w - 1);
+        let mut pattern = Pattern {
+            width: (right - left + 1).max(0),
+            height: (bottom - top + 1).max(0),
+            rule: Some(self.rule.clone()),
+            ..Pattern::default()
+        };
+        for r in top..=bottom {
+            for c in left..=right {
+                let cell = CellLocation {
+                    r: r - top,
+                    c: c - left,
+                };
+                match self.get(r, c) {
+                    Some(CellState::Alive) => pattern.cells.push(cell),
+                    Some(CellState::Dying(_)) if self.is_wired() => pattern.tails.push(cell),
+                    Some(CellState::Wire) => pattern.wires.push(cell),
+                    _ => {}
+                }
+            }
+        }
+        pattern
+    }
+
+    // Somewhere a width x height pattern fits with at least a cell of
+    // dead space around it, giving up after attempts tries
+    pub fn find_empty_region<R: Rng>(
+        &self,
+        rng: &mut R,
+        width: i32,
+        height: i32,
+        attempts: usize,
+    ) -> Option<CellLocation> {
+        if width > self.dimensions.w || height > self.dimensions.h {
+            return None;
+        }
+
+        (0..attempts)
+            .map(|_| CellLocation {
+                r: rng.gen_range(0, self.dimensions.h - height + 1),
+                c: rng.gen_range(0, self.dimensions.w - width + 1),
+            })
+            .find(|top_left| {
+                (top_left.r - 1..=top_left.r + height).all(|r| {
+                    (top_left.c - 1..=top_left.c + width).all(|c| {
+                        let location = CellLocation { r, c };
+                        !self.contains(&location) || !self.is_alive(r, c)
+                    })
+                })
+            })
+    }
+
+    pub fn get_random_location(&self) -> CellLocation {
         let mut rng = rand::thread_rng();
         CellLocation {
             r: rng.gen_range(0, self.dimensions.h),
             c: rng.gen_range(0, self.dimensions.w),
         }
     }
-    #[allow(unused)]
-    fn insert_glider(&mut self, top_left: CellLocation) -> Option<CellLocation> {
-        let glider_width = 3;
-        let glider_height = 3;
-        if top_left.c + glider_width >= self.dimensions.w
-            || top_left.r + glider_height >= self.dimensions.h
-        {
-            return None;
+
+    fn randomise(&mut self) {
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                let state: CellState = thread_rng().gen();
+                self.set_alive(r, c, state == CellState::Alive);
+            }
         }
+    }
 
-        self.cells[top_left.r as usize][top_left.c as usize + 1].state = CellState::Alive;
-        self.cells[top_left.r as usize + 1][top_left.c as usize + 2].state = CellState::Alive;
-        self.cells[top_left.r as usize + 2][top_left.c as usize].state = CellState::Alive;
-        self.cells[top_left.r as usize + 2][top_left.c as usize + 1].state = CellState::Alive;
-        self.cells[top_left.r as usize + 2][top_left.c as usize + 2].state = CellState::Alive;
-        Some(top_left)
+    // density is the chance of each cell starting alive, the same seed and
+    // density always give the same board. Dying cells and wires are cleared.
+    pub fn randomize_with_seed(&mut self, seed: u64, density: f64) {
+        self.fill_random(&mut StdRng::seed_from_u64(seed), density);
     }
 
-    fn get_relative_cell(&self, from: &Cell, dir: Direction) -> Option<&Cell> {
-        let delta = CellLocation::from(dir);
-        let next_location = from.location.clone() + delta;
-        if next_location.c < 0 || next_location.r < 0 {
-            return None;
+    // A fresh random board, the same as randomize_with_seed with a seed no
+    // one picked
+    pub fn randomize(&mut self, density: f64) {
+        self.fill_random(&mut thread_rng(), density);
+    }
+
+    pub(crate) fn fill_random<R: Rng>(&mut self, rng: &mut R, density: f64) {
+        self.clear();
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                self.set_alive(r, c, rng.gen_bool(density));
+            }
+        }
+        for i in 0..self.colony.len() {
+            let (r, c) = (i as i32 / self.dimensions.w, i as i32 % self.dimensions.w);
+            self.colony[i] = if self.is_alive(r, c) {
+                rng.gen_range(0, self.colonies)
+            } else {
+                0
+            };
         }
+    }
 
-        if next_location.c >= self.dimensions.w || next_location.r >= self.dimensions.h {
-            return None;
+    // The row at r, wrapped onto the board on a torus, otherwise None if
+    // it's off the board. Works the same for columns given the width.
+    fn wrap(&self, r: i32, edge: i32) -> Option<i32> {
+        if (0..edge).contains(&r) {
+            Some(r)
+        } else if self.topology == Topology::Torus {
+            Some(r.rem_euclid(edge))
+        } else {
+            None
         }
+    }
 
-        Some(&self.cells[next_location.r as usize][next_location.c as usize])
+    #[cfg(feature = "simd")]
+    fn process_row(&self, r: i32, row: &mut [u64]) {
+        if self.neighbourhood != Neighbourhood::Moore {
+            return self.process_row_offsets(r, row);
+        }
+        self.process_row_words(r, row);
     }
 
-    fn assign_locations(&mut self) {
-        for (r_idx, row) in self.cells.iter_mut().enumerate() {
-            for (c_idx, cell) in row.iter_mut().enumerate() {
-                cell.location = CellLocation {
-                    r: r_idx as i32,
-                    c: c_idx as i32,
-                };
+    #[cfg(not(feature = "simd"))]
+    fn process_row(&self, r: i32, row: &mut [u64]) {
+        if self.neighbourhood != Neighbourhood::Moore {
+            return self.process_row_offsets(r, row);
+        }
+        self.process_row_cells(r, row);
+    }
 
-                cell.state = thread_rng().gen();
+    // Row r's word i with everything shifted a column, so each cell's bit
+    // holds its neighbour to the west or east. Columns off the edge of a
+    // torus come round from the other side, and the bits past the last
+    // column are always clear so they read as dead.
+    #[cfg(any(feature = "simd", test))]
+    fn shifted_words(&self, r: i32, i: usize) -> (u64, u64) {
+        let words = &self.bits[r as usize * self.words_per_row..][..self.words_per_row];
+        let last = self.words_per_row - 1;
+        let mut west = words[i] << 1 | if i > 0 { words[i - 1] >> 63 } else { 0 };
+        let mut east = words[i] >> 1 | if i < last { words[i + 1] << 63 } else { 0 };
+        if self.topology == Topology::Torus {
+            let (w, c_last) = (self.dimensions.w, self.dimensions.w - 1);
+            if i == 0 && self.is_alive(r, c_last) {
+                west |= 1;
+            }
+            if i == last && self.is_alive(r, 0) {
+                east |= 1 << ((w - 1) % 64);
             }
         }
+        (west, east)
     }
 
-    fn count_neighbours(&self, c: &Cell) -> u8 {
-        let cells = vec![
-            self.get_relative_cell(c, Direction::TopLeft),
-            self.get_relative_cell(c, Direction::TopRight),
-            self.get_relative_cell(c, Direction::TopMiddle),
-            self.get_relative_cell(c, Direction::BottomLeft),
-            self.get_relative_cell(c, Direction::BottomRight),
-            self.get_relative_cell(c, Direction::BottomMiddle),
-            self.get_relative_cell(c, Direction::Left),
-            self.get_relative_cell(c, Direction::Right),
+    // The Moore neighbourhood counted 64 cells at a time. The eight
+    // neighbours of every cell in a word are added as bitsets, bit n of the
+    // count for each cell being in sums[n], then the rule picks the cells
+    // alive next from the counts.
+    #[cfg(any(feature = "simd", test))]
+    fn process_row_words(&self, r: i32, row: &mut [u64]) {
+        let rows = [
+            self.wrap(r - 1, self.dimensions.h),
+            Some(r),
+            self.wrap(r + 1, self.dimensions.h),
         ];
-        let somes: Vec<&Cell> = cells.into_iter().filter_map(|n| n).collect();
-        let alives: Vec<&Cell> = somes
-            .into_iter()
-            .filter(|n| n.state == CellState::Alive)
-            .collect();
-        alives.len() as u8
+        let born: Vec<u8> = (0..=8).filter(|n| self.rule.is_born(*n)).collect();
+        let survives: Vec<u8> = (0..=8).filter(|n| self.rule.survives(*n)).collect();
+        let tail_bits = self.dimensions.w as u32 % 64;
+        for (i, word) in row.iter_mut().enumerate() {
+            let mut sums = [0u64; 4];
+            let mut add = |mut carry: u64| {
+                for sum in sums.iter_mut() {
+                    let next_carry = *sum & carry;
+                    *sum ^= carry;
+                    carry = next_carry;
+                }
+            };
+            // The row above or below can be the cell's own on a small torus
+            for (k, neighbour_row) in rows.iter().enumerate() {
+                if let Some(neighbour_row) = neighbour_row {
+                    let (west, east) = self.shifted_words(*neighbour_row, i);
+                    add(west);
+                    add(east);
+                    if k != 1 {
+                        add(self.bits[*neighbour_row as usize * self.words_per_row + i]);
+                    }
+                }
+            }
+            let count = |n: u8| -> u64 {
+                sums.iter().enumerate().fold(!0, |cells, (bit, sum)| {
+                    cells & if n & (1 << bit) != 0 { *sum } else { !*sum }
+                })
+            };
+            let alive = self.bits[r as usize * self.words_per_row + i];
+            *word = born.iter().fold(0, |next, n| next | count(*n) & !alive)
+                | survives.iter().fold(0, |next, n| next | count(*n) & alive);
+            if i == self.words_per_row - 1 && tail_bits != 0 {
+                *word &= (1 << tail_bits) - 1;
+            }
+        }
     }
 
-    pub fn process(self) -> Self {
-        let mut next = self.clone();
-        for row in next.cells.iter_mut() {
-            for cell in row.iter_mut() {
-                let neighbours = self.count_neighbours(cell);
+    // Keeps a running sum of the three rows around r, column by column, so
+    // each cell only reads the column to its right
+    #[cfg_attr(feature = "simd", allow(dead_code))]
+    fn process_row_cells(&self, r: i32, row: &mut [u64]) {
+        let rows = [
+            self.wrap(r - 1, self.dimensions.h),
+            Some(r),
+            self.wrap(r + 1, self.dimensions.h),
+        ];
+        let column = |c: i32| -> u8 {
+            match self.wrap(c, self.dimensions.w) {
+                Some(c) => rows
+                    .iter()
+                    .flatten()
+                    .map(|r| self.is_alive(*r, c) as u8)
+                    .sum(),
+                None => 0,
+            }
+        };
+
+        let (mut left, mut middle) = (column(-1), column(0));
+        for c in 0..self.dimensions.w {
+            let right = column(c + 1);
+            let is_alive = self.is_alive(r, c);
+            let neighbours = left + middle + right - is_alive as u8;
 
-                match cell.state {
-                    CellState::Alive => {
-                        if neighbours != 2 && neighbours != 3 {
-                            cell.state = CellState::Dead;
-                        }
+            let alive = if is_alive {
+                self.rule.survives(neighbours)
+            } else {
+                self.rule.is_born(neighbours)
+            };
+
+            let (word, mask) = column_bit(c);
+            if alive {
+                row[word] |= mask;
+            } else {
+                row[word] &= !mask;
+            }
+            left = middle;
+            middle = right;
+        }
+    }
+
+    fn process_row_offsets(&self, r: i32, row: &mut [u64]) {
+        let offsets = self.neighbourhood.offsets(r);
+        for c in 0..self.dimensions.w {
+            let neighbours = offsets
+                .iter()
+                .filter(|(dr, dc)| {
+                    match (
+                        self.wrap(r + dr, self.dimensions.h),
+                        self.wrap(c + dc, self.dimensions.w),
+                    ) {
+                        (Some(nr), Some(nc)) => self.is_alive(nr, nc),
+                        _ => false,
                     }
-                    CellState::Dead => {
-                        if neighbours == 3 {
-                            cell.state = CellState::Alive;
+                })
+                .count() as u8;
+
+            let alive = if self.is_alive(r, c) {
+                self.rule.survives(neighbours)
+            } else {
+                self.rule.is_born(neighbours)
+            };
+            let (word, mask) = column_bit(c);
+            if alive {
+                row[word] |= mask;
+            } else {
+                row[word] &= !mask;
+            }
+        }
+    }
+
+    pub fn process(mut self) -> Self {
+        self.step();
+        self
+    }
+
+    // Moves the board on a generation in place. After the first call this
+    // doesn't allocate, the two buffers are swapped each generation.
+    pub fn step(&mut self) {
+        if self.ants.is_empty() {
+            self.step_cells();
+        } else {
+            self.move_ants();
+        }
+        self.iteration += 1;
+        self.record_history();
+    }
+
+    // Each generation after this one in turn, worked out as it's asked for,
+    // so board.generations().nth(99) is the board a hundred generations on.
+    // It never ends, even once the board has settled or died out. This
+    // board is left as it is.
+    pub fn generations(&self) -> impl Iterator<Item = LifeBoard> {
+        let mut board = self.clone();
+        std::iter::repeat_with(move || {
+            board.step();
+            board.clone()
+        })
+    }
+
+    // The same as step() but with automaton moving the cells on rather
+    // than the board's own rule
+    pub fn step_with<A: Automaton + ?Sized>(&mut self, automaton: &A) {
+        automaton.step(self);
+        self.iteration += 1;
+        self.record_history();
+    }
+
+    // Sets every cell to next of the board as it was before any of them
+    // changed
+    pub fn update_cells(&mut self, next: impl Fn(&LifeBoard, &CellLocation) -> CellState) {
+        let before = self.clone();
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                let location = CellLocation { r, c };
+                self.set_state(&location, next(&before, &location));
+            }
+        }
+    }
+
+    // The states of the cells around location in the board's neighbourhood,
+    // leaving out any off the edge of a bounded board
+    pub fn neighbours<'a>(
+        &'a self,
+        location: &CellLocation,
+    ) -> impl Iterator<Item = CellState> + 'a {
+        let (r, c) = (location.r, location.c);
+        self.neighbourhood
+            .offsets(r)
+            .iter()
+            .filter_map(move |(dr, dc)| {
+                let r = self.wrap(r + dr, self.dimensions.h)?;
+                let c = self.wrap(c + dc, self.dimensions.w)?;
+                self.get(r, c)
+            })
+    }
+
+    fn move_ants(&mut self) {
+        let mut ants = std::mem::take(&mut self.ants);
+        for ant in ants.iter_mut() {
+            let (r, c) = (ant.location.r, ant.location.c);
+            let alive = self.is_alive(r, c);
+            ant.heading = if alive {
+                ant.heading.left()
+            } else {
+                ant.heading.right()
+            };
+            self.set_alive(r, c, !alive);
+            let ahead = &ant.location + &ant.heading.offset();
+            match (
+                self.wrap(ahead.r, self.dimensions.h),
+                self.wrap(ahead.c, self.dimensions.w),
+            ) {
+                (Some(r), Some(c)) => ant.location = CellLocation { r, c },
+                // The edge of a bounded board turns it around
+                _ => ant.heading = ant.heading.left().left(),
+            }
+        }
+        self.ants = ants;
+    }
+
+    fn step_cells(&mut self) {
+        let mut next = std::mem::take(&mut self.scratch.0);
+        next.resize(self.bits.len(), 0);
+        self.process_rows(&mut next);
+        for (word, wires) in next.iter_mut().zip(&self.wires) {
+            *word &= wires;
+        }
+        if self.rule.is_generations() {
+            self.decay(&mut next);
+        }
+        if self.colonies > 1 {
+            self.inherit(&next);
+        }
+        std::mem::swap(&mut self.bits, &mut next);
+        self.scratch.0 = next;
+    }
+
+    // Cells that stopped surviving start dying, dying cells carry on
+    // decaying, and none of them can be born until they are dead
+    fn decay(&mut self, next: &mut [u64]) {
+        let last_stage = self.rule.states() - 2;
+        for r in 0..self.dimensions.h {
+            for c in 0..self.dimensions.w {
+                let (word, mask) = self.bit(r, c);
+                let i = self.index(&CellLocation { r, c });
+                let stage = &mut self.decay[i];
+                if *stage > 0 {
+                    next[word] &= !mask;
+                    *stage = if *stage < last_stage { *stage + 1 } else { 0 };
+                } else if self.bits[word] & mask != 0 && next[word] & mask == 0 {
+                    *stage = 1;
+                }
+            }
+        }
+    }
+
+    // Survivors stay in their colony and newborns take the most common
+    // colony of their parents, ties going to the lowest
+    fn inherit(&mut self, next: &[u64]) {
+        let w = self.dimensions.w;
+        let mut colony = std::mem::take(&mut self.colony);
+        let before = colony.clone();
+        for r in 0..self.dimensions.h {
+            for c in 0..w {
+                let (word, mask) = self.bit(r, c);
+                let i = (r * w + c) as usize;
+                colony[i] = match (self.bits[word] & mask != 0, next[word] & mask != 0) {
+                    (_, false) => 0,
+                    (true, true) => before[i],
+                    (false, true) => {
+                        let mut parents = [0; MAX_COLONIES as usize];
+                        for (dr, dc) in self.neighbourhood.offsets(r) {
+                            if let (Some(pr), Some(pc)) =
+                                (self.wrap(r + dr, self.dimensions.h), self.wrap(c + dc, w))
+                            {
+                                if self.is_alive(pr, pc) {
+                                    parents[before[(pr * w + pc) as usize] as usize] += 1;
+                                }
+                            }
                         }
+                        (0..self.colonies)
+                            .max_by_key(|k| (parents[*k as usize], std::cmp::Reverse(*k)))
+                            .unwrap_or(0)
+                    }
+                };
+            }
+        }
+        self.colony = colony;
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn process_rows(&self, next: &mut [u64]) {
+        for (r, row) in next.chunks_mut(self.words_per_row).enumerate() {
+            self.process_row(r as i32, row);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn process_rows(&self, next: &mut [u64]) {
+        use rayon::prelude::*;
+
+        if next.len() < PARALLEL_THRESHOLD_WORDS {
+            for (r, row) in next.chunks_mut(self.words_per_row).enumerate() {
+                self.process_row(r as i32, row);
+            }
+        } else {
+            next.par_chunks_mut(self.words_per_row)
+                .enumerate()
+                .for_each(|(r, row)| self.process_row(r as i32, row));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Boards are drawn a row per string, O for a live cell and . for a dead
+    // one
+    fn board(rows: &[&str], topology: Topology) -> LifeBoard {
+        let mut board = LifeBoard::empty(Term {
+            w: rows[0].len() as i32,
+            h: rows.len() as i32,
+        })
+        .with_topology(topology);
+        for (r, row) in rows.iter().enumerate() {
+            for (c, cell) in row.chars().enumerate() {
+                if cell == 'O' {
+                    let location = CellLocation {
+                        r: r as i32,
+                        c: c as i32,
+                    };
+                    board.set_state(&location, CellState::Alive);
+                }
+            }
+        }
+        board
+    }
+
+    fn picture(board: &LifeBoard) -> Vec<String> {
+        (0..board.height())
+            .map(|r| {
+                (0..board.width())
+                    .map(|c| if board.is_alive(r, c) { 'O' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn after(board: &LifeBoard, generations: usize) -> Vec<String> {
+        let mut board = board.clone();
+        for _ in 0..generations {
+            board.step();
+        }
+        picture(&board)
+    }
+
+    #[test]
+    fn block_is_still() {
+        let block = board(&["....", ".OO.", ".OO.", "...."], Topology::Bounded);
+        for generations in [1, 2, 4] {
+            assert_eq!(after(&block, generations), picture(&block));
+        }
+    }
+
+    #[test]
+    fn history_is_only_kept_once_asked_for() {
+        let mut blinker = board(
+            &[".....", ".....", ".OOO.", ".....", "....."],
+            Topology::Bounded,
+        );
+        let centre = CellLocation { r: 2, c: 2 };
+        for _ in 0..4 {
+            blinker.step();
+        }
+        assert!(blinker.cell_history.is_empty() && blinker.ages.is_empty());
+        assert_eq!(blinker.age(&centre), 0);
+        assert!(!blinker.locally_periodic(2).iter().any(|&settled| settled));
+
+        blinker.track_history(true);
+        for _ in 0..4 {
+            blinker.step();
+        }
+        assert_eq!(blinker.age(&centre), 4);
+        assert!(blinker.locally_periodic(2).iter().all(|&settled| settled));
+
+        blinker.track_history(false);
+        assert!(blinker.cell_history.is_empty() && blinker.ages.is_empty());
+    }
+
+    #[test]
+    fn blinker_flips_every_generation() {
+        let horizontal = [".....", ".....", ".OOO.", ".....", "....."];
+        let vertical = [".....", "..O..", "..O..", "..O..", "....."];
+        let blinker = board(&horizontal, Topology::Bounded);
+        assert_eq!(after(&blinker, 1), vertical);
+        assert_eq!(after(&blinker, 2), horizontal);
+        assert_eq!(after(&blinker, 4), horizontal);
+    }
+
+    #[test]
+    fn toad_has_period_two() {
+        let first = ["......", "......", "..OOO.", ".OOO..", "......", "......"];
+        let second = ["......", "...O..", ".O..O.", ".O..O.", "..O...", "......"];
+        let toad = board(&first, Topology::Bounded);
+        assert_eq!(after(&toad, 1), second);
+        assert_eq!(after(&toad, 2), first);
+        assert_eq!(after(&toad, 4), first);
+    }
+
+    #[test]
+    fn glider_moves_diagonally() {
+        let glider = board(
+            &[".O....", "..O...", "OOO...", "......", "......", "......"],
+            Topology::Bounded,
+        );
+        assert_eq!(
+            after(&glider, 1),
+            ["......", "O.O...", ".OO...", ".O....", "......", "......"]
+        );
+        assert_eq!(
+            after(&glider, 2),
+            ["......", "..O...", "O.O...", ".OO...", "......", "......"]
+        );
+        assert_eq!(
+            after(&glider, 4),
+            ["......", "..O...", "...O..", ".OOO..", "......", "......"]
+        );
+    }
+
+    #[test]
+    fn cells_past_a_bounded_edge_are_dead() {
+        let blinker = board(&[".OOO.", ".....", ".....", "....."], Topology::Bounded);
+        assert_eq!(after(&blinker, 1), ["..O..", "..O..", ".....", "....."]);
+        assert_eq!(after(&blinker, 2), [".....", ".....", ".....", "....."]);
+        assert_eq!(after(&blinker, 4), [".....", ".....", ".....", "....."]);
+    }
+
+    #[test]
+    fn glider_stops_in_a_bounded_corner() {
+        let glider = board(
+            &[".O....", "..O...", "OOO...", "......", "......", "......"],
+            Topology::Bounded,
+        );
+        let block = ["......", "......", "......", "......", "....OO", "....OO"];
+        assert_eq!(
+            after(&glider, 12),
+            ["......", "......", "......", "....O.", ".....O", "...OOO"]
+        );
+        assert_eq!(after(&glider, 16), block);
+        assert_eq!(after(&glider, 20), block);
+    }
+
+    #[test]
+    fn torus_wraps_round_the_edges() {
+        let blinker = board(&[".OOO.", ".....", ".....", "....."], Topology::Torus);
+        assert_eq!(after(&blinker, 1), ["..O..", "..O..", ".....", "..O.."]);
+        assert_eq!(after(&blinker, 2), [".OOO.", ".....", ".....", "....."]);
+        assert_eq!(after(&blinker, 4), [".OOO.", ".....", ".....", "....."]);
+    }
+
+    #[test]
+    fn glider_crosses_a_torus_corner() {
+        let glider = board(
+            &["......", "......", "......", "....O.", ".....O", "...OOO"],
+            Topology::Torus,
+        );
+        assert_eq!(
+            after(&glider, 4),
+            ["O...OO", "......", "......", "......", ".....O", "O....."]
+        );
+        // Six cells across in 24 generations brings it back where it was
+        assert_eq!(after(&glider, 24), picture(&glider));
+    }
+
+    #[test]
+    fn copied_region_pastes_back_the_same() {
+        let glider = board(
+            &["......", "..O...", "...O..", ".OOO..", "......"],
+            Topology::Bounded,
+        );
+        let pattern =
+            glider.copy_region(&CellLocation { r: 3, c: 3 }, &CellLocation { r: 1, c: 1 });
+        assert_eq!(
+            (pattern.width, pattern.height, pattern.cells.len()),
+            (3, 3, 5)
+        );
+        let mut copy = board(&["......"; 5], Topology::Bounded);
+        assert_eq!(
+            copy.stamp(
+                &pattern,
+                CellLocation { r: 1, c: 1 },
+                StampMode::Or,
+                Edges::Reject
+            ),
+            Some(CellLocation { r: 1, c: 1 })
+        );
+        assert_eq!(picture(&copy), picture(&glider));
+
+        let corner = glider.copy_region(&CellLocation { r: 3, c: 5 }, &CellLocation { r: 9, c: 9 });
+        assert_eq!((corner.width, corner.height), (1, 2));
+        assert!(corner.cells.is_empty());
+    }
+
+    #[test]
+    fn resizing_keeps_the_cells_by_the_anchor() {
+        let board = board(&["O..", ".O.", "..O"], Topology::Bounded);
+        let grown = board.resized(Term { w: 5, h: 4 }, Anchor::Centre);
+        assert_eq!(picture(&grown), [".O...", "..O..", "...O.", "....."]);
+        let shrunk = grown.resized(Term { w: 2, h: 2 }, Anchor::BottomRight);
+        assert_eq!(picture(&shrunk), ["O.", ".."]);
+        let shrunk = grown.resized(Term { w: 3, h: 2 }, Anchor::TopLeft);
+        assert_eq!(picture(&shrunk), [".O.", "..O"]);
+        // Shrinking back round the centre undoes growing
+        assert_eq!(
+            picture(&grown.resized(Term { w: 3, h: 3 }, Anchor::Centre)),
+            picture(&board)
+        );
+    }
+
+    #[test]
+    fn transforms_compose_and_commute_with_stepping() {
+        let board = board(&["OOO.", "O...", "OO.."], Topology::Torus);
+        assert_eq!(picture(&board.rotate90()), ["OOO", "O.O", "..O", "..."]);
+        assert_eq!(picture(&board.flip_vertical()), ["OO..", "O...", "OOO."]);
+        assert_eq!(
+            picture(&board.rotate90().rotate90().rotate90().rotate90()),
+            picture(&board)
+        );
+        assert_eq!(
+            picture(&board.rotate90().rotate180()),
+            picture(&board.rotate270())
+        );
+        assert_eq!(
+            picture(&board.flip_horizontal().flip_vertical()),
+            picture(&board.rotate180())
+        );
+        assert_eq!(
+            picture(&board.flip_horizontal().rotate90()),
+            picture(&board.rotate90().flip_vertical())
+        );
+        // Life doesn't care which way up the board is
+        let mut turned = board.rotate90();
+        turned.step();
+        let mut stepped = board.clone();
+        stepped.step();
+        assert_eq!(picture(&turned), picture(&stepped.rotate90()));
+    }
+
+    #[test]
+    fn stamping_combines_and_handles_the_edges_as_asked() {
+        // With a dead row under it for overwriting to clear
+        let blinker = Pattern {
+            width: 3,
+            height: 2,
+            cells: (0..3).map(|c| CellLocation { r: 0, c }).collect(),
+            ..Pattern::default()
+        };
+        let under = board(&["....", ".OO.", ".O.O"], Topology::Bounded);
+        let at = CellLocation { r: 1, c: 0 };
+        let stamped = |mode, edges, at: &CellLocation| {
+            let mut board = under.clone();
+            let placed = board.stamp(&blinker, at.clone(), mode, edges);
+            (placed.is_some(), picture(&board))
+        };
+        assert_eq!(
+            stamped(StampMode::Or, Edges::Reject, &at),
+            (true, vec!["....".to_string(), "OOO.".into(), ".O.O".into()])
+        );
+        assert_eq!(
+            stamped(StampMode::Overwrite, Edges::Reject, &at),
+            (true, vec!["....".to_string(), "OOO.".into(), "...O".into()])
+        );
+        assert_eq!(
+            stamped(StampMode::Xor, Edges::Reject, &at),
+            (true, vec!["....".to_string(), "O...".into(), ".O.O".into()])
+        );
+
+        // Hanging off the right and bottom edges
+        let over = CellLocation { r: 2, c: 2 };
+        assert_eq!(
+            stamped(StampMode::Or, Edges::Reject, &over),
+            (false, picture(&under))
+        );
+        assert_eq!(
+            stamped(StampMode::Overwrite, Edges::Clip, &over),
+            (true, vec!["....".to_string(), ".OO.".into(), ".OOO".into()])
+        );
+        assert_eq!(
+            stamped(StampMode::Overwrite, Edges::Wrap, &over),
+            (true, vec!["....".to_string(), ".OO.".into(), "OOOO".into()])
+        );
+    }
+
+    #[test]
+    fn the_iterators_see_the_same_cells() {
+        // Wider than a word, so rows span several
+        let mut board = LifeBoard::from(Term { w: 150, h: 7 });
+        board.randomize_with_seed(5, 0.3);
+        let live: Vec<CellLocation> = board
+            .iter_cells()
+            .filter(|c| c.state == CellState::Alive)
+            .map(|c| c.location)
+            .collect();
+        assert_eq!(board.iter_live().collect::<Vec<_>>(), live);
+        assert_eq!(live.len(), board.population());
+        let rows: Vec<Vec<Cell>> = board.iter_rows().map(|row| row.collect()).collect();
+        assert_eq!(rows.len(), 7);
+        assert!(rows.iter().all(|row| row.len() == 150));
+        let states = |cells: Vec<Cell>| -> Vec<_> {
+            cells.into_iter().map(|c| (c.location, c.state)).collect()
+        };
+        assert_eq!(states(rows.concat()), states(board.iter_cells().collect()));
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            assert_eq!(board.par_iter_live().collect::<Vec<_>>(), live);
+            assert_eq!(board.par_iter_cells().count(), 150 * 7);
+            assert_eq!(board.par_iter_rows().count(), 7);
+        }
+    }
+
+    #[test]
+    fn cells_are_got_and_set_within_the_bounds() {
+        let mut board = board(&["O..", "..."], Topology::Bounded);
+        assert_eq!(board.get(0, 0), Some(CellState::Alive));
+        assert_eq!(board.get(1, 2), Some(CellState::Dead));
+        assert_eq!(board.get(2, 0), None);
+        assert_eq!(board.get(0, -1), None);
+        assert_eq!(board.set(1, 2, CellState::Alive), Ok(()));
+        assert_eq!(
+            board.set(1, 3, CellState::Alive),
+            Err(OutOfBounds {
+                location: CellLocation { r: 1, c: 3 },
+                width: 3,
+                height: 2
+            })
+        );
+        assert_eq!(picture(&board), ["O..", "..O"]);
+        assert_eq!(board[CellLocation { r: 1, c: 2 }], CellState::Alive);
+        assert_eq!(board[&CellLocation { r: 0, c: 1 }], CellState::Dead);
+
+        let mut brain = board
+            .clone()
+            .with_rule("B2/S/C3".parse().expect("Unable to parse Brian's Brain"));
+        brain
+            .set(0, 1, CellState::Dying(1))
+            .expect("The cell is on the board");
+        assert_eq!(brain[CellLocation { r: 0, c: 1 }], CellState::Dying(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't on the 3x2 board")]
+    fn indexing_off_the_board_panics() {
+        let board = board(&["...", "..."], Topology::Bounded);
+        let _ = &board[CellLocation { r: 2, c: 0 }];
+    }
+
+    #[test]
+    fn a_huge_saved_board_with_no_cells_is_rejected() {
+        let mut header = SAVE_MAGIC.to_vec();
+        header.extend_from_slice(&MAX_SAVED_EDGE.to_le_bytes());
+        header.extend_from_slice(&MAX_SAVED_EDGE.to_le_bytes());
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.push(0);
+        header.push(9);
+        header.extend_from_slice(b"B3/S23/C3");
+        let error = LifeBoard::decode(&header)
+            .err()
+            .expect("The board was loaded");
+        assert!(error.starts_with("Expected"), "{}", error);
+    }
+
+    #[test]
+    fn generations_follow_one_another() {
+        let glider = board(
+            &["......", "..O...", "...O..", ".OOO..", "......"],
+            Topology::Torus,
+        );
+        let hundredth = glider.generations().take(100).last();
+        assert_eq!(hundredth.map(|b| picture(&b)), Some(after(&glider, 100)));
+        assert_eq!(glider.iteration(), 0);
+
+        // A turned board's generations are the turned generations
+        let turned = glider.rotate90();
+        assert!(glider
+            .generations()
+            .zip(turned.generations())
+            .take(20)
+            .all(|(a, b)| picture(&a.rotate90()) == picture(&b)));
+    }
+
+    #[test]
+    fn inverting_swaps_the_living_and_the_dead() {
+        let mut board = board(&["O...", ".OO.", "...O"], Topology::Bounded);
+        board.invert();
+        assert_eq!(picture(&board), [".OOO", "O..O", "OOO."]);
+        board.clear();
+        assert_eq!(board.population(), 0);
+        board.randomize(1.0);
+        assert_eq!(board.population(), 12);
+    }
+
+    #[test]
+    fn randomizing_by_seed_clears_the_board_first() {
+        let dying = CellLocation { r: 1, c: 1 };
+        let mut seeded = LifeBoard::from(Term { w: 4, h: 3 })
+            .with_rule("B2/S/C3".parse().expect("Unable to parse Brian's Brain"));
+        let mut unseeded = seeded.clone();
+        for board in [&mut seeded, &mut unseeded] {
+            board.clear();
+            board.set_state(&dying, CellState::Dying(1));
+            assert_eq!(board[&dying], CellState::Dying(1));
+        }
+        seeded.randomize_with_seed(3, 0.0);
+        unseeded.randomize(0.0);
+        assert_eq!(seeded[&dying], CellState::Dead);
+        assert_eq!(seeded.encode(), unseeded.encode());
+
+        let wire = CellLocation { r: 0, c: 2 };
+        let mut circuit = LifeBoard::from(Term { w: 4, h: 3 })
+            .with_rule(Rule::wireworld())
+            .with_wires();
+        circuit.clear();
+        circuit.set_state(&wire, CellState::Wire);
+        assert_eq!(circuit[&wire], CellState::Wire);
+        circuit.randomize_with_seed(3, 0.0);
+        assert_eq!(circuit[&wire], CellState::Dead);
+    }
+
+    #[test]
+    fn changed_cells_are_the_ones_whose_state_changed() {
+        for rule in ["B3/S23", "B2/S/C3"] {
+            let mut board = LifeBoard::empty(Term { w: 150, h: 40 })
+                .with_rule(rule.parse().expect("Unable to parse the rule"));
+            board.randomize_with_seed(7, 0.3);
+            for _ in 0..3 {
+                let previous = board.clone();
+                board.step();
+                let expected: Vec<CellLocation> = board
+                    .iter_cells()
+                    .filter(|cell| previous.state(&cell.location).as_ref() != Some(&cell.state))
+                    .map(|cell| cell.location)
+                    .collect();
+                assert!(!expected.is_empty());
+                assert_eq!(
+                    board.changed_cells(&previous).collect::<Vec<_>>(),
+                    expected,
+                    "{}",
+                    rule
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn counting_by_the_word_matches_counting_by_the_cell() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for w in [1, 2, 63, 64, 65, 130] {
+            for h in [1, 2, 5] {
+                for topology in [Topology::Bounded, Topology::Torus] {
+                    let mut board = LifeBoard::empty(Term { w, h })
+                        .with_topology(topology)
+                        .with_rule(random_rule(&mut rng));
+                    board.randomize_with_seed(rng.gen(), 0.4);
+                    for r in 0..h {
+                        let mut by_cell = vec![0; board.words_per_row];
+                        let mut by_word = vec![0; board.words_per_row];
+                        board.process_row_cells(r, &mut by_cell);
+                        board.process_row_words(r, &mut by_word);
+                        assert_eq!(
+                            by_word,
+                            by_cell,
+                            "Row {} of a {}x{} {} board with {}",
+                            r,
+                            w,
+                            h,
+                            topology,
+                            board.rule()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // The property tests below each try CASES random boards, seeded by the
+    // case number so a failure can be reproduced from the message
+    const CASES: u64 = 200;
+
+    fn random_rule(rng: &mut StdRng) -> Rule {
+        let born: String = (1..=8)
+            .filter(|_| rng.gen_bool(0.5))
+            .map(|n| n.to_string())
+            .collect();
+        let survives: String = (0..=8)
+            .filter(|_| rng.gen_bool(0.5))
+            .map(|n| n.to_string())
+            .collect();
+        format!("B{}/S{}", born, survives)
+            .parse()
+            .expect("Unable to parse a random rule")
+    }
+
+    #[test]
+    fn dead_boards_stay_dead() {
+        let neighbourhoods = [
+            Neighbourhood::Moore,
+            Neighbourhood::VonNeumann,
+            Neighbourhood::Hexagonal,
+        ];
+        let topologies = [Topology::Bounded, Topology::Torus];
+        for case in 0..CASES {
+            let mut rng = StdRng::seed_from_u64(case);
+            let rule = random_rule(&mut rng);
+            let mut board = LifeBoard::empty(Term {
+                w: rng.gen_range(1, 70),
+                h: rng.gen_range(1, 30),
+            })
+            .with_topology(topologies[rng.gen_range(0, topologies.len())])
+            .with_neighbourhood(neighbourhoods[rng.gen_range(0, neighbourhoods.len())])
+            .with_rule(rule.clone());
+            for _ in 0..10 {
+                board.step();
+            }
+            assert_eq!(board.population(), 0, "case {} with {}", case, rule);
+        }
+    }
+
+    const STILL_LIFES: [&[&str]; 5] = [
+        &["OO", "OO"],
+        &[".OO.", "O..O", ".OO."],
+        &[".OO.", "O..O", ".O.O", "..O."],
+        &["OO.", "O.O", ".O."],
+        &[".O.", "O.O", ".O."],
+    ];
+    // Room for the biggest still life with two dead cells all round, so
+    // neighbouring ones never touch
+    const SLOT: i32 = 6;
+
+    #[test]
+    fn still_lifes_are_fixed_points() {
+        for case in 0..CASES {
+            let mut rng = StdRng::seed_from_u64(case);
+            let (across, down) = (rng.gen_range(1, 8), rng.gen_range(1, 8));
+            let topology = if rng.gen_bool(0.5) {
+                Topology::Bounded
+            } else {
+                Topology::Torus
+            };
+            let mut board = LifeBoard::empty(Term {
+                w: across * SLOT,
+                h: down * SLOT,
+            })
+            .with_topology(topology);
+            for slot in 0..across * down {
+                if rng.gen_bool(0.2) {
+                    continue;
+                }
+                let rows = STILL_LIFES[rng.gen_range(0, STILL_LIFES.len())];
+                for (r, row) in rows.iter().enumerate() {
+                    for (c, _) in row.chars().enumerate().filter(|(_, cell)| *cell == 'O') {
+                        let location = CellLocation {
+                            r: slot / across * SLOT + 2 + r as i32,
+                            c: slot % across * SLOT + 2 + c as i32,
+                        };
+                        board.set_state(&location, CellState::Alive);
+                    }
+                }
+            }
+            let start = picture(&board);
+            for generation in 1..=4 {
+                board.step();
+                assert_eq!(
+                    picture(&board),
+                    start,
+                    "case {} generation {}",
+                    case,
+                    generation
+                );
+            }
+        }
+    }
+
+    // Where a cell goes under each symmetry of a w by h torus. Swapping
+    // rows and columns is only tried on square boards.
+    fn reflect(symmetry: usize, w: i32, h: i32, r: i32, c: i32) -> (i32, i32) {
+        match symmetry {
+            0 => (r, w - 1 - c),
+            1 => (h - 1 - r, c),
+            2 => (h - 1 - r, w - 1 - c),
+            _ => (c, r),
+        }
+    }
+
+    #[test]
+    fn torus_keeps_symmetric_boards_symmetric() {
+        for case in 0..CASES {
+            let mut rng = StdRng::seed_from_u64(case);
+            let symmetry = rng.gen_range(0, 4);
+            let (w, h) = if symmetry == 3 {
+                let edge = rng.gen_range(3, 40);
+                (edge, edge)
+            } else {
+                (rng.gen_range(3, 70), rng.gen_range(3, 40))
+            };
+            let density = rng.gen_range(0.1, 0.6);
+            let mut board = LifeBoard::empty(Term { w, h }).with_topology(Topology::Torus);
+            for r in 0..h {
+                for c in 0..w {
+                    if rng.gen_bool(density) {
+                        let (rr, rc) = reflect(symmetry, w, h, r, c);
+                        board.set_state(&CellLocation { r, c }, CellState::Alive);
+                        board.set_state(&CellLocation { r: rr, c: rc }, CellState::Alive);
+                    }
+                }
+            }
+            for generation in 0..=20 {
+                for r in 0..h {
+                    for c in 0..w {
+                        let (rr, rc) = reflect(symmetry, w, h, r, c);
+                        assert_eq!(
+                            board.is_alive(r, c),
+                            board.is_alive(rr, rc),
+                            "case {} symmetry {} generation {} at {},{}",
+                            case,
+                            symmetry,
+                            generation,
+                            r,
+                            c
+                        );
                     }
                 }
+                board.step();
             }
         }
-        next
     }
 }
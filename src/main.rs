@@ -4,74 +4,1103 @@ Any Dead cell with three live neighbours becomes a live cell.
 All other live cells die in the next generation. Similarly, all other Dead cells stay Dead.
 */
 
+#[cfg(feature = "tui")]
+use std::collections;
 use std::fmt;
+use std::io::Write;
+#[cfg(feature = "sdl")]
+use std::sync::mpsc;
+use std::sync::Mutex;
 use std::thread;
-use std::{collections, time};
+use std::time;
 
-mod cells;
-mod life;
-use cells::*;
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
+use gol::export::{self, MeshSettings, TileIds};
+use gol::repro::{self, Edit};
+use gol::settings::{new_board, random_board};
+#[cfg(any(feature = "sdl", feature = "tui"))]
+use gol::Comparison;
+#[cfg(all(feature = "snapshot", any(feature = "sdl", feature = "tui")))]
+use gol::Compositor;
+#[cfg(feature = "gpu")]
+use gol::GpuLife;
+#[cfg(feature = "tui")]
+use gol::Heading;
+#[cfg(any(feature = "sdl", feature = "tui"))]
+use gol::{age_intensity, wire_colour, Action, CellState, Colony, ColorScheme, Keymap, ANT_COLOUR};
+#[cfg(feature = "sdl")]
+use gol::{font, Camera, Cell, MIN_GRID_CELL};
+use gol::{
+    keymap, verify, Anchor, Ca, Catalog, CatalogEntry, CellLocation, Config, Cycle, Engine, Fade,
+    GUISettings, GameOfLifeError, Grid, HashLife, LifeBoard, Neighbourhood, Paths, Pattern, Preset,
+    Recogniser, Reference, Renderer, Repro, ReproEnd, Rule, Simulation, Soup, SoupSearch,
+    SparseLife, Stereo, Term, Theme, Topology, Tracks, Universe, MAX_COLONIES, MAX_HISTORY,
+};
+use rand::Rng;
 
-use clap::{App, Arg};
-use life::*;
+const MIN_SPEED: f32 = 0.5;
+const MAX_SPEED: f32 = 500.0;
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const SPEED_STEP: f32 = 1.5;
+const MAX_STEPS_PER_FRAME: usize = 16;
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const SETTLED_PERIOD: usize = 4;
+const BUSY_ACTIVITY: f32 = 0.05;
+const GOVERNOR_EASING: f32 = 0.1;
+const MAX_ANTS: usize = 16;
+const MAX_HEATMAP: usize = 10_000;
+const MAX_SKIP: usize = 1_000_000;
+// Cells that have been alive at all are never drawn as dark as the
+// background
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const COLDEST_HEAT: f32 = 0.15;
+// The arrow keys move the view of an infinite board by a quarter of it
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const VIEW_STEPS: i32 = 4;
+const TERMINAL_FRAME: time::Duration = time::Duration::from_millis(50);
+#[cfg(feature = "tui")]
+const TUI_FRAME: time::Duration = time::Duration::from_millis(33);
+#[cfg(feature = "sdl")]
+const SDL_FRAME: time::Duration = time::Duration::from_millis(16);
+#[cfg(feature = "sdl")]
+const ZOOM_STEP: f32 = 1.25;
+#[cfg(feature = "sdl")]
+const PAN_FRACTION: f32 = 0.1;
+#[cfg(feature = "sdl")]
+const HUD_SCALE: u32 = 2;
+// SDL's grey, the same as the stamp being placed
+#[cfg(feature = "sdl")]
+const SETTLED_COLOUR: [u8; 3] = [128, 128, 128];
+#[cfg(feature = "sdl")]
+const SELECTION_COLOUR: [u8; 3] = [255, 200, 0];
+// How far ahead of a ship its arrow reaches
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const VECTOR_GENERATIONS: f32 = 16.0;
+#[cfg(any(feature = "sdl", feature = "tui"))]
+const TRACK_COLOUR: [u8; 3] = [0, 200, 255];
 
-struct GUISettings {
-    history_length: usize,
-    cell_edge: i32,
-    board_height: i32,
-    board_width: i32,
+static CRASH_REPORT: Mutex<Option<CrashReport>> = Mutex::new(None);
+
+struct CrashReport {
+    settings: String,
+    board: Option<LifeBoard>,
+}
+
+fn install_panic_hook(settings: &GUISettings) {
+    *CRASH_REPORT
+        .lock()
+        .expect("Unable to lock the crash report") = Some(CrashReport {
+        settings: settings.to_string(),
+        board: None,
+    });
+
+    let crash_dir = settings.paths.crash_dir();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_crash_report(&crash_dir, &info.to_string()) {
+            Ok(dir) => eprintln!("Diagnostics saved to {}", dir.display()),
+            Err(e) => eprintln!("Unable to save diagnostics: {}", e),
+        }
+    }));
 }
 
-impl Default for GUISettings {
-    fn default() -> Self {
-        GUISettings {
-            history_length: 4,
-            cell_edge: 10,
-            board_height: 50,
-            board_width: 100,
+fn record_crash_board(board: &LifeBoard) {
+    if let Ok(mut report) = CRASH_REPORT.lock() {
+        if let Some(report) = report.as_mut() {
+            report.board = Some(board.clone());
         }
     }
 }
 
-impl fmt::Display for GUISettings {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "History Length = {}, Cell Edge = {}, Board Height = {}, Board Width = {}",
-            self.history_length, self.cell_edge, self.board_height, self.board_width
-        )
+fn write_crash_report(
+    crash_dir: &std::path::Path,
+    message: &str,
+) -> std::io::Result<std::path::PathBuf> {
+    let dir = Paths::timestamped(crash_dir, "gol-crash", "")?;
+    std::fs::create_dir_all(&dir)?;
+
+    let backtrace = std::backtrace::Backtrace::force_capture();
+    std::fs::write(
+        dir.join("panic.txt"),
+        format!("{}\n\n{}", message, backtrace),
+    )?;
+
+    // try_lock as the panic may have happened while the report was held
+    if let Ok(report) = CRASH_REPORT.try_lock() {
+        if let Some(report) = report.as_ref() {
+            std::fs::write(dir.join("config.txt"), format!("{}\n", report.settings))?;
+            if let Some(board) = &report.board {
+                board.save(&dir.join("board.gol"))?;
+            }
+        }
     }
+    Ok(dir)
 }
 
-fn show_cell(
-    r: &mut sdl2::render::Canvas<sdl2::video::Window>,
+// The colour of a cell that isn't dead in one of the history's boards,
+// age being how far through the history the board is
+#[cfg(feature = "sdl")]
+fn cell_colour(
+    sim: &Simulation,
+    board: &LifeBoard,
     cell: &Cell,
-    intensity: f32,
-    settings: &GUISettings,
-) {
-    let tl_x = cell.location.c * settings.cell_edge;
-    let tl_y = cell.location.r * settings.cell_edge;
-    let colour_intensity = (255.0 * intensity) as u8;
-    let g_b_intensity = if colour_intensity != 255 {
-        colour_intensity
+    is_latest: bool,
+    age: f32,
+) -> gol::compositor::Rgb {
+    if board.is_wired() {
+        return wire_colour(&cell.state);
+    }
+    let intensity = if is_latest && sim.by_age && cell.state == CellState::Alive {
+        age_intensity(board.age(&cell.location))
     } else {
-        0
+        age * board.rule().brightness(&cell.state)
     };
-    let colour = match cell.state {
-        CellState::Alive => {
-            sdl2::pixels::Color::RGB(colour_intensity, g_b_intensity, g_b_intensity)
+    match board
+        .colony(&cell.location)
+        .filter(|_| is_latest && board.colonies() > 1)
+    {
+        Some(colony) => Colony(colony).colour(intensity),
+        None => sim.cell_colours().colour(intensity),
+    }
+}
+
+// Cold red for cells hardly ever alive to white for ones always alive
+#[cfg(any(feature = "sdl", feature = "tui"))]
+fn heat_colour(heat: f32) -> gol::compositor::Rgb {
+    Fade::Heatmap.colour(COLDEST_HEAT + (1.0 - COLDEST_HEAT) * heat)
+}
+
+// Every cell that's drawn with its colour, the oldest board in the history
+// first so the newer boards are drawn over their trails. The heatmap is
+// drawn instead of all of them while it's on.
+#[cfg(feature = "sdl")]
+fn for_each_drawn_cell(
+    sim: &Simulation,
+    settled: Option<&[bool]>,
+    mut draw: impl FnMut(&CellLocation, gol::compositor::Rgb) -> Result<(), GameOfLifeError>,
+) -> Result<(), GameOfLifeError> {
+    if let Some(heatmap) = &sim.heatmap {
+        for (location, heat) in heatmap.cells() {
+            draw(&location, heat_colour(heat))?;
+        }
+        return Ok(());
+    }
+
+    let history = &sim.history;
+    let age_incr = 1.0 / (history.len() as f32);
+    let mut age = age_incr;
+    for (i, board) in history.iter().enumerate() {
+        let is_latest = i + 1 == history.len();
+        if board.is_wired() && !is_latest {
+            continue;
+        }
+        for cell in board
+            .iter_cells()
+            .filter(|cell| cell.state != CellState::Dead)
+        {
+            draw(
+                &cell.location,
+                cell_colour(sim, board, &cell, is_latest, age),
+            )?;
+        }
+        age += age_incr;
+    }
+
+    if let (Some(settled), Some(board)) = (settled, history.back()) {
+        for (cell, is_settled) in board.iter_cells().zip(settled) {
+            if *is_settled && cell.state == CellState::Alive {
+                draw(&cell.location, SETTLED_COLOUR)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The board drawn one pixel per cell and stretched over the window when
+// it's copied, so a frame is a single copy however many cells are alive.
+// It's made again whenever the board changes size.
+#[cfg(feature = "sdl")]
+struct BoardTexture<'a> {
+    creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    texture: Option<(sdl2::render::Texture<'a>, u32, u32)>,
+    pixels: Vec<u8>,
+    // With no trails, ageing or settled cells each cell's colour only
+    // depends on its own state, so the last board drawn and the colours it
+    // was drawn in are kept and only the cells that have changed since are
+    // drawn again
+    drawn: Option<(LifeBoard, Theme, Option<Fade>)>,
+}
+
+#[cfg(feature = "sdl")]
+impl<'a> BoardTexture<'a> {
+    fn new(creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>) -> Self {
+        BoardTexture {
+            creator,
+            texture: None,
+            pixels: Vec::new(),
+            drawn: None,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+        sim: &Simulation,
+        camera: &Camera,
+        settled: Option<&[bool]>,
+    ) -> Result<(), GameOfLifeError> {
+        let (width, height) = (sim.board.width() as u32, sim.board.height() as u32);
+        if !matches!(self.texture, Some((_, w, h)) if (w, h) == (width, height)) {
+            let texture = self
+                .creator
+                .create_texture_streaming(sdl2::pixels::PixelFormatEnum::RGB24, width, height)
+                .map_err(GameOfLifeError::sdl("create the board texture"))?;
+            self.texture = Some((texture, width, height));
+            self.drawn = None;
+        }
+
+        let pitch = width as usize * 3;
+        let latest = sim.history.back().unwrap_or(&sim.board);
+        let is_incremental =
+            sim.history.len() == 1 && !sim.by_age && settled.is_none() && sim.heatmap.is_none();
+        let changed = match &self.drawn.take() {
+            Some((drawn, theme, fade))
+                if is_incremental && (*theme, *fade) == (sim.theme, sim.fade) =>
+            {
+                self.redraw_changes(sim, latest, drawn)
+            }
+            _ => {
+                self.redraw_all(sim, settled)?;
+                Some(sdl2::rect::Rect::new(0, 0, width, height))
+            }
+        };
+        self.drawn = if is_incremental {
+            Some((latest.clone(), sim.theme, sim.fade))
+        } else {
+            None
+        };
+
+        let texture = &mut self.texture.as_mut().expect("The texture was just made").0;
+        if let Some(changed) = changed {
+            let start = changed.y() as usize * pitch + changed.x() as usize * 3;
+            texture
+                .update(changed, &self.pixels[start..], pitch)
+                .map_err(GameOfLifeError::sdl("update the board texture"))?;
+        }
+        let (left, top) = camera.to_screen(&CellLocation { r: 0, c: 0 });
+        let (right, bottom) = camera.to_screen(&CellLocation {
+            r: sim.board.height(),
+            c: sim.board.width(),
+        });
+        canvas
+            .copy(
+                texture,
+                None,
+                sdl2::rect::Rect::new(left, top, (right - left) as u32, (bottom - top) as u32),
+            )
+            .map_err(GameOfLifeError::sdl("draw the board"))
+    }
+
+    fn redraw_all(
+        &mut self,
+        sim: &Simulation,
+        settled: Option<&[bool]>,
+    ) -> Result<(), GameOfLifeError> {
+        let pitch = sim.board.width() as usize * 3;
+        self.pixels.clear();
+        self.pixels.extend(
+            sim.theme
+                .background
+                .iter()
+                .copied()
+                .cycle()
+                .take(pitch * sim.board.height() as usize),
+        );
+        let pixels = &mut self.pixels;
+        for_each_drawn_cell(sim, settled, |location, colour| {
+            let i = location.r as usize * pitch + location.c as usize * 3;
+            pixels[i..i + 3].copy_from_slice(&colour);
+            Ok(())
+        })
+    }
+
+    // The area that needs uploading again, None if nothing changed
+    fn redraw_changes(
+        &mut self,
+        sim: &Simulation,
+        latest: &LifeBoard,
+        drawn: &LifeBoard,
+    ) -> Option<sdl2::rect::Rect> {
+        let pitch = latest.width() as usize * 3;
+        // Top, left, bottom and right of the changed cells
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+        for location in latest.changed_cells(drawn) {
+            let (r, c) = (location.r, location.c);
+            let state = latest.state(&location).unwrap_or_default();
+            let colour = if state == CellState::Dead {
+                sim.theme.background
+            } else {
+                let cell = Cell { state, location };
+                cell_colour(sim, latest, &cell, true, 1.0)
+            };
+            let i = r as usize * pitch + c as usize * 3;
+            self.pixels[i..i + 3].copy_from_slice(&colour);
+            bounds = Some(match bounds {
+                None => (r, c, r, c),
+                Some((top, left, bottom, right)) => {
+                    (top.min(r), left.min(c), bottom.max(r), right.max(c))
+                }
+            });
+        }
+        bounds.map(|(top, left, bottom, right)| {
+            sdl2::rect::Rect::new(
+                left,
+                top,
+                (right - left + 1) as u32,
+                (bottom - top + 1) as u32,
+            )
+        })
+    }
+
+    // Staggered hexagonal rows and gaps between the cells can't be drawn by
+    // stretching the texture, nor can boards bigger than the renderer allows
+    fn fits(
+        canvas: &sdl2::render::Canvas<sdl2::video::Window>,
+        sim: &Simulation,
+        camera: &Camera,
+        has_gap: bool,
+    ) -> bool {
+        let info = canvas.info();
+        !camera.offset_rows
+            && !has_gap
+            && sim.board.width() as u32 <= info.max_texture_width
+            && sim.board.height() as u32 <= info.max_texture_height
+    }
+}
+
+// The cells, grid lines and ants of one board
+#[cfg(feature = "sdl")]
+fn draw_board(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    board_texture: &mut BoardTexture,
+    sim: &Simulation,
+    camera: &Camera,
+    settled: Option<&[bool]>,
+    grid: Grid,
+) -> Result<(), GameOfLifeError> {
+    let is_gridded = camera.cell_size() >= MIN_GRID_CELL;
+    let has_gap = grid == Grid::Gap && is_gridded;
+    let edge = camera.cell_size() - if has_gap { 1 } else { 0 };
+    if BoardTexture::fits(canvas, sim, camera, has_gap) {
+        board_texture.draw(canvas, sim, camera, settled)?;
+    } else {
+        for_each_drawn_cell(sim, settled, |location, [red, green, blue]| {
+            let (x, y) = camera.to_screen(location);
+            canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+            canvas
+                .fill_rect(sdl2::rect::Rect::new(x, y, edge, edge))
+                .map_err(GameOfLifeError::sdl("draw a cell"))
+        })?;
+    }
+
+    if grid == Grid::Lines && is_gridded {
+        draw_grid_lines(canvas, camera, &sim.board, sim.theme.grid)?;
+    }
+
+    if let Some(board) = sim.history.back() {
+        let [red, green, blue] = ANT_COLOUR;
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+        for ant in board.ants() {
+            let (x, y) = camera.to_screen(&ant.location);
+            canvas
+                .fill_rect(sdl2::rect::Rect::new(x, y, edge, edge))
+                .map_err(GameOfLifeError::sdl("draw an ant"))?;
+        }
+    }
+    draw_tracks(canvas, sim, camera)
+}
+
+// Trails are lines through where each ship has been, vectors a line to
+// where it's going with an arrowhead on the end
+#[cfg(feature = "sdl")]
+fn draw_tracks(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    sim: &Simulation,
+    camera: &Camera,
+) -> Result<(), GameOfLifeError> {
+    let point = |(r, c): (f32, f32)| sdl2::rect::Point::from(camera.point_to_screen(r, c));
+    let [red, green, blue] = TRACK_COLOUR;
+    canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+    for track in sim.tracked_ships() {
+        match sim.tracks {
+            Tracks::Trails => {
+                let points: Vec<sdl2::rect::Point> =
+                    track.trail.iter().map(|p| point(*p)).collect();
+                canvas
+                    .draw_lines(&points[..])
+                    .map_err(GameOfLifeError::sdl("draw a trail"))?;
+            }
+            Tracks::Vectors => {
+                let (r, c) = track.position();
+                let (end_r, end_c) = track.ahead(VECTOR_GENERATIONS);
+                // The head's two sides are the line turned back on itself
+                // a little either way, a cell and a half long
+                let length = (end_r - r).hypot(end_c - c);
+                let (back_r, back_c) = ((r - end_r) / length * 1.5, (c - end_c) / length * 1.5);
+                let side = |turn: f32| {
+                    let (sin, cos) = turn.sin_cos();
+                    point((
+                        end_r + back_r * cos - back_c * sin,
+                        end_c + back_r * sin + back_c * cos,
+                    ))
+                };
+                let end = point((end_r, end_c));
+                for (from, to) in [(point((r, c)), end), (side(0.5), end), (side(-0.5), end)] {
+                    canvas
+                        .draw_line(from, to)
+                        .map_err(GameOfLifeError::sdl("draw a vector"))?;
+                }
+            }
+            Tracks::Off => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sdl")]
+#[allow(clippy::too_many_arguments)]
+fn render_frame(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    board_texture: &mut BoardTexture,
+    sim: &Simulation,
+    camera: &Camera,
+    hud: Option<&str>,
+    ghost: Option<(&Pattern, CellLocation)>,
+    selection: Option<&(CellLocation, CellLocation)>,
+    settled: Option<&[bool]>,
+    beside: Option<(&mut BoardTexture, &Simulation, Option<&[bool]>)>,
+    grid: Grid,
+) -> Result<(), GameOfLifeError> {
+    let [red, green, blue] = sim.theme.background;
+    canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+    canvas.clear();
+
+    draw_board(canvas, board_texture, sim, camera, settled, grid)?;
+    if let Some((texture, beside, settled)) = beside {
+        // A cell's gap to the right of the first board, wherever it's panned
+        let mut beside_camera = camera.clone();
+        beside_camera.x -= (sim.board.width() + 1) as f32;
+        draw_board(canvas, texture, beside, &beside_camera, settled, grid)?;
+    }
+
+    let has_gap = grid == Grid::Gap && camera.cell_size() >= MIN_GRID_CELL;
+    let edge = camera.cell_size() - if has_gap { 1 } else { 0 };
+    if let Some((pattern, top_left)) = ghost {
+        canvas.set_draw_color(sdl2::pixels::Color::GREY);
+        for cell in &pattern.cells {
+            let (x, y) = camera.to_screen(&(&top_left + cell));
+            canvas
+                .fill_rect(sdl2::rect::Rect::new(x, y, edge, edge))
+                .map_err(GameOfLifeError::sdl("draw the stamp"))?;
+        }
+    }
+
+    if let Some((corner, other)) = selection {
+        let (left, top) = camera.to_screen(&CellLocation {
+            r: corner.r.min(other.r),
+            c: corner.c.min(other.c),
+        });
+        let (right, bottom) = camera.to_screen(&CellLocation {
+            r: corner.r.max(other.r) + 1,
+            c: corner.c.max(other.c) + 1,
+        });
+        let [red, green, blue] = SELECTION_COLOUR;
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+        canvas
+            .draw_rect(sdl2::rect::Rect::new(
+                left,
+                top,
+                (right - left).max(1) as u32,
+                (bottom - top).max(1) as u32,
+            ))
+            .map_err(GameOfLifeError::sdl("draw the selection"))?;
+    }
+
+    if let Some(text) = hud {
+        draw_hud(canvas, text)?;
+    }
+    canvas.present();
+    Ok(())
+}
+
+// A line along every cell edge in the window, or just the board's for the
+// bounded topologies. Hexagonal rows are staggered so they only get the
+// lines between rows.
+#[cfg(feature = "sdl")]
+fn draw_grid_lines(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    camera: &Camera,
+    board: &LifeBoard,
+    [red, green, blue]: gol::compositor::Rgb,
+) -> Result<(), GameOfLifeError> {
+    let (width, height) = canvas
+        .output_size()
+        .map_err(GameOfLifeError::sdl("get the window size"))?;
+    let top_left = camera.to_board(0, 0);
+    let bottom_right = camera.to_board(width as i32, height as i32);
+    let (mut rows, mut cols) = (
+        (top_left.r, bottom_right.r + 1),
+        (top_left.c, bottom_right.c + 1),
+    );
+    if board.topology() != Topology::Infinite {
+        rows = (rows.0.max(0), rows.1.min(board.height()));
+        cols = (cols.0.max(0), cols.1.min(board.width()));
+    }
+    let corner = |r, c| camera.to_screen(&CellLocation { r, c });
+    let (left, top) = corner(rows.0, cols.0);
+    let (right, bottom) = corner(rows.1, cols.1);
+
+    canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+    for r in rows.0..=rows.1 {
+        let (_, y) = corner(r, cols.0);
+        canvas
+            .draw_line((left, y), (right, y))
+            .map_err(GameOfLifeError::sdl("draw the grid"))?;
+    }
+    if !camera.offset_rows {
+        for c in cols.0..=cols.1 {
+            let (x, _) = corner(rows.0, c);
+            canvas
+                .draw_line((x, top), (x, bottom))
+                .map_err(GameOfLifeError::sdl("draw the grid"))?;
+        }
+    }
+    Ok(())
+}
+
+// Stamps are placed centred on the cursor
+#[cfg(feature = "sdl")]
+fn stamp_origin(pattern: &Pattern, cursor: &CellLocation) -> CellLocation {
+    CellLocation {
+        r: cursor.r - pattern.height / 2,
+        c: cursor.c - pattern.width / 2,
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn palette_index(key: sdl2::keyboard::Keycode) -> Option<usize> {
+    use sdl2::keyboard::Keycode;
+    let keys = [
+        Keycode::Num1,
+        Keycode::Num2,
+        Keycode::Num3,
+        Keycode::Num4,
+        Keycode::Num5,
+        Keycode::Num6,
+        Keycode::Num7,
+        Keycode::Num8,
+        Keycode::Num9,
+    ];
+    keys.iter().position(|k| *k == key)
+}
+
+#[cfg(feature = "sdl")]
+fn draw_hud(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    text: &str,
+) -> Result<(), GameOfLifeError> {
+    let margin = HUD_SCALE as i32;
+    canvas.set_draw_color(sdl2::pixels::Color::BLACK);
+    canvas
+        .fill_rect(sdl2::rect::Rect::new(
+            0,
+            0,
+            (font::text_width(text) + 2 * margin) as u32 * HUD_SCALE,
+            (font::GLYPH_HEIGHT + 2 * margin) as u32 * HUD_SCALE,
+        ))
+        .map_err(GameOfLifeError::sdl("draw the HUD"))?;
+
+    canvas.set_draw_color(sdl2::pixels::Color::WHITE);
+    for (x, y) in font::text_pixels(text) {
+        canvas
+            .fill_rect(sdl2::rect::Rect::new(
+                (x + margin) * HUD_SCALE as i32,
+                (y + margin) * HUD_SCALE as i32,
+                HUD_SCALE,
+                HUD_SCALE,
+            ))
+            .map_err(GameOfLifeError::sdl("draw the HUD"))?;
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "sdl", feature = "tui"))]
+fn save_path(paths: &Paths) -> std::io::Result<std::path::PathBuf> {
+    Paths::timestamped(&paths.autosave_dir(), "gol", "gol")
+}
+
+// Drawn off screen so the image is the same whatever the window size or
+// camera
+#[cfg(all(feature = "snapshot", any(feature = "sdl", feature = "tui")))]
+fn snapshot(sim: &Simulation, settings: &GUISettings) -> std::io::Result<std::path::PathBuf> {
+    let path = Paths::timestamped(
+        &settings.paths.screenshot_dir(),
+        &format!("gol-{}", sim.generation),
+        "png",
+    )?;
+    let mut compositor = Compositor::default();
+    compositor.cell_size = settings.snapshot_cell_size;
+    compositor.stereo = settings.stereo;
+    compositor.background = sim.theme.background;
+    compositor.alive = sim.theme.alive;
+    compositor.fade = sim.fade;
+    compositor.by_age = sim.by_age;
+    let image = compositor.compose(&sim.history, sim.generation);
+    export::write_png(
+        &image,
+        std::io::BufWriter::new(std::fs::File::create(&path)?),
+    )?;
+    Ok(path)
+}
+
+// What the simulation gave up on, printed where nothing's drawing over the
+// terminal
+fn report_problems(sim: &mut Simulation) {
+    for problem in sim.take_problems() {
+        eprintln!("{}", problem);
+    }
+}
+
+fn run_headless(mut lb: LifeBoard, generations: usize) -> LifeBoard {
+    for _ in 0..generations {
+        lb.step();
+    }
+    lb
+}
+
+// Only the engine is timed
+fn run_bench(
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    generations: u64,
+) -> Result<(), GameOfLifeError> {
+    let mut sim = Simulation::new(first_board, settings)?;
+    let start = time::Instant::now();
+    let population = match &mut sim.universe {
+        Some(universe) => {
+            for _ in 0..generations {
+                universe.advance(1);
+            }
+            universe.population()
+        }
+        None => {
+            for _ in 0..generations {
+                sim.board.step();
+            }
+            sim.board.population() as u64
         }
-        CellState::Dead => return,
     };
+    let elapsed = start.elapsed().as_secs_f64();
 
-    r.set_draw_color(colour);
-    r.fill_rect(sdl2::rect::Rect::new(
-        tl_x,
-        tl_y,
-        settings.cell_edge as u32,
-        settings.cell_edge as u32,
-    ))
-    .expect("Unable to draw rectangle");
+    let cells = sim.board.width() as f64 * sim.board.height() as f64 * generations as f64;
+    println!(
+        "{} generations of a {}x{} {} board with the {} engine",
+        generations,
+        sim.board.width(),
+        sim.board.height(),
+        settings.topology,
+        settings.engine
+    );
+    println!("Wall time\t\t{:.3}s", elapsed);
+    println!("Generations/sec\t\t{:.1}", generations as f64 / elapsed);
+    println!("Cells updated/sec\t{:.0}", cells / elapsed);
+    println!("Final population\t{}", population);
+    Ok(())
+}
+
+// The fast engines stepped alongside the reference one and checked against
+// it after every generation, stopping at the first cell any of them gets
+// wrong. Engines that can't run the board are left out: the dense board
+// only sees its own edges so it can't run an infinite one, and HashLife can
+// only run an infinite one.
+fn run_verify(
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    generations: u64,
+) -> Result<(), GameOfLifeError> {
+    let mut reference = Reference::from_board(&first_board).map_err(GameOfLifeError::Engine)?;
+    let mut dense = Some(first_board.clone()).filter(|b| b.topology() != Topology::Infinite);
+    let mut universes = Vec::new();
+    match SparseLife::from_board(&first_board) {
+        Ok(universe) => universes.push((Engine::Sparse, Universe::Sparse(universe))),
+        Err(e) => println!("Not verifying the sparse engine: {}", e),
+    }
+    if first_board.topology() == Topology::Infinite {
+        match HashLife::from_board(&first_board) {
+            Ok(universe) => universes.push((Engine::HashLife, Universe::HashLife(universe))),
+            Err(e) => println!("Not verifying HashLife: {}", e),
+        }
+    }
+    #[cfg(feature = "gpu")]
+    match GpuLife::from_board(&first_board) {
+        Ok(universe) => universes.push((Engine::Gpu, Universe::Gpu(universe))),
+        Err(e) => println!("Not verifying the GPU engine: {}", e),
+    }
+    let dense_name = match (cfg!(feature = "parallel"), cfg!(feature = "simd")) {
+        (true, true) => "naive (parallel simd)",
+        (true, false) => "naive (parallel)",
+        (false, true) => "naive (simd)",
+        (false, false) => "naive",
+    };
+    let mut names: Vec<String> = universes.iter().map(|(e, _)| e.to_string()).collect();
+    if dense.is_some() {
+        names.insert(0, dense_name.to_string());
+    }
+    let engines = match names.split_last() {
+        None => {
+            return Err(GameOfLifeError::Engine(
+                "None of the engines can run this board".to_string(),
+            ))
+        }
+        Some((last, [])) => format!("the {} engine", last),
+        Some((last, rest)) => format!("the {} and {} engines", rest.join(", "), last),
+    };
+    println!(
+        "Verifying {} against the reference for {} generations of a {}x{} {} board",
+        engines,
+        generations,
+        first_board.width(),
+        first_board.height(),
+        settings.topology
+    );
+
+    for generation in 1..=generations {
+        reference.step();
+        let mut stepped = Vec::new();
+        if let Some(board) = &mut dense {
+            board.step();
+            let live = board.iter_live().collect();
+            stepped.push((dense_name.to_string(), live));
+        }
+        for (engine, universe) in universes.iter_mut() {
+            universe.advance(1);
+            stepped.push((engine.to_string(), universe.live_cells()));
+        }
+        for (name, live) in stepped {
+            if let Some((location, is_expected)) =
+                verify::first_difference(reference.live_cells(), &live)
+            {
+                return Err(GameOfLifeError::Engine(format!(
+                    "The {} engine parted from the reference at generation {}, it has {},{} {} where the reference has it {}",
+                    name,
+                    generation,
+                    location.r,
+                    location.c,
+                    if is_expected { "dead" } else { "alive" },
+                    if is_expected { "alive" } else { "dead" }
+                )));
+            }
+        }
+    }
+    println!(
+        "All {} generations matched, final population {}",
+        generations,
+        reference.live_cells().len()
+    );
+    Ok(())
+}
+
+// For scripts and golden tests, the board after exactly so many generations
+// with nothing else on stdout, or every generation as it's worked out for
+// another program to read. It runs until it's out of generations, it settles
+// and --stop-on-stable is on or whatever's reading the frames stops. An .rle
+// file gets the final board as a pattern, anything else the save format
+// --load reads.
+fn run_generations(
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    generations: Option<u64>,
+    frames: Option<FrameFormat>,
+    output: Option<&str>,
+    is_quiet: bool,
+) -> Result<(), GameOfLifeError> {
+    let mut sim = Simulation::new(first_board, settings)?;
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    loop {
+        if let Some(format) = frames {
+            match write_frame(&mut out, &sim, format).and_then(|_| out.flush()) {
+                // Whatever was reading has had all it wants
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                written => written.map_err(GameOfLifeError::io("write a frame"))?,
+            }
+        }
+        if generations.is_some_and(|n| sim.generation >= n) || sim.stable.is_some() {
+            break;
+        }
+        if let Some(chaos) = sim.advance_by(1, settings) {
+            if !is_quiet {
+                eprintln!("Chaos dropped {}", chaos);
+            }
+        }
+        report_problems(&mut sim);
+    }
+    sim.finish();
+    report_problems(&mut sim);
+    match output {
+        Some(path) if path.ends_with(".rle") => {
+            let mut pattern = sim.board.copy_region(
+                &CellLocation::default(),
+                &CellLocation {
+                    r: sim.board.height() - 1,
+                    c: sim.board.width() - 1,
+                },
+            );
+            pattern.name = Some(format!("Generation {}", sim.generation));
+            std::fs::write(path, pattern.to_rle())
+                .map_err(GameOfLifeError::io("write the final board"))?
+        }
+        Some(path) => sim
+            .board
+            .save(std::path::Path::new(path))
+            .map_err(GameOfLifeError::io("write the final board"))?,
+        // The last frame was the final board
+        None if frames.is_some() => {}
+        None => write!(out, "{}", sim.board)
+            .and_then(|_| out.flush())
+            .map_err(GameOfLifeError::io("print the final board"))?,
+    }
+    if !is_quiet {
+        eprintln!(
+            "Ran {} generations, final population {}",
+            sim.generation,
+            sim.board.population()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FrameFormat {
+    // A line with the generation, population and size, then the board as
+    // it prints, a row to a line
+    Text,
+    // An object per line with the live cells as [row, column] pairs
+    Json,
+}
+
+fn write_frame(out: &mut impl Write, sim: &Simulation, format: FrameFormat) -> std::io::Result<()> {
+    let board = &sim.board;
+    match format {
+        FrameFormat::Text => write!(
+            out,
+            "generation {} population {} {}x{}\n{}",
+            sim.generation,
+            board.population(),
+            board.width(),
+            board.height(),
+            board
+        ),
+        FrameFormat::Json => {
+            let cells: Vec<String> = board
+                .iter_live()
+                .map(|l| format!("[{},{}]", l.r, l.c))
+                .collect();
+            writeln!(
+                out,
+                "{{\"generation\":{},\"population\":{},\"width\":{},\"height\":{},\"cells\":[{}]}}",
+                sim.generation,
+                board.population(),
+                board.width(),
+                board.height(),
+                cells.join(",")
+            )
+        }
+    }
+}
+
+fn export_mesh(
+    path: &str,
+    lb: LifeBoard,
+    generations: usize,
+    mesh: &MeshSettings,
+) -> Result<(), GameOfLifeError> {
+    let lb = run_headless(lb, generations);
+    let mut file =
+        std::fs::File::create(path).map_err(GameOfLifeError::io("create the mesh file"))?;
+    if path.ends_with(".obj") {
+        export::write_obj(&lb, mesh, &mut file)
+    } else {
+        export::write_stl(&lb, mesh, &mut file)
+    }
+    .map_err(GameOfLifeError::io("write the mesh file"))
+}
+
+fn export_map(
+    path: &str,
+    lb: LifeBoard,
+    generations: usize,
+    ids: &TileIds,
+) -> Result<(), GameOfLifeError> {
+    let lb = run_headless(lb, generations);
+    let mut file =
+        std::fs::File::create(path).map_err(GameOfLifeError::io("create the map file"))?;
+    if path.ends_with(".json") {
+        export::write_tiled_json(&lb, ids, &mut file)
+    } else if path.ends_with(".tmx") {
+        export::write_tmx(&lb, ids, &mut file)
+    } else {
+        export::write_csv(&lb, ids, &mut file)
+    }
+    .map_err(GameOfLifeError::io("write the map file"))
+}
+
+// Steps the board on a thread of its own so a slow generation on a huge
+// board doesn't hold up the window. Each board comes back with the
+// fingerprint of the one it was stepped from, so generations of a board
+// that's been painted, rewound or had chaos dropped on it since it was sent
+// can be told apart and thrown away. HashLife and the sparse engine are
+// still stepped in place.
+#[cfg(feature = "sdl")]
+struct Stepper {
+    jobs: mpsc::Sender<(LifeBoard, usize)>,
+    done: mpsc::Receiver<(u64, LifeBoard, time::Duration)>,
+    in_flight: usize,
+    owed: usize,
+}
+
+#[cfg(feature = "sdl")]
+impl Stepper {
+    fn new() -> Stepper {
+        let (jobs, work) = mpsc::channel::<(LifeBoard, usize)>();
+        let (finished, done) = mpsc::channel();
+        thread::spawn(move || {
+            for (mut board, generations) in work {
+                for _ in 0..generations {
+                    let from = board.fingerprint();
+                    let start = time::Instant::now();
+                    board.step();
+                    if finished
+                        .send((from, board.clone(), start.elapsed()))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+        Stepper {
+            jobs,
+            done,
+            in_flight: 0,
+            owed: 0,
+        }
+    }
+
+    // Adds the generations finished since it was last called to sim, then
+    // sends off the ones owed if the thread is free. Returns how many were
+    // added and what chaos dropped on the way.
+    fn run(
+        &mut self,
+        sim: &mut Simulation,
+        steps: usize,
+        settings: &GUISettings,
+    ) -> (usize, Option<String>) {
+        if sim.universe.is_some() {
+            return (steps, sim.advance_by(steps, settings));
+        }
+        self.owed = (self.owed + steps).min(MAX_STEPS_PER_FRAME);
+        let mut added = 0;
+        let mut chaos = None;
+        for (from, board, step_time) in self.done.try_iter() {
+            self.in_flight -= 1;
+            if sim.stable.is_none() && from == sim.board.fingerprint() {
+                sim.advance_to(board, step_time);
+                chaos = sim.chaos_due(settings).or(chaos);
+                added += 1;
+            }
+        }
+        if self.in_flight == 0 && self.owed > 0 && sim.stable.is_none() {
+            if self.jobs.send((sim.board.clone(), self.owed)).is_ok() {
+                self.in_flight = self.owed;
+            }
+            self.owed = 0;
+        }
+        (added, chaos)
+    }
+}
+
+// Works out how many generations are due each frame so the simulation
+// runs at its own rate however fast frames are drawn
+struct Speed {
+    generations_per_second: f32,
+    owed: f32,
+    last_tick: time::Instant,
+    adaptive: Option<(f32, f32)>,
+}
+
+impl Speed {
+    fn new(generations_per_second: f32, adaptive: Option<(f32, f32)>) -> Speed {
+        Speed {
+            generations_per_second: generations_per_second.clamp(MIN_SPEED, MAX_SPEED),
+            owed: 0.0,
+            last_tick: time::Instant::now(),
+            adaptive,
+        }
+    }
+
+    // Changing the speed by hand turns off the adaptive speed
+    #[cfg(any(feature = "sdl", feature = "tui"))]
+    fn faster(&mut self) {
+        self.adaptive = None;
+        self.generations_per_second = (self.generations_per_second * SPEED_STEP).min(MAX_SPEED);
+    }
+
+    #[cfg(any(feature = "sdl", feature = "tui"))]
+    fn slower(&mut self) {
+        self.adaptive = None;
+        self.generations_per_second = (self.generations_per_second / SPEED_STEP).max(MIN_SPEED);
+    }
+
+    // Eases towards the slowest speed as activity approaches BUSY_ACTIVITY
+    // and towards the fastest as the board settles down
+    fn govern(&mut self, activity: f32) {
+        if let Some((slowest, fastest)) = self.adaptive {
+            let busyness = (activity / BUSY_ACTIVITY).clamp(0.0, 1.0);
+            let target = fastest - (fastest - slowest) * busyness;
+            self.generations_per_second += (target - self.generations_per_second) * GOVERNOR_EASING;
+        }
+    }
+
+    // Generations are owed at the configured rate however long each frame
+    // takes, so the speed doesn't follow the frame rate. Anything owed
+    // beyond MAX_STEPS_PER_FRAME is dropped, so a slow frame doesn't leave
+    // a backlog of generations to catch up on.
+    fn due(&mut self, is_running: bool) -> usize {
+        let now = time::Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f32();
+        self.last_tick = now;
+        if !is_running {
+            self.owed = 0.0;
+            return 0;
+        }
+
+        self.owed += elapsed * self.generations_per_second;
+        let steps = self.owed.floor();
+        self.owed -= steps;
+        (steps as usize).min(MAX_STEPS_PER_FRAME)
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.1} gen/s", self.generations_per_second)?;
+        if self.adaptive.is_some() {
+            write!(f, " auto")?;
+        }
+        Ok(())
+    }
 }
 
 fn check_limits<T: std::fmt::Display + std::cmp::PartialOrd + std::str::FromStr>(
@@ -106,7 +1135,14 @@ impl Logger {
 }
 
 fn main() {
-    let matches = App::new("Game of Life")
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn cli() -> App<'static, 'static> {
+    App::new("Game of Life")
         .version("1.0")
         .author("Patrick Mintram")
         .about("Conways Game Of Life")
@@ -114,8 +1150,24 @@ fn main() {
             Arg::with_name("history length")
                 .takes_value(true)
                 .long("history")
-                .help("How much fade behind the current shape")
-                .validator(|val| check_limits(&val, 1, 20)),
+                .help("How much fade behind the current shape, [ and ] change it while running")
+                .validator(|val| check_limits(&val, 1, MAX_HISTORY)),
+        )
+        .arg(
+            Arg::with_name("generations per second")
+                .takes_value(true)
+                .long("fps")
+                .help("How many generations to run each second, + and - change it while running")
+                .validator(|val| check_limits(&val, MIN_SPEED, MAX_SPEED)),
+        )
+        .arg(
+            Arg::with_name("adaptive speed")
+                .takes_value(true)
+                .long("adaptive-speed")
+                .number_of_values(2)
+                .value_names(&["slowest", "fastest"])
+                .help("Slow down while the board is busy and speed up as it settles, between these generations per second")
+                .validator(|val| check_limits(&val, MIN_SPEED, MAX_SPEED)),
         )
         .arg(
             Arg::with_name("cell edge length")
@@ -139,108 +1191,2181 @@ fn main() {
                 .validator(|val| check_limits(&val, 10, 200)),
         )
         .arg(
-            Arg::with_name("verbose")
-                .short("v")
-                .long("verbose")
-                .help("enable verbose logging"),
+            Arg::with_name("topology")
+                .takes_value(true)
+                .long("topology")
+                .help("Whether the board edges are walls or wrap around, or the board is a view onto an infinite plane")
+                .possible_values(&["bounded", "torus", "infinite"]),
         )
-        .get_matches();
-
-    let history_length = clap::value_t!(matches.value_of("history length"), usize).unwrap_or(4);
-    let cell_edge = clap::value_t!(matches.value_of("cell edge length"), i32).unwrap_or(10);
-    let board_height = clap::value_t!(matches.value_of("board height"), i32).unwrap_or(80);
-    let board_width = clap::value_t!(matches.value_of("board width"), i32).unwrap_or(100);
-    let is_verbose = matches.is_present("verbose");
-
-    let clog = Logger { is_verbose };
-
-    let settings = GUISettings {
-        history_length,
-        cell_edge,
-        board_width,
-        board_height,
-    };
-    clog.nice_to_know(&format!("Settings\t{}", &settings));
-
-    let sdl_context = sdl2::init().expect("Unable to initialise SDL2");
-    clog.nice_to_know("Initialised SDL2");
-    clog.nice_to_know(&format!("SDL2 Version = {}", sdl2::version::version()));
-
-    let vss = sdl_context
-        .video()
-        .expect("Unable to get a video context from SDL");
-    clog.nice_to_know("Initialised a video context");
-
-    let window = vss
-        .window(
-            "Game of Life",
-            (settings.board_width * settings.cell_edge) as u32,
-            (settings.board_height * settings.cell_edge) as u32,
+        .arg(
+            Arg::with_name("neighbourhood")
+                .takes_value(true)
+                .long("neighbourhood")
+                .help("Which cells count as neighbours, all eight around, the four orthogonal ones or the six of a hex grid")
+                .possible_values(&["moore", "von-neumann", "hexagonal"]),
         )
-        .position_centered()
-        .build()
-        .expect("Unable to create window");
-    clog.nice_to_know("Created a window");
-
-    let mut canvas = window
-        .into_canvas()
-        .build()
-        .expect("Unable to get a canvas");
-    clog.nice_to_know("Created a canvas");
-
-    canvas.set_draw_color(sdl2::pixels::Color::BLACK);
-    canvas.clear();
+        .arg(
+            Arg::with_name("colonies")
+                .takes_value(true)
+                .long("colonies")
+                .help("How many colonies the random board is split between, 2 is the Immigration game")
+                .validator(|val| check_limits(&val, 1, MAX_COLONIES)),
+        )
+        .arg(
+            Arg::with_name("ca")
+                .takes_value(true)
+                .long("ca")
+                .help("The cellular automaton to run, wireworld replaces the rule and starts with a clock circuit unless given a .wire pattern, ant is Langton's Ant on an empty board")
+                .possible_values(&["life", "wireworld", "ant"]),
+        )
+        .arg(
+            Arg::with_name("ants")
+                .takes_value(true)
+                .long("ants")
+                .help("How many ants there are with --ca ant")
+                .validator(|val| check_limits(&val, 1, MAX_ANTS)),
+        )
+        .arg(
+            Arg::with_name("rule")
+                .takes_value(true)
+                .long("rule")
+                .help("The birth/survival rule in Bx/Sy notation, e.g. B36/S23, or Bx/Sy/Cz for Generations rules with dying states, e.g. Brian's Brain B2/S/C3")
+                .validator(|val| val.parse::<Rule>().map(|_| ())),
+        )
+        .arg(
+            Arg::with_name("preset")
+                .takes_value(true)
+                .long("preset")
+                .help("Use a level generation rule, overridden by --rule")
+                .possible_values(&["maze", "cave"]),
+        )
+        .arg(
+            Arg::with_name("pattern file")
+                .takes_value(true)
+                .long("pattern-file")
+                .help("Start from an empty board with a .cells, .lif, .rle, .mc or .wire pattern in the middle"),
+        )
+        .arg(
+            Arg::with_name("pattern")
+                .takes_value(true)
+                .long("pattern")
+                .help("Start from an empty board with a built-in pattern in the middle, see --list-patterns")
+                .conflicts_with("pattern file")
+                .validator(|val| {
+                    Pattern::named(&val)
+                        .map(|_| ())
+                        .ok_or_else(|| format!("There's no built-in pattern called {}", val))
+                }),
+        )
+        .arg(
+            Arg::with_name("fetch")
+                .takes_value(true)
+                .long("fetch")
+                .help("Start from an empty board with a pattern downloaded from the LifeWiki archive by name, e.g. gosperglidergun. Needs curl and a build with --features online")
+                .conflicts_with_all(&["pattern file", "pattern"]),
+        )
+        .arg(
+            Arg::with_name("stdin")
+                .long("stdin")
+                .help("Start from an empty board with a pattern read from stdin in the middle, as plain text rows of . and * or RLE")
+                .conflicts_with_all(&["pattern file", "pattern", "fetch", "load"]),
+        )
+        .arg(
+            Arg::with_name("list patterns")
+                .long("list-patterns")
+                .help("List the built-in patterns and exit"),
+        )
+        .arg(
+            Arg::with_name("load")
+                .takes_value(true)
+                .long("load")
+                .help("Resume from a board saved with the s key"),
+        )
+        .arg(
+            Arg::with_name("renderer")
+                .takes_value(true)
+                .long("renderer")
+                .help("Draw in an SDL window, in this terminal or in a full screen terminal UI")
+                .possible_values(&["sdl", "terminal", "tui"]),
+        )
+        .arg(
+            Arg::with_name("engine")
+                .takes_value(true)
+                .long("engine")
+                .help("The algorithm used to step the board, hashlife treats it as a window onto an infinite plane, sparse only tracks live cells and gpu steps it in a compute shader in builds with the gpu feature")
+                .possible_values(&["naive", "hashlife", "sparse", "gpu"]),
+        )
+        .arg(
+            Arg::with_name("compare rule")
+                .takes_value(true)
+                .long("compare-rule")
+                .help("Run a second board beside the first from the same start with this rule, in step with it")
+                .validator(|val| val.parse::<Rule>().map(|_| ()))
+                .conflicts_with("chaos interval"),
+        )
+        .arg(
+            Arg::with_name("compare engine")
+                .takes_value(true)
+                .long("compare-engine")
+                .help("Run a second board beside the first from the same start on this engine, in step with it")
+                .possible_values(&["naive", "hashlife", "sparse"])
+                .conflicts_with("chaos interval"),
+        )
+        .arg(
+            Arg::with_name("export map")
+                .takes_value(true)
+                .long("export-map")
+                .help("Run headless and write the board to a .csv, Tiled .json or .tmx tilemap"),
+        )
+        .arg(
+            Arg::with_name("alive tile")
+                .takes_value(true)
+                .long("alive-tile")
+                .help("The tile ID exported for live cells")
+                .validator(|val| check_limits(&val, 0, u32::MAX)),
+        )
+        .arg(
+            Arg::with_name("dead tile")
+                .takes_value(true)
+                .long("dead-tile")
+                .help("The tile ID exported for dead cells")
+                .validator(|val| check_limits(&val, 0, u32::MAX)),
+        )
+        .arg(
+            Arg::with_name("export mesh")
+                .takes_value(true)
+                .long("export-mesh")
+                .help("Run headless and write the live cells as an .stl or .obj mesh"),
+        )
+        .arg(
+            Arg::with_name("mesh cell size")
+                .takes_value(true)
+                .long("mesh-cell-size")
+                .help("How wide each cell is in the exported mesh")
+                .validator(|val| check_limits(&val, 0.01, 1000.0)),
+        )
+        .arg(
+            Arg::with_name("mesh height")
+                .takes_value(true)
+                .long("mesh-height")
+                .help("How far live cells are extruded in the exported mesh")
+                .validator(|val| check_limits(&val, 0.01, 1000.0)),
+        )
+        .arg(
+            Arg::with_name("mesh base")
+                .takes_value(true)
+                .long("mesh-base")
+                .help("How thick the plate under the exported mesh is, 0 for none")
+                .validator(|val| check_limits(&val, 0.0, 1000.0)),
+        )
+        .arg(
+            Arg::with_name("bench")
+                .takes_value(true)
+                .long("bench")
+                .help("Run this many generations headless as fast as possible, then print how long they took")
+                .conflicts_with_all(&["record", "record repro", "export map", "export mesh"])
+                .validator(|val| check_limits(&val, 1, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .takes_value(true)
+                .long("verify")
+                .help("Run this many generations headless with every engine that can run the board, checking each against a plain reference engine after every generation")
+                .conflicts_with_all(&["record", "record repro", "export map", "export mesh", "bench"])
+                .validator(|val| check_limits(&val, 1, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("generations")
+                .takes_value(true)
+                .long("generations")
+                .value_name("N")
+                .help("Run exactly this many generations headless, print the final board and exit")
+                .conflicts_with_all(&["record", "record repro", "export map", "export mesh", "bench", "verify"])
+                .validator(|val| check_limits(&val, 0, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("emit frames")
+                .takes_value(true)
+                .min_values(0)
+                .long("emit-frames")
+                .value_name("format")
+                .possible_values(&["text", "json"])
+                .help("Run headless and write every generation to stdout, as text or JSON lines, until --generations runs out or the reader stops")
+                .conflicts_with_all(&["record", "record repro", "export map", "export mesh", "bench", "verify", "verbose"]),
+        )
+        .group(ArgGroup::with_name("headless").args(&["generations", "emit frames"]).multiple(true))
+        .arg(
+            Arg::with_name("output")
+                .takes_value(true)
+                .long("output")
+                .value_name("path")
+                .requires("headless")
+                .help("Write the final board of --generations or --emit-frames here instead of printing it, as a pattern if it ends in .rle or to be resumed with --load otherwise"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .requires("headless")
+                .conflicts_with("verbose")
+                .help("Print nothing but the boards of --generations or --emit-frames"),
+        )
+        .arg(
+            Arg::with_name("export steps")
+                .takes_value(true)
+                .long("export-steps")
+                .help("How many generations to run before exporting")
+                .validator(|val| check_limits(&val, 0, 100_000)),
+        )
+        .arg(
+            Arg::with_name("chaos interval")
+                .takes_value(true)
+                .long("chaos-interval")
+                .help("Drop a random pattern somewhere empty every N generations, c does it by hand")
+                .validator(|val| check_limits(&val, 1, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .takes_value(true)
+                .long("seed")
+                .help("Seed for the random board, the seed of every run is logged with -v")
+                .validator(|val| check_limits(&val, 0, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("density")
+                .takes_value(true)
+                .long("density")
+                .help("The fraction of cells alive on a random board, long lived soups like 0.3 to 0.4")
+                .validator(|val| check_limits(&val, 0.0, 1.0)),
+        )
+        .arg(
+            Arg::with_name("record")
+                .takes_value(true)
+                .long("record")
+                .help("Record the run to an animated GIF"),
+        )
+        .arg(
+            Arg::with_name("record every")
+                .takes_value(true)
+                .long("record-every")
+                .help("Only record every Nth generation")
+                .validator(|val| check_limits(&val, 1, u64::MAX)),
+        )
+        .arg(
+            Arg::with_name("snapshot cell size")
+                .takes_value(true)
+                .long("snapshot-cell-size")
+                .help("How many pixels each cell is in snapshots taken with the p key")
+                .validator(|val| check_limits(&val, 1, 64)),
+        )
+        .arg(
+            Arg::with_name("stereo")
+                .takes_value(true)
+                .long("stereo")
+                .help("Draw recordings and snapshots in 3D, older trails sinking into the screen")
+                .possible_values(&["off", "anaglyph", "side-by-side"]),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .takes_value(true)
+                .long("theme")
+                .help("The background, cell, trail and grid colours, t changes it while running")
+                .possible_values(&["classic", "solarized", "matrix-green", "high-contrast"]),
+        )
+        .arg(
+            Arg::with_name("grid")
+                .takes_value(true)
+                .long("grid")
+                .help("Draw lines or leave a gap between cells in the window when they're big enough, G changes it while running")
+                .possible_values(&["off", "lines", "gap"]),
+        )
+        .arg(
+            Arg::with_name("tracks")
+                .takes_value(true)
+                .long("tracks")
+                .help("Follow gliders and other spaceships, drawing where they've been or arrows showing where they're going, T changes it while running")
+                .possible_values(&["off", "trails", "vectors"]),
+        )
+        .arg(
+            Arg::with_name("resize anchor")
+                .takes_value(true)
+                .long("resize-anchor")
+                .help("What stays put when the board is grown or shrunk while running")
+                .possible_values(&["top-left", "top-right", "bottom-left", "bottom-right", "centre"]),
+        )
+        .arg(
+            Arg::with_name("fade")
+                .takes_value(true)
+                .long("fade")
+                .help("The colours the cells fade through instead of the theme's, k changes it while running")
+                .possible_values(&["red", "blue", "grayscale", "heatmap", "viridis"]),
+        )
+        .arg(
+            Arg::with_name("colour by age")
+                .long("colour-by-age")
+                .help("Colour cells by how long they have been alive, newborns brightest, a toggles it while running"),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .takes_value(true)
+                .long("heatmap")
+                .value_name("generations")
+                .help("Colour each cell by how many of the last so many generations it's been alive in, H toggles it while running")
+                .validator(|val| check_limits(&val, 1, MAX_HEATMAP)),
+        )
+        .arg(
+            Arg::with_name("bind")
+                .takes_value(true)
+                .long("bind")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("action=key ...")
+                .help("Move actions to other keys, e.g. \"pause=p step=space\", see the README for the actions and key names")
+                .validator(|val| keymap::parse_bindings(&val).map(|_| ())),
+        )
+        .arg(
+            Arg::with_name("record repro")
+                .takes_value(true)
+                .long("record-repro")
+                .help("Write everything needed to replay this session exactly to a .repro file"),
+        )
+        .arg(
+            Arg::with_name("stats out")
+                .takes_value(true)
+                .long("stats-out")
+                .help("Add the generation, population, births, deaths and step time of every generation to a CSV file, or JSON lines if it ends in .json or .jsonl"),
+        )
+        .arg(
+            Arg::with_name("stop on stable")
+                .long("stop-on-stable")
+                .help("Stop once the board is a still life or an oscillator, up to period 64, and say when it got there"),
+        )
+        .arg(
+            Arg::with_name("rewind")
+                .takes_value(true)
+                .long("rewind")
+                .help("How many generations the left arrow can go back through while paused")
+                .validator(|val| check_limits(&val, 0, 10000)),
+        )
+        .arg(
+            Arg::with_name("skip")
+                .takes_value(true)
+                .long("skip")
+                .value_name("generations")
+                .help("How many generations Shift+Right jumps ahead without drawing the ones in between")
+                .validator(|val| check_limits(&val, 1, MAX_SKIP)),
+        )
+        .arg(
+            Arg::with_name("portable")
+                .long("portable")
+                .help("Keep saves, patterns and crash reports beside the executable"),
+        )
+        .arg(
+            Arg::with_name("dump config")
+                .long("dump-config")
+                .help("Print the settings in use in the config file format and exit"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("enable verbose logging"),
+        )
+        .subcommand(
+            SubCommand::with_name("patterns")
+                .about("Index and search a directory of pattern files")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("index")
+                        .about("Index every pattern under a directory, by default the pattern directory")
+                        .arg(Arg::with_name("dir")),
+                )
+                .subcommand(
+                    SubCommand::with_name("search")
+                        .about("Find indexed patterns matching every word, pN matches period N")
+                        .arg(Arg::with_name("query").required(true).multiple(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Rerun a session saved with --record-repro and check it ends the same way")
+                .arg(Arg::with_name("file").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("soup-search")
+                .about("Run random soups headless until each settles, then save the longest lived and any with uncommon periods as RLE. --seed, --density and --rule pick the soups, before or after soup-search.")
+                .arg(
+                    Arg::with_name("seed")
+                        .takes_value(true)
+                        .long("seed")
+                        .help("Seed for the first soup, the rest follow on from it")
+                        .validator(|val| check_limits(&val, 0, u64::MAX)),
+                )
+                .arg(
+                    Arg::with_name("density")
+                        .takes_value(true)
+                        .long("density")
+                        .help("The fraction of cells alive in each soup, 0.5 by default")
+                        .validator(|val| check_limits(&val, 0.0, 1.0)),
+                )
+                .arg(
+                    Arg::with_name("rule")
+                        .takes_value(true)
+                        .long("rule")
+                        .help("The birth/survival rule the soups are run under, B3/S23 by default")
+                        .validator(|val| val.parse::<Rule>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("soups")
+                        .takes_value(true)
+                        .long("soups")
+                        .help("How many soups to run, 1000 by default")
+                        .validator(|val| check_limits(&val, 1, u64::MAX)),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .takes_value(true)
+                        .long("size")
+                        .help("How many cells across each soup is, 16 by default")
+                        .validator(|val| check_limits(&val, 1, 1024)),
+                )
+                .arg(
+                    Arg::with_name("room")
+                        .takes_value(true)
+                        .long("room")
+                        .help("How many cells across the bounded board each soup runs in, 128 by default")
+                        .validator(|val| check_limits(&val, 1, 4096)),
+                )
+                .arg(
+                    Arg::with_name("max generations")
+                        .takes_value(true)
+                        .long("max-generations")
+                        .help("How long a soup is given to settle, 50000 by default")
+                        .validator(|val| check_limits(&val, 1, u64::MAX)),
+                )
+                .arg(
+                    Arg::with_name("keep")
+                        .takes_value(true)
+                        .long("keep")
+                        .help("How many of the longest lived soups to save, 10 by default")
+                        .validator(|val| check_limits(&val, 0, usize::MAX)),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .takes_value(true)
+                        .long("out")
+                        .help("The directory the soups are saved in, soups by default"),
+                ),
+        )
+}
+
+// Options on the command line win over the same ones in the config file
+fn matches_with_config(paths: &Paths) -> Result<clap::ArgMatches<'static>, GameOfLifeError> {
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = paths.config_file();
+    let config = Config::load(&config_path).map_err(GameOfLifeError::Config)?;
+    let is_given = |key: &str| {
+        let option = format!("--{}", key);
+        args.iter()
+            .skip(1)
+            .any(|a| *a == option || a.starts_with(&format!("{}=", option)))
+    };
+    let merged = args
+        .iter()
+        .take(1)
+        .cloned()
+        .chain(config.args(is_given))
+        .chain(args.iter().skip(1).cloned());
+    cli()
+        .setting(AppSettings::ColorNever)
+        .get_matches_from_safe(merged)
+        .map_err(|e| {
+            let message = e.message.lines().next().unwrap_or_default();
+            GameOfLifeError::Config(format!(
+                "{} with the settings from {}",
+                message.trim_start_matches("error: "),
+                config_path.display()
+            ))
+        })
+}
+
+fn run() -> Result<(), GameOfLifeError> {
+    // Parsed once without the config file to find it, and so mistakes on
+    // the command line are reported as usual
+    let matches = cli().get_matches();
+    let paths = Paths::new(matches.is_present("portable"));
+    let matches = matches_with_config(&paths)?;
+
+    let history_length = clap::value_t!(matches.value_of("history length"), usize).unwrap_or(4);
+    let generations_per_second =
+        clap::value_t!(matches.value_of("generations per second"), f32).unwrap_or(20.0);
+    let adaptive_speed = matches.values_of("adaptive speed").map(|values| {
+        let bounds: Vec<f32> = values
+            .map(|v| v.parse().expect("Unable to parse the adaptive speed"))
+            .collect();
+        (bounds[0].min(bounds[1]), bounds[0].max(bounds[1]))
+    });
+    let cell_edge = clap::value_t!(matches.value_of("cell edge length"), i32).unwrap_or(10);
+    let renderer = clap::value_t!(matches.value_of("renderer"), Renderer).unwrap_or_default();
+    let default_size = match renderer {
+        Renderer::Terminal | Renderer::Tui => {
+            Term::from_terminal().unwrap_or(Term { w: 100, h: 80 })
+        }
+        Renderer::Sdl => Term { w: 100, h: 80 },
+    };
+    let board_height =
+        clap::value_t!(matches.value_of("board height"), i32).unwrap_or(default_size.h);
+    let board_width =
+        clap::value_t!(matches.value_of("board width"), i32).unwrap_or(default_size.w);
+    let topology = clap::value_t!(matches.value_of("topology"), Topology).unwrap_or_default();
+    let neighbourhood =
+        clap::value_t!(matches.value_of("neighbourhood"), Neighbourhood).unwrap_or_default();
+    let colonies = clap::value_t!(matches.value_of("colonies"), u8).unwrap_or(1);
+    let ca = clap::value_t!(matches.value_of("ca"), Ca).unwrap_or_default();
+    let ants = clap::value_t!(matches.value_of("ants"), usize).unwrap_or(1);
+    let preset = clap::value_t!(matches.value_of("preset"), Preset).ok();
+    let rule = clap::value_t!(matches.value_of("rule"), Rule)
+        .unwrap_or_else(|_| preset.map(|p| p.rule()).unwrap_or_default());
+    let engine = clap::value_t!(matches.value_of("engine"), Engine).unwrap_or_default();
+    let compare_rule = clap::value_t!(matches.value_of("compare rule"), Rule).ok();
+    let compare_engine = clap::value_t!(matches.value_of("compare engine"), Engine).ok();
+    let mut bindings = Vec::new();
+    for value in matches.values_of("bind").into_iter().flatten() {
+        let parsed = keymap::parse_bindings(value)
+            .map_err(|e| GameOfLifeError::Config(format!("bind = {}: {}", value, e)))?;
+        bindings.extend(parsed);
+    }
+    if let Some(patterns) = matches.subcommand_matches("patterns") {
+        return run_patterns(patterns, &paths);
+    }
+    if let Some(replay) = matches.subcommand_matches("replay") {
+        return run_replay(replay.value_of("file").expect("replay needs a file"));
+    }
+    if let Some(search) = matches.subcommand_matches("soup-search") {
+        let defaults = SoupSearch::default();
+        // The soup options can come after soup-search as well as before it
+        let soup_value = |name| search.value_of(name).or_else(|| matches.value_of(name));
+        let soup_search = SoupSearch {
+            size: clap::value_t!(search.value_of("size"), i32).unwrap_or(defaults.size),
+            room: clap::value_t!(search.value_of("room"), i32).unwrap_or(defaults.room),
+            density: soup_value("density")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.density),
+            rule: clap::value_t!(search.value_of("rule"), Rule).unwrap_or(rule),
+            max_generations: clap::value_t!(search.value_of("max generations"), u64)
+                .unwrap_or(defaults.max_generations),
+        };
+        if soup_search.size > soup_search.room {
+            return Err(GameOfLifeError::Config(
+                "The soups have to fit in the room they're run in".to_string(),
+            ));
+        }
+        return run_soup_search(
+            &soup_search,
+            soup_value("seed")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| rand::thread_rng().gen()),
+            clap::value_t!(search.value_of("soups"), u64).unwrap_or(1000),
+            clap::value_t!(search.value_of("keep"), usize).unwrap_or(10),
+            std::path::Path::new(search.value_of("out").unwrap_or("soups")),
+        );
+    }
+    if matches.is_present("list patterns") {
+        list_patterns();
+        return Ok(());
+    }
+    let pattern = match (
+        matches.is_present("stdin"),
+        matches.value_of("pattern file"),
+        matches.value_of("pattern"),
+        matches.value_of("fetch"),
+    ) {
+        (true, _, _, _) => {
+            let text = std::io::read_to_string(std::io::stdin())
+                .map_err(GameOfLifeError::io("read the pattern from stdin"))?;
+            Some(Pattern::from_text(&text).map_err(GameOfLifeError::Pattern)?)
+        }
+        (false, Some(path), _, _) => Some(
+            Pattern::load(&paths.find_pattern(std::path::Path::new(path)))
+                .map_err(GameOfLifeError::Pattern)?,
+        ),
+        (false, None, Some(name), _) => Pattern::named(name),
+        (false, None, None, Some(name)) => Some(fetch_pattern(name, &paths)?),
+        (false, None, None, None) => None,
+    };
+    let is_verbose = matches.is_present("verbose");
+
+    let clog = Logger { is_verbose };
+
+    let loaded = match matches.value_of("load") {
+        Some(path) => Some(
+            LifeBoard::load(std::path::Path::new(path))
+                .map_err(GameOfLifeError::io("load the saved board"))?,
+        ),
+        None => None,
+    };
+
+    let chaos_interval = clap::value_t!(matches.value_of("chaos interval"), u64).ok();
+    let record_path = matches.value_of("record").map(|p| p.to_string());
+    if record_path.is_some() && !cfg!(feature = "record") {
+        return Err(GameOfLifeError::Unsupported(
+            "GIF support, rebuild with --features record",
+        ));
+    }
+    let record_every = clap::value_t!(matches.value_of("record every"), u64).unwrap_or(1);
+    let repro_path = matches.value_of("record repro").map(|p| p.to_string());
+    let stereo = clap::value_t!(matches.value_of("stereo"), Stereo).unwrap_or_default();
+    let theme = clap::value_t!(matches.value_of("theme"), Theme).unwrap_or_default();
+    let grid = clap::value_t!(matches.value_of("grid"), Grid).unwrap_or_default();
+    let tracks = clap::value_t!(matches.value_of("tracks"), Tracks).unwrap_or_default();
+    let resize_anchor =
+        clap::value_t!(matches.value_of("resize anchor"), Anchor).unwrap_or_default();
+    let fade = clap::value_t!(matches.value_of("fade"), Fade).ok();
+    let snapshot_cell_size =
+        clap::value_t!(matches.value_of("snapshot cell size"), u32).unwrap_or(10);
+    let density = clap::value_t!(matches.value_of("density"), f64).unwrap_or(0.5);
+    let seed =
+        clap::value_t!(matches.value_of("seed"), u64).unwrap_or_else(|_| rand::thread_rng().gen());
+
+    let mut settings = GUISettings {
+        history_length,
+        generations_per_second,
+        adaptive_speed,
+        cell_edge,
+        board_width,
+        board_height,
+        topology,
+        neighbourhood,
+        colonies,
+        ca,
+        ants,
+        rule,
+        engine,
+        pattern,
+        renderer,
+        paths,
+        chaos_interval,
+        seed,
+        density,
+        record_path,
+        record_every,
+        snapshot_cell_size,
+        stereo,
+        theme,
+        grid,
+        tracks,
+        resize_anchor,
+        fade,
+        colour_by_age: matches.is_present("colour by age"),
+        heatmap: clap::value_t!(matches.value_of("heatmap"), usize).ok(),
+        bindings,
+        repro_path,
+        stats_path: matches.value_of("stats out").map(|p| p.to_string()),
+        stop_on_stable: matches.is_present("stop on stable"),
+        rewind_depth: clap::value_t!(matches.value_of("rewind"), usize).unwrap_or(100),
+        skip: clap::value_t!(matches.value_of("skip"), usize).unwrap_or(100),
+        compare_rule,
+        compare_engine,
+    };
+    if let Some(lb) = &loaded {
+        settings.board_width = lb.width();
+        settings.board_height = lb.height();
+        settings.topology = lb.topology();
+        settings.neighbourhood = lb.neighbourhood();
+        settings.colonies = lb.colonies();
+        settings.ca = match (lb.is_wired(), lb.ants().len()) {
+            (true, _) => Ca::WireWorld,
+            (false, 0) => Ca::Life,
+            (false, ants) => {
+                settings.ants = ants;
+                Ca::Ant
+            }
+        };
+        settings.rule = lb.rule().clone();
+        // The saved cells are used instead
+        settings.pattern = None;
+    }
+    if settings.pattern.as_ref().is_some_and(|p| p.is_circuit()) && settings.ca != Ca::WireWorld {
+        clog.nice_to_know("The pattern is a WireWorld circuit, running WireWorld");
+        settings.ca = Ca::WireWorld;
+    }
+    if settings.ca == Ca::WireWorld {
+        settings.rule = Rule::wireworld();
+    }
+    if settings.topology == Topology::Infinite && settings.engine == Engine::Naive {
+        clog.nice_to_know("The naive engine can't run an infinite board, using sparse");
+        settings.engine = Engine::Sparse;
+    }
+    clog.nice_to_know(&format!("Settings\t{}", &settings));
+    if matches.is_present("dump config") {
+        print!("{}", settings.config());
+        return Ok(());
+    }
+
+    install_panic_hook(&settings);
+    let first_board = match loaded {
+        Some(lb) => lb,
+        None => new_board(&settings)?,
+    };
+    record_crash_board(&first_board);
+
+    let export_generations = clap::value_t!(matches.value_of("export steps"), usize)
+        .unwrap_or_else(|_| preset.map_or(100, |p| p.generations()));
+
+    if let Some(path) = matches.value_of("export mesh") {
+        let defaults = MeshSettings::default();
+        let mesh = MeshSettings {
+            cell_size: clap::value_t!(matches.value_of("mesh cell size"), f32)
+                .unwrap_or(defaults.cell_size),
+            height: clap::value_t!(matches.value_of("mesh height"), f32).unwrap_or(defaults.height),
+            base: clap::value_t!(matches.value_of("mesh base"), f32).unwrap_or(defaults.base),
+        };
+        export_mesh(path, first_board, export_generations, &mesh)?;
+        clog.nice_to_know(&format!(
+            "Exported generation {} to {}",
+            export_generations, path
+        ));
+        return Ok(());
+    }
+
+    if let Some(path) = matches.value_of("export map") {
+        let defaults = TileIds::default();
+        let ids = TileIds {
+            alive: clap::value_t!(matches.value_of("alive tile"), u32).unwrap_or(defaults.alive),
+            dead: clap::value_t!(matches.value_of("dead tile"), u32).unwrap_or(defaults.dead),
+        };
+        export_map(path, first_board, export_generations, &ids)?;
+        clog.nice_to_know(&format!(
+            "Exported generation {} to {}",
+            export_generations, path
+        ));
+        return Ok(());
+    }
+
+    if let Ok(generations) = clap::value_t!(matches.value_of("bench"), u64) {
+        return run_bench(first_board, &settings, generations);
+    }
+
+    if let Ok(generations) = clap::value_t!(matches.value_of("verify"), u64) {
+        return run_verify(first_board, &settings, generations);
+    }
+
+    if matches.is_present("headless") {
+        let frames = match matches.value_of("emit frames") {
+            _ if !matches.is_present("emit frames") => None,
+            Some("json") => Some(FrameFormat::Json),
+            _ => Some(FrameFormat::Text),
+        };
+        return run_generations(
+            first_board,
+            &settings,
+            clap::value_t!(matches.value_of("generations"), u64).ok(),
+            frames,
+            matches.value_of("output"),
+            matches.is_present("quiet"),
+        );
+    }
+
+    // Without a display, or SDL, the game can still run in the terminal
+    #[cfg(not(feature = "sdl"))]
+    if settings.renderer == Renderer::Sdl {
+        eprintln!("This build has no SDL support, falling back to the terminal renderer");
+        settings.renderer = Renderer::Terminal;
+    }
+    #[cfg(feature = "sdl")]
+    let mut window = None;
+    #[cfg(feature = "sdl")]
+    if settings.renderer == Renderer::Sdl {
+        match open_window(&settings, &clog) {
+            Ok(opened) => window = Some(opened),
+            Err(e) => {
+                eprintln!("{}, falling back to the terminal renderer", e);
+                settings.renderer = Renderer::Terminal;
+            }
+        }
+    }
+
+    let stable = match settings.renderer {
+        #[cfg(feature = "sdl")]
+        Renderer::Sdl => run_sdl(
+            window.expect("The window is opened before the SDL renderer runs"),
+            first_board,
+            &settings,
+            &clog,
+        )?,
+        #[cfg(not(feature = "sdl"))]
+        Renderer::Sdl => unreachable!("Builds without SDL fall back to the terminal"),
+        Renderer::Terminal => run_terminal(first_board, &settings, &clog)?,
+        #[cfg(feature = "tui")]
+        Renderer::Tui => run_tui(first_board, &settings, &clog)?,
+        #[cfg(not(feature = "tui"))]
+        Renderer::Tui => {
+            return Err(GameOfLifeError::Unsupported(
+                "TUI support, use --renderer terminal",
+            ))
+        }
+    };
+    if let Some((cycle, board)) = stable {
+        println!("Settled into a {}", cycle);
+        match Recogniser::new(board.rule().clone()).and_then(|r| r.census(&board)) {
+            Ok(census) => print!("{}", census),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "online")]
+fn fetch_pattern(name: &str, paths: &Paths) -> Result<Pattern, GameOfLifeError> {
+    gol::fetch::fetch(name, &paths.fetch_dir()).map_err(GameOfLifeError::Pattern)
+}
+
+#[cfg(not(feature = "online"))]
+fn fetch_pattern(_name: &str, _paths: &Paths) -> Result<Pattern, GameOfLifeError> {
+    Err(GameOfLifeError::Unsupported(
+        "LifeWiki support, rebuild with --features online",
+    ))
+}
+
+fn list_patterns() {
+    for name in Pattern::library_names() {
+        let pattern = Pattern::named(name).expect("Unable to find a built-in pattern");
+        let entry = CatalogEntry::from_pattern(std::path::Path::new(name), &pattern);
+        let period = entry.period.map_or(String::new(), |p| format!("p{}, ", p));
+        println!(
+            "{:<16}{} ({}{}x{})",
+            name, entry.name, period, entry.width, entry.height
+        );
+    }
+}
+
+fn run_patterns(matches: &clap::ArgMatches, paths: &Paths) -> Result<(), GameOfLifeError> {
+    match matches.subcommand() {
+        ("index", Some(index)) => {
+            let dir = index
+                .value_of("dir")
+                .map_or_else(|| paths.pattern_dir(), std::path::PathBuf::from);
+            let catalog =
+                Catalog::build(&dir).map_err(GameOfLifeError::io("read the pattern directory"))?;
+            catalog
+                .save(&paths.catalog_file())
+                .map_err(GameOfLifeError::io("save the pattern index"))?;
+            println!(
+                "Indexed {} patterns from {}",
+                catalog.entries.len(),
+                dir.display()
+            );
+        }
+        ("search", Some(search)) => {
+            let catalog = Catalog::load(&paths.catalog_file()).map_err(GameOfLifeError::io(
+                "load the pattern index, run patterns index first",
+            ))?;
+            let query: Vec<&str> = search.values_of("query").into_iter().flatten().collect();
+            for entry in catalog.search(&query.join(" ")) {
+                println!("{}", entry);
+            }
+        }
+        _ => unreachable!("clap requires a patterns subcommand"),
+    }
+    Ok(())
+}
+
+// Soups are seeded one after another from the first seed so any of them can
+// be run again
+fn run_soup_search(
+    search: &SoupSearch,
+    first_seed: u64,
+    soups: u64,
+    keep: usize,
+    out: &std::path::Path,
+) -> Result<(), GameOfLifeError> {
+    println!(
+        "Running {} {}x{} soups from seed {} at {}% in a {}x{} board",
+        soups,
+        search.size,
+        search.size,
+        first_seed,
+        search.density * 100.0,
+        search.room,
+        search.room
+    );
+    let recogniser = match Recogniser::new(search.rule.clone()) {
+        Ok(recogniser) => Some(recogniser),
+        Err(e) => {
+            eprintln!("{}, the soups won't have a census", e);
+            None
+        }
+    };
+    let haul = search.run_many(first_seed, soups, keep, recogniser.as_ref());
+
+    std::fs::create_dir_all(out).map_err(GameOfLifeError::io("create the soup directory"))?;
+    let save = |soup: &Soup| -> Result<(), GameOfLifeError> {
+        let path = out.join(format!("soup-{}.rle", soup.seed));
+        std::fs::write(&path, soup.to_rle(search)).map_err(GameOfLifeError::io("save a soup"))?;
+        println!("  {}: {}", path.display(), soup.describe(search));
+        Ok(())
+    };
+    println!("Longest lived:");
+    for soup in &haul.longest {
+        save(soup)?;
+    }
+    if !haul.uncommon.is_empty() {
+        println!("Uncommon objects:");
+        for soup in &haul.uncommon {
+            save(soup)?;
+        }
+    }
+    if let (Some(total), Some(apgcodes)) = (&haul.census, haul.to_apgcodes(search)) {
+        println!("Census of the settled soups:");
+        print!("{}", total);
+        let path = out.join("census.txt");
+        std::fs::write(&path, apgcodes).map_err(GameOfLifeError::io("save the census"))?;
+        println!("Saved the census as apgcodes to {}", path.display());
+    }
+    Ok(())
+}
+
+fn run_replay(path: &str) -> Result<(), GameOfLifeError> {
+    let text = std::fs::read_to_string(path).map_err(GameOfLifeError::io("read the repro"))?;
+    let Repro {
+        settings: started,
+        board,
+        edits,
+        end: recorded_end,
+    } = Repro::from_text(&text).map_err(|e| GameOfLifeError::Repro(format!("{}: {}", path, e)))?;
+    let settings = GUISettings {
+        board_width: started.size.w,
+        board_height: started.size.h,
+        topology: started.topology,
+        neighbourhood: started.neighbourhood,
+        colonies: started.colonies,
+        ca: started.ca,
+        ants: started.ants,
+        rule: started.rule,
+        engine: started.engine,
+        history_length: started.history_length,
+        rewind_depth: started.rewind_depth,
+        seed: started.seed,
+        density: started.density,
+        ..GUISettings::default()
+    };
+    let board = board.unwrap_or_else(|| random_board(&settings));
+    let mut sim = Simulation::new(board, &settings)?;
+
+    // Without an end the session was killed, so all there is to go on is
+    // the last edit
+    let end = recorded_end
+        .map(|end| end.generation)
+        .or_else(|| edits.last().map(|(generation, _)| *generation))
+        .unwrap_or(sim.generation);
+    let mut edits = edits.iter().peekable();
+    loop {
+        while let Some((_, edit)) = edits.next_if(|(g, _)| *g <= sim.generation) {
+            match edit {
+                Edit::Cell(location, state) => sim.paint(location, state.clone()),
+                Edit::View(origin) => {
+                    sim.move_view(origin.r - sim.origin.r, origin.c - sim.origin.c)
+                }
+                Edit::Resize(size, anchor) => {
+                    sim.resize(size.clone(), *anchor)
+                        .map_err(GameOfLifeError::Repro)?;
+                }
+                Edit::Back => {
+                    sim.back().map_err(GameOfLifeError::Repro)?;
+                }
+            }
+        }
+        // Going back means the session may have passed the end before
+        if sim.generation >= end && edits.peek().is_none() {
+            break;
+        }
+        sim.advance();
+    }
+
+    let sum = repro::checksum(&sim.board);
+    match recorded_end {
+        None => println!(
+            "{} was never finished, replayed its edits up to generation {}, checksum {:016x}",
+            path, end, sum
+        ),
+        Some(ReproEnd { checksum, .. }) if checksum == sum => {
+            println!(
+                "Replayed to generation {}, checksum {:016x} matches",
+                end, sum
+            )
+        }
+        Some(ReproEnd { checksum, .. }) => {
+            println!(
+                "Replayed to generation {} but the checksum is {:016x} where the session had {:016x}",
+                end, sum, checksum
+            );
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(ReproEnd { crashed: true, .. }) = recorded_end {
+        println!(
+            "The session crashed after generation {}, running the next one",
+            end
+        );
+        sim.advance();
+        println!("Generation {} ran without crashing", sim.generation);
+    }
+    Ok(())
+}
+
+fn run_terminal(
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    clog: &Logger,
+) -> Result<Option<(Cycle, LifeBoard)>, GameOfLifeError> {
+    let mut sim = Simulation::new(first_board, settings)?;
+    clog.nice_to_know("Running in the terminal");
+
+    let mut speed = Speed::new(settings.generations_per_second, settings.adaptive_speed);
+    let stdout = std::io::stdout();
+    // Kept under the board, as it's cleared every frame
+    let mut problems = String::new();
+    loop {
+        for problem in sim.take_problems() {
+            problems += &format!("\n{}", problem);
+        }
+        let mut out = std::io::BufWriter::new(stdout.lock());
+        writeln!(out, "\x1B[2J\x1B[H{}{}{}", sim.board, sim.hud(), problems)
+            .and_then(|_| out.flush())
+            .map_err(GameOfLifeError::io("draw to the terminal"))?;
+        drop(out);
+
+        thread::sleep(TERMINAL_FRAME);
+        let steps = speed.due(true);
+        if let Some(chaos) = sim.advance_by(steps, settings) {
+            clog.nice_to_know(&format!("Chaos dropped {}", chaos));
+        }
+        if steps > 0 {
+            speed.govern(sim.activity());
+        }
+        record_crash_board(&sim.board);
+        if sim.stable.is_some() {
+            sim.finish();
+            report_problems(&mut sim);
+            return Ok(sim.stable.map(|cycle| (cycle, sim.board.clone())));
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+struct TuiGuard;
+
+#[cfg(feature = "tui")]
+impl TuiGuard {
+    fn enter() -> Result<TuiGuard, GameOfLifeError> {
+        crossterm::terminal::enable_raw_mode()
+            .map_err(GameOfLifeError::io("put the terminal in raw mode"))?;
+        // Built before anything can fail so the terminal is put back
+        let guard = TuiGuard;
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::cursor::Hide
+        )
+        .map_err(GameOfLifeError::io("enter the alternate screen"))?;
+
+        // The panic message is printed before unwinding, so put the terminal
+        // back first or it is lost with the alternate screen
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            TuiGuard::restore();
+            hook(info);
+        }));
+        Ok(guard)
+    }
+
+    fn restore() {
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::cursor::Show,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Drop for TuiGuard {
+    fn drop(&mut self) {
+        TuiGuard::restore();
+    }
+}
+
+#[cfg(feature = "tui")]
+fn ant_arrow(heading: Heading) -> char {
+    match heading {
+        Heading::North => '\u{25b2}',
+        Heading::East => '\u{25b6}',
+        Heading::South => '\u{25bc}',
+        Heading::West => '\u{25c0}',
+    }
+}
+
+// The nearest of eight arrows to the way a ship's going
+#[cfg(feature = "tui")]
+fn track_arrow((dr, dc): (f32, f32)) -> char {
+    const ARROWS: [char; 8] = [
+        '\u{2192}', '\u{2198}', '\u{2193}', '\u{2199}', '\u{2190}', '\u{2196}', '\u{2191}',
+        '\u{2197}',
+    ];
+    let eighth = (dr.atan2(dc) / std::f32::consts::FRAC_PI_4).round() as i32;
+    ARROWS[eighth.rem_euclid(8) as usize]
+}
+
+// The characters drawn over the empty cells a ship's track goes through
+#[cfg(feature = "tui")]
+fn track_marks(sim: &Simulation) -> collections::HashMap<CellLocation, char> {
+    let at = |(r, c): (f32, f32)| CellLocation {
+        r: r.floor() as i32,
+        c: c.floor() as i32,
+    };
+    let mut marks = collections::HashMap::new();
+    for track in sim.tracked_ships() {
+        match sim.tracks {
+            Tracks::Trails => {
+                for point in &track.trail {
+                    marks.insert(at(*point), '\u{00b7}');
+                }
+            }
+            Tracks::Vectors => {
+                let (r, c) = track.position();
+                let (end_r, end_c) = track.ahead(VECTOR_GENERATIONS);
+                let steps = (end_r - r).abs().max((end_c - c).abs()).ceil() as i32;
+                for step in 1..steps {
+                    let along = step as f32 / steps as f32;
+                    marks.insert(
+                        at((r + (end_r - r) * along, c + (end_c - c) * along)),
+                        '\u{00b7}',
+                    );
+                }
+                marks.insert(at((end_r, end_c)), track_arrow(track.velocity));
+            }
+            Tracks::Off => {}
+        }
+    }
+    marks
+}
+
+// One row of a board, starting wherever the cursor is
+#[cfg(feature = "tui")]
+fn draw_tui_row(
+    out: &mut impl Write,
+    sim: &Simulation,
+    settled: Option<&[bool]>,
+    marks: &collections::HashMap<CellLocation, char>,
+    r: u16,
+    visible_cols: u16,
+) -> std::io::Result<()> {
+    use crossterm::{style, QueueableCommand};
+
+    let fade = sim.cell_colours();
+    // A black background is left as the terminal's own
+    let background = match sim.theme.background {
+        [0, 0, 0] => style::Color::Reset,
+        [r, g, b] => style::Color::Rgb { r, g, b },
+    };
+    out.queue(style::SetBackgroundColor(background))?;
+    let mut line = String::with_capacity(visible_cols as usize);
+    let mut line_colour = None;
+    for c in 0..visible_cols {
+        let location = CellLocation {
+            r: r as i32,
+            c: c as i32,
+        };
+        let is_settled =
+            settled.is_some_and(|s| s[(location.r * sim.board.width() + location.c) as usize]);
+        // Lit by the newest board it was alive in, the same as the
+        // trails are drawn in the window
+        let age = sim
+            .history
+            .iter()
+            .rev()
+            .position(|b| b.state(&location) == Some(CellState::Alive));
+        let state = sim.board.state(&location).unwrap_or_default();
+        let ant = sim.board.ants().iter().find(|a| a.location == location);
+        let cell = match (age, &state, is_settled) {
+            (_, CellState::Wire, _) => '\u{2591}',
+            (Some(0), _, true) => '\u{2592}',
+            (Some(0), _, false) => '\u{2588}',
+            (_, CellState::Dying(_), _) => '\u{2593}',
+            (Some(_), _, _) if sim.board.is_wired() => ' ',
+            (Some(_), _, _) => '\u{2588}',
+            (None, _, _) => ' ',
+        };
+        let heat = sim.heatmap.as_ref().map(|h| h.heat(&location));
+        let cell = match heat {
+            Some(heat) if heat > 0.0 => '\u{2588}',
+            Some(_) => ' ',
+            None => cell,
+        };
+        let cell = ant.map_or(cell, |ant| ant_arrow(ant.heading));
+        let mark = marks.get(&location).filter(|_| cell == ' ');
+        let cell = mark.copied().unwrap_or(cell);
+        let intensity = match (age, &state) {
+            _ if sim.board.is_wired() => None,
+            (Some(0), _) if sim.by_age => Some(age_intensity(sim.board.age(&location))),
+            (_, CellState::Dying(_)) => Some(sim.board.rule().brightness(&state)),
+            (Some(age), _) => Some((sim.history.len() - age) as f32 / sim.history.len() as f32),
+            (None, _) => None,
+        };
+        let colony = sim
+            .board
+            .colony(&location)
+            .filter(|_| age == Some(0) && sim.board.colonies() > 1);
+        let colour = match (intensity, colony) {
+            _ if ant.is_some() => Some(ANT_COLOUR),
+            _ if mark.is_some() => Some(TRACK_COLOUR),
+            _ if heat.is_some() => heat.filter(|h| *h > 0.0).map(heat_colour),
+            _ if sim.board.is_wired() && cell != ' ' => Some(wire_colour(&state)),
+            (Some(intensity), Some(colony)) => Some(Colony(colony).colour(intensity)),
+            (Some(intensity), None) => Some(fade.colour(intensity)),
+            (None, _) => None,
+        };
+        if let Some(colour) = colour {
+            if line_colour != Some(colour) {
+                out.queue(style::Print(&line))?
+                    .queue(style::SetForegroundColor(style::Color::Rgb {
+                        r: colour[0],
+                        g: colour[1],
+                        b: colour[2],
+                    }))?;
+                line.clear();
+                line_colour = Some(colour);
+            }
+        }
+        line.push(cell);
+    }
+    out.queue(style::Print(&line))?.queue(style::ResetColor)?;
+    Ok(())
+}
+
+// The boards side by side with a column between them, then the status bar
+// for the first
+#[cfg(feature = "tui")]
+fn draw_tui(
+    out: &mut impl Write,
+    boards: &[(&Simulation, Option<&[bool]>)],
+    fps: f32,
+    speed: &Speed,
+    is_paused: bool,
+    status: &str,
+) -> std::io::Result<()> {
+    use crossterm::{cursor, style, terminal, QueueableCommand};
+
+    // Every line is overwritten in place rather than clearing the screen
+    // first, which is what stops the flicker
+    let (cols, rows) = terminal::size()?;
+    let sim = boards[0].0;
+    let tallest = boards
+        .iter()
+        .map(|(b, _)| b.board.height())
+        .max()
+        .unwrap_or(0);
+    let visible_rows = (tallest as u16).min(rows.saturating_sub(1));
+    let marks: Vec<_> = boards.iter().map(|(b, _)| track_marks(b)).collect();
+    for r in 0..visible_rows {
+        let mut left = 0;
+        for ((board, settled), marks) in boards.iter().zip(&marks) {
+            if left >= cols {
+                break;
+            }
+            let visible_cols = (board.board.width() as u16).min(cols - left);
+            out.queue(cursor::MoveTo(left, r))?;
+            if (r as i32) < board.board.height() {
+                draw_tui_row(out, board, *settled, marks, r, visible_cols)?;
+            } else {
+                out.queue(style::Print(" ".repeat(visible_cols as usize)))?;
+            }
+            left += visible_cols + 1;
+        }
+        out.queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+    }
+
+    let mut bar = format!(
+        " Generation {} | Population {} (+{} -{}){} | {} | FPS {:.0} | Rule {}{}{}{}",
+        sim.generation,
+        sim.board.population(),
+        sim.births,
+        sim.deaths,
+        sim.colonies()
+            .map_or(String::new(), |c| format!(" | Colonies {}", c)),
+        speed,
+        fps,
+        sim.board.rule(),
+        if sim.board.topology() == Topology::Infinite {
+            format!(" | At {},{}", sim.origin.r, sim.origin.c)
+        } else {
+            String::new()
+        },
+        match (is_paused, sim.rewound()) {
+            (false, _) => String::new(),
+            (true, 0) => " | Paused".to_string(),
+            (true, n) => format!(" | Paused {} back", n),
+        },
+        status
+    );
+    bar.truncate(cols as usize);
+    out.queue(cursor::MoveTo(0, visible_rows))?
+        .queue(style::SetAttribute(style::Attribute::Reverse))?
+        .queue(style::Print(format!(
+            "{:width$}",
+            bar,
+            width = cols as usize
+        )))?
+        .queue(style::SetAttribute(style::Attribute::Reset))?
+        .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    out.flush()
+}
+
+// The name the keymap knows a key by, see Keymap::action
+#[cfg(feature = "tui")]
+fn tui_key_name(
+    code: &crossterm::event::KeyCode,
+    modifiers: crossterm::event::KeyModifiers,
+) -> Option<String> {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    let name = match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Esc => "escape".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        _ => return None,
+    };
+    let name = if modifiers.contains(KeyModifiers::SHIFT) && name.chars().count() > 1 {
+        format!("shift+{}", name)
+    } else {
+        name
+    };
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        Some(format!("ctrl+{}", name))
+    } else {
+        Some(name)
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    clog: &Logger,
+) -> Result<Option<(Cycle, LifeBoard)>, GameOfLifeError> {
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+    let mut comparison = Comparison::new(&first_board, settings)?;
+    let mut sim = Simulation::new(first_board, settings)?;
+    clog.nice_to_know("Running in the terminal UI");
+    let keymap = Keymap::with_bindings(&settings.bindings);
+    let guard = TuiGuard::enter()?;
+
+    let stdout = std::io::stdout();
+    let mut speed = Speed::new(settings.generations_per_second, settings.adaptive_speed);
+    let mut is_paused = false;
+    let mut status = String::new();
+    let mut fps = 0.0;
+    let mut frames = 0;
+    let mut fps_since = time::Instant::now();
+    'running: loop {
+        let frame_start = time::Instant::now();
+        let settled = |sim: &Simulation| {
+            if sim.grey_settled {
+                Some(sim.board.locally_periodic(SETTLED_PERIOD))
+            } else {
+                None
+            }
+        };
+        let problems = sim.take_problems();
+        if !problems.is_empty() {
+            status = format!(" | {}", problems.join(" | "));
+        }
+        let first_settled = settled(&sim);
+        let mut boards = vec![(&sim, first_settled.as_deref())];
+        let mut bar = status.clone();
+        let beside_settled;
+        if let Some(comparison) = comparison.as_mut() {
+            comparison.keep_up(&sim).map_err(GameOfLifeError::Engine)?;
+            beside_settled = settled(&comparison.sim);
+            boards.push((&comparison.sim, beside_settled.as_deref()));
+            bar += &comparison.status();
+        }
+        draw_tui(&mut stdout.lock(), &boards, fps, &speed, is_paused, &bar)
+            .map_err(GameOfLifeError::io("draw to the terminal"))?;
+
+        let mut step_once = false;
+        while let Some(timeout) = TUI_FRAME.checked_sub(frame_start.elapsed()) {
+            if !event::poll(timeout).map_err(GameOfLifeError::io("read terminal events"))? {
+                break;
+            }
+            let event = event::read().map_err(GameOfLifeError::io("read terminal events"))?;
+            let action = match &event {
+                Event::Key(KeyEvent {
+                    kind: KeyEventKind::Release,
+                    ..
+                }) => continue,
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                }) => break 'running,
+                Event::Key(KeyEvent {
+                    code, modifiers, ..
+                }) => tui_key_name(code, *modifiers).and_then(|key| keymap.action(&key, false)),
+                _ => None,
+            };
+            match action {
+                Some(Action::Quit) => break 'running,
+                Some(Action::Pause) => is_paused = !is_paused,
+                Some(Action::Step) => step_once = is_paused,
+                Some(Action::Faster) => speed.faster(),
+                Some(Action::Slower) => speed.slower(),
+                Some(Action::GreySettled) => sim.grey_settled = !sim.grey_settled,
+                Some(Action::ShorterTrails) => {
+                    sim.set_history_length(sim.history_length - 1);
+                    status = format!(" | Trails {} long", sim.history_length);
+                }
+                Some(Action::LongerTrails) => {
+                    sim.set_history_length(sim.history_length + 1);
+                    status = format!(" | Trails {} long", sim.history_length);
+                }
+                Some(Action::ColourByAge) => sim.by_age = !sim.by_age,
+                Some(Action::Fade) => status = format!(" | Fading through {}", sim.next_fade()),
+                Some(Action::Theme) => status = format!(" | Theme {}", sim.next_theme()),
+                Some(Action::Heatmap) => {
+                    status = if sim.toggle_heatmap() {
+                        format!(" | Heatmap of {} generations", sim.heatmap_generations)
+                    } else {
+                        " | Heatmap off".to_string()
+                    };
+                }
+                Some(Action::Tracks) => {
+                    status = match sim.set_tracks(sim.tracks.next()) {
+                        Ok(()) => format!(" | Tracks {}", sim.tracks),
+                        Err(e) => format!(" | {}", e),
+                    };
+                }
+                Some(Action::Chaos) => {
+                    status = match sim.chaos(&mut rand::thread_rng()) {
+                        Some(chaos) => format!(" | Dropped {}", chaos),
+                        None => " | Nowhere empty enough for chaos".to_string(),
+                    };
+                }
+                Some(Action::Save) => {
+                    status = match save_path(&settings.paths) {
+                        Ok(path) => match sim.board.save(&path) {
+                            Ok(()) => format!(" | Saved {}", path.display()),
+                            Err(e) => format!(" | Unable to save {}: {}", path.display(), e),
+                        },
+                        Err(e) => format!(" | Unable to save the board: {}", e),
+                    };
+                }
+                #[cfg(feature = "snapshot")]
+                Some(Action::Snapshot) => {
+                    status = match snapshot(&sim, settings) {
+                        Ok(path) => format!(" | Snapshot {}", path.display()),
+                        Err(e) => format!(" | Unable to save a snapshot: {}", e),
+                    };
+                }
+                Some(Action::Clear) => sim.rework(LifeBoard::clear),
+                Some(Action::Reseed) => sim.rework(|board| board.randomize(settings.density)),
+                Some(Action::Invert) => sim.rework(LifeBoard::invert),
+                Some(Action::Grow) | Some(Action::Shrink) => {
+                    status = match sim.grow(action == Some(Action::Grow), settings.resize_anchor) {
+                        Ok(_) => format!(
+                            " | The board is now {}x{}",
+                            sim.board.width(),
+                            sim.board.height()
+                        ),
+                        Err(e) => format!(" | {}", e),
+                    };
+                    // A smaller board doesn't draw over all of the last one
+                    crossterm::execute!(
+                        stdout.lock(),
+                        crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+                    )
+                    .map_err(GameOfLifeError::io("clear the terminal"))?;
+                }
+                Some(Action::Skip) => {
+                    let chaos = sim.advance_by(settings.skip, settings);
+                    status = format!(" | Skipped to generation {}", sim.generation);
+                    if let Some(chaos) = chaos {
+                        status += &format!(", dropped {}", chaos);
+                    }
+                }
+                Some(Action::Undo) => {
+                    let undone = sim.undo();
+                    if !undone {
+                        status = " | Nothing to undo".to_string();
+                    }
+                }
+                Some(Action::Redo) => {
+                    let redone = sim.redo();
+                    if !redone {
+                        status = " | Nothing to redo".to_string();
+                    }
+                }
+                // The rest are for the window
+                Some(_) => {}
+                None => {}
+            }
+            if action.is_some() {
+                continue;
+            }
+            match event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Left,
+                    ..
+                }) if is_paused => {
+                    let went_back = sim.back().map_err(GameOfLifeError::Engine)?;
+                    if !went_back {
+                        status = " | Can't go back any further".to_string();
+                    }
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Right,
+                    ..
+                }) if is_paused => sim.forward().map_err(GameOfLifeError::Engine)?,
+                Event::Key(KeyEvent {
+                    code: code @ KeyCode::Left,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: code @ KeyCode::Right,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: code @ KeyCode::Up,
+                    ..
+                })
+                | Event::Key(KeyEvent {
+                    code: code @ KeyCode::Down,
+                    ..
+                }) => {
+                    let (step_r, step_c) = (
+                        (sim.board.height() / VIEW_STEPS).max(1),
+                        (sim.board.width() / VIEW_STEPS).max(1),
+                    );
+                    match code {
+                        KeyCode::Left => sim.move_view(0, -step_c),
+                        KeyCode::Right => sim.move_view(0, step_c),
+                        KeyCode::Up => sim.move_view(-step_r, 0),
+                        _ => sim.move_view(step_r, 0),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let steps = speed.due(!is_paused) + if step_once { 1 } else { 0 };
+        if let Some(chaos) = sim.advance_by(steps, settings) {
+            status = format!(" | Dropped {}", chaos);
+        }
+        if steps > 0 {
+            speed.govern(sim.activity());
+        }
+        record_crash_board(&sim.board);
+        if sim.stable.is_some() {
+            break 'running;
+        }
+
+        frames += 1;
+        let elapsed = fps_since.elapsed();
+        if elapsed >= time::Duration::from_secs(1) {
+            fps = frames as f32 / elapsed.as_secs_f32();
+            frames = 0;
+            fps_since = time::Instant::now();
+        }
+    }
+    sim.finish();
+    // Printed once the terminal's back to normal
+    drop(guard);
+    report_problems(&mut sim);
+    Ok(sim.stable.map(|cycle| (cycle, sim.board.clone())))
+}
+
+#[cfg(feature = "sdl")]
+struct SdlWindow {
+    // Kept alive for as long as the window is open
+    _context: sdl2::Sdl,
+    canvas: sdl2::render::Canvas<sdl2::video::Window>,
+    event_pump: sdl2::EventPump,
+    clipboard: sdl2::clipboard::ClipboardUtil,
+    // Presenting waits for the display, so each frame is paced by its
+    // refresh rate rather than by sleeping for SDL_FRAME
+    is_vsynced: bool,
+}
+
+// Fails without a display, e.g. on a headless server or with no DISPLAY set
+#[cfg(feature = "sdl")]
+fn open_window(settings: &GUISettings, clog: &Logger) -> Result<SdlWindow, GameOfLifeError> {
+    let sdl_context = sdl2::init().map_err(GameOfLifeError::sdl("initialise SDL2"))?;
+    clog.nice_to_know("Initialised SDL2");
+    clog.nice_to_know(&format!("SDL2 Version = {}", sdl2::version::version()));
+
+    let vss = sdl_context
+        .video()
+        .map_err(GameOfLifeError::sdl("get a video context from SDL"))?;
+    clog.nice_to_know("Initialised a video context");
+    let clipboard = vss.clipboard();
+
+    // Room for a board compared beside the first and a cell between them
+    let across = if settings.compare_rule.is_some() || settings.compare_engine.is_some() {
+        settings.board_width * 2 + 1
+    } else {
+        settings.board_width
+    };
+    let window = vss
+        .window(
+            "Game of Life",
+            (across * settings.cell_edge) as u32,
+            (settings.board_height * settings.cell_edge) as u32,
+        )
+        .position_centered()
+        .resizable()
+        .build()
+        .map_err(GameOfLifeError::sdl("create a window"))?;
+    clog.nice_to_know("Created a window");
+
+    let mut canvas = window
+        .into_canvas()
+        .present_vsync()
+        .build()
+        .map_err(GameOfLifeError::sdl("get a canvas"))?;
+    clog.nice_to_know("Created a canvas");
+    let is_vsynced =
+        canvas.info().flags & sdl2::sys::SDL_RendererFlags::SDL_RENDERER_PRESENTVSYNC as u32 != 0;
+    clog.nice_to_know(if is_vsynced {
+        "Presenting in step with the display"
+    } else {
+        "No vsync, the frame rate is kept by sleeping"
+    });
+
+    let [red, green, blue] = settings.theme.background;
+    canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+    canvas.clear();
     canvas.present();
-    let mut event_pump = sdl_context
+    let event_pump = sdl_context
         .event_pump()
-        .expect("Unable to get SDL event pump");
+        .map_err(GameOfLifeError::sdl("get an SDL event pump"))?;
     clog.nice_to_know("Created an event pump");
+    Ok(SdlWindow {
+        _context: sdl_context,
+        canvas,
+        event_pump,
+        clipboard,
+        is_vsynced,
+    })
+}
 
-    let mut lb = LifeBoard::from(Term {
-        w: settings.board_width,
-        h: settings.board_height,
-    });
-    clog.nice_to_know("Created first board");
+// The name the keymap knows a key by, see Keymap::action. SDL names the
+// letter keys in capitals whether or not shift is held.
+#[cfg(feature = "sdl")]
+fn sdl_key_name(key: sdl2::keyboard::Keycode, keymod: sdl2::keyboard::Mod) -> String {
+    use sdl2::keyboard::Mod;
+    let name = key.name();
+    let is_shifted = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+    let name = match name.chars().next() {
+        Some(c) if name.len() == 1 && c.is_ascii_alphabetic() => {
+            if is_shifted {
+                c.to_ascii_uppercase().to_string()
+            } else {
+                c.to_ascii_lowercase().to_string()
+            }
+        }
+        _ if is_shifted && name.chars().count() > 1 => format!("shift+{}", name),
+        _ => name,
+    };
+    if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+        format!("ctrl+{}", name)
+    } else {
+        name
+    }
+}
 
-    let mut history = collections::VecDeque::new();
-    history.push_front(lb.clone());
+#[cfg(feature = "sdl")]
+fn run_sdl(
+    window: SdlWindow,
+    first_board: LifeBoard,
+    settings: &GUISettings,
+    clog: &Logger,
+) -> Result<Option<(Cycle, LifeBoard)>, GameOfLifeError> {
+    let SdlWindow {
+        mut canvas,
+        mut event_pump,
+        clipboard: system_clipboard,
+        is_vsynced,
+        ..
+    } = window;
+    let mut comparison = Comparison::new(&first_board, settings)?;
+    let mut sim = Simulation::new(first_board, settings)?;
+    clog.nice_to_know("Created first board");
+    let keymap = Keymap::with_bindings(&settings.bindings);
 
     clog.nice_to_know("Running");
 
+    let mut camera = Camera::new(settings.cell_edge as f32);
+    camera.offset_rows = sim.board.neighbourhood() == Neighbourhood::Hexagonal;
+    let mut mouse = (0, 0);
+    let mut is_paused = false;
+    let mut paint_state = None;
+    let mut show_hud = true;
+    let mut speed = Speed::new(settings.generations_per_second, settings.adaptive_speed);
+    let library = Pattern::library();
+    let mut stamp: Option<Pattern> = None;
+    let mut grid = settings.grid;
+    // While selecting, dragging marks out a rectangle instead of painting
+    let mut is_selecting = false;
+    let mut selection: Option<(CellLocation, CellLocation)> = None;
+    let mut clipboard: Option<Pattern> = None;
+    let texture_creator = canvas.texture_creator();
+    let mut board_texture = BoardTexture::new(&texture_creator);
+    let mut beside_texture = BoardTexture::new(&texture_creator);
+    let is_comparing = comparison.is_some();
+    let across = |sim: &Simulation| {
+        if is_comparing {
+            sim.board.width() * 2 + 1
+        } else {
+            sim.board.width()
+        }
+    };
+    let mut stepper = Stepper::new();
     'running: loop {
-        canvas.set_draw_color(sdl2::pixels::Color::BLACK);
-        canvas.clear();
-
+        let frame_start = time::Instant::now();
+        report_problems(&mut sim);
+        let mut step_once = false;
         for event in event_pump.poll_iter() {
+            let action = match &event {
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(key),
+                    keymod,
+                    ..
+                } => keymap.action(&sdl_key_name(*key, *keymod), is_selecting),
+                _ => None,
+            };
+            match action {
+                Some(Action::Quit) => break 'running,
+                Some(Action::Pause) => {
+                    is_paused = !is_paused;
+                    clog.nice_to_know(if is_paused { "Paused" } else { "Resumed" });
+                }
+                Some(Action::Step) => step_once = is_paused,
+                Some(Action::Faster) => {
+                    speed.faster();
+                    clog.nice_to_know(&format!("Speed {}", speed));
+                }
+                Some(Action::Slower) => {
+                    speed.slower();
+                    clog.nice_to_know(&format!("Speed {}", speed));
+                }
+                Some(Action::GreySettled) => sim.grey_settled = !sim.grey_settled,
+                Some(Action::Grid) => {
+                    grid = grid.next();
+                    clog.nice_to_know(&format!("Grid {}", grid));
+                }
+                Some(Action::Heatmap) => {
+                    if sim.toggle_heatmap() {
+                        clog.nice_to_know(&format!(
+                            "Heatmap of {} generations",
+                            sim.heatmap_generations
+                        ));
+                    } else {
+                        clog.nice_to_know("Heatmap off");
+                    }
+                }
+                Some(Action::Tracks) => match sim.set_tracks(sim.tracks.next()) {
+                    Ok(()) => clog.nice_to_know(&format!("Tracks {}", sim.tracks)),
+                    Err(e) => eprintln!("{}", e),
+                },
+                Some(Action::ShorterTrails) => {
+                    sim.set_history_length(sim.history_length - 1);
+                    clog.nice_to_know(&format!("Trails {} long", sim.history_length));
+                }
+                Some(Action::LongerTrails) => {
+                    sim.set_history_length(sim.history_length + 1);
+                    clog.nice_to_know(&format!("Trails {} long", sim.history_length));
+                }
+                Some(Action::ColourByAge) => sim.by_age = !sim.by_age,
+                Some(Action::Fade) => {
+                    let fade = sim.next_fade();
+                    clog.nice_to_know(&format!("Fading through {}", fade));
+                }
+                Some(Action::Theme) => {
+                    let theme = sim.next_theme();
+                    clog.nice_to_know(&format!("Theme {}", theme));
+                }
+                Some(Action::Chaos) => match sim.chaos(&mut rand::thread_rng()) {
+                    Some(chaos) => clog.nice_to_know(&format!("Chaos dropped {}", chaos)),
+                    None => clog.nice_to_know("Nowhere empty enough for chaos"),
+                },
+                Some(Action::Save) => match save_path(&settings.paths) {
+                    Ok(path) => match sim.board.save(&path) {
+                        Ok(()) => clog.nice_to_know(&format!("Saved board to {}", path.display())),
+                        Err(e) => {
+                            eprintln!("Unable to save the board to {}: {}", path.display(), e)
+                        }
+                    },
+                    Err(e) => eprintln!("Unable to save the board: {}", e),
+                },
+                Some(Action::Snapshot) =>
+                {
+                    #[cfg(feature = "snapshot")]
+                    match snapshot(&sim, settings) {
+                        Ok(path) => {
+                            clog.nice_to_know(&format!("Saved snapshot to {}", path.display()))
+                        }
+                        Err(e) => eprintln!("Unable to save a snapshot: {}", e),
+                    }
+                }
+                Some(Action::Hud) => show_hud = !show_hud,
+                Some(Action::Rotate) => stamp = stamp.map(|p| p.rotate90()),
+                Some(Action::Mirror) => stamp = stamp.map(|p| p.flip_horizontal()),
+                Some(Action::Flip) => stamp = stamp.map(|p| p.flip_vertical()),
+                Some(Action::DropStamp) => stamp = None,
+                Some(Action::Select) => {
+                    is_selecting = !is_selecting;
+                    selection = None;
+                    paint_state = None;
+                    clog.nice_to_know(if is_selecting {
+                        "Selecting"
+                    } else {
+                        "Stopped selecting"
+                    });
+                }
+                Some(Action::Copy) => match &selection {
+                    Some((corner, other)) => {
+                        let pattern = sim.board.copy_region(corner, other);
+                        clog.nice_to_know(&format!("Copied {}", pattern));
+                        clipboard = Some(pattern);
+                    }
+                    None => clog.nice_to_know("Nothing selected to copy"),
+                },
+                Some(Action::Erase) => match &selection {
+                    Some((corner, other)) => sim.erase(corner, other),
+                    None => clog.nice_to_know("Nothing selected to erase"),
+                },
+                Some(Action::Paste) => match &clipboard {
+                    Some(pattern) => {
+                        match sim.stamp_by_hand(pattern, camera.to_board(mouse.0, mouse.1)) {
+                            Some(top_left) => {
+                                clog.nice_to_know(&format!("Pasted {} at {}", pattern, top_left))
+                            }
+                            None => clog.nice_to_know(&format!("{} does not fit there", pattern)),
+                        }
+                    }
+                    None => clog.nice_to_know("Nothing copied to paste"),
+                },
+                Some(Action::CopyRle) => match &selection {
+                    Some((corner, other)) => {
+                        let pattern = sim.board.copy_region(corner, other);
+                        match system_clipboard.set_clipboard_text(&pattern.to_rle()) {
+                            Ok(()) => clog.nice_to_know(&format!("Copied {} as RLE", pattern)),
+                            Err(e) => eprintln!("Unable to copy to the clipboard: {}", e),
+                        }
+                    }
+                    None => clog.nice_to_know("Nothing selected to copy"),
+                },
+                Some(Action::PasteRle) => {
+                    match system_clipboard
+                        .clipboard_text()
+                        .and_then(|text| Pattern::from_rle(&text))
+                    {
+                        Ok(pattern) => {
+                            match sim.stamp_by_hand(&pattern, camera.to_board(mouse.0, mouse.1)) {
+                                Some(top_left) => clog
+                                    .nice_to_know(&format!("Pasted {} at {}", pattern, top_left)),
+                                None => {
+                                    clog.nice_to_know(&format!("{} does not fit there", pattern))
+                                }
+                            }
+                            // So v puts down more of it
+                            clipboard = Some(pattern);
+                        }
+                        Err(e) => eprintln!("Unable to paste from the clipboard: {}", e),
+                    }
+                }
+                Some(Action::Clear) => sim.rework(LifeBoard::clear),
+                Some(Action::Reseed) => sim.rework(|board| board.randomize(settings.density)),
+                Some(Action::Invert) => sim.rework(LifeBoard::invert),
+                Some(Action::Grow) | Some(Action::Shrink) => {
+                    match sim.grow(action == Some(Action::Grow), settings.resize_anchor) {
+                        Ok(shift) => {
+                            // So the cells that were kept stay where they
+                            // were on screen
+                            let edge = camera.cell_size() as f32;
+                            camera.pan(shift.c as f32 * edge, shift.r as f32 * edge);
+                            selection = None;
+                            clog.nice_to_know(&format!(
+                                "The board is now {}x{}",
+                                sim.board.width(),
+                                sim.board.height()
+                            ));
+                        }
+                        Err(e) => clog.nice_to_know(&e),
+                    }
+                }
+                // Stepped here rather than by the stepper, which throws away
+                // whatever it was working on once the board's moved on
+                Some(Action::Skip) => {
+                    if let Some(chaos) = sim.advance_by(settings.skip, settings) {
+                        clog.nice_to_know(&format!("Chaos dropped {}", chaos));
+                    }
+                    clog.nice_to_know(&format!("Skipped to generation {}", sim.generation));
+                }
+                Some(Action::Undo) => {
+                    let undone = sim.undo();
+                    if !undone {
+                        clog.nice_to_know("Nothing to undo");
+                    }
+                }
+                Some(Action::Redo) => {
+                    let redone = sim.redo();
+                    if !redone {
+                        clog.nice_to_know("Nothing to redo");
+                    }
+                }
+                None => {}
+            }
+            if action.is_some() {
+                continue;
+            }
             match event {
-                sdl2::event::Event::Quit { .. }
+                sdl2::event::Event::Quit { .. } => break 'running,
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(key), ..
+                } if palette_index(key).is_some() => {
+                    stamp = palette_index(key).and_then(|i| library.get(i).cloned());
+                    if let Some(pattern) = &stamp {
+                        clog.nice_to_know(&format!("Selected {}", pattern));
+                    }
+                }
+                sdl2::event::Event::MouseButtonDown {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let location = camera.to_board(x, y);
+                    if is_selecting {
+                        selection = Some((location.clone(), location));
+                    } else if let Some(pattern) = &stamp {
+                        let top_left = stamp_origin(pattern, &location);
+                        match sim.stamp_by_hand(pattern, top_left) {
+                            Some(top_left) => {
+                                clog.nice_to_know(&format!("Stamped {} at {}", pattern, top_left))
+                            }
+                            None => clog.nice_to_know(&format!("{} does not fit there", pattern)),
+                        }
+                    } else if let Some(state) = sim.board.state(&location) {
+                        // On a WireWorld board clicking lays a conductor,
+                        // then puts an electron on it
+                        let state = match (state, sim.board.is_wired()) {
+                            (CellState::Dead, true) => CellState::Wire,
+                            (CellState::Alive, _) => CellState::Dead,
+                            (CellState::Dying(_), true) => CellState::Dead,
+                            (_, _) => CellState::Alive,
+                        };
+                        sim.paint_by_hand(&location, state.clone());
+                        paint_state = Some(state);
+                    }
+                }
+                sdl2::event::Event::MouseMotion {
+                    mousestate,
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    mouse = (x, y);
+                    if mousestate.right() {
+                        camera.pan(-xrel as f32, -yrel as f32);
+                    } else if let (true, Some((_, other))) = (mousestate.left(), &mut selection) {
+                        *other = camera.to_board(x, y);
+                    } else if let (true, Some(state)) = (mousestate.left(), &paint_state) {
+                        let location = camera.to_board(x, y);
+                        sim.paint_by_hand(&location, state.clone());
+                    }
+                }
+                sdl2::event::Event::MouseWheel { y, .. } => {
+                    camera.zoom_at(ZOOM_STEP.powi(y), mouse.0, mouse.1);
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Left),
+                    ..
+                } if is_paused => {
+                    let went_back = sim.back().map_err(GameOfLifeError::Engine)?;
+                    if !went_back {
+                        clog.nice_to_know("Can't go back any further");
+                    }
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Right),
+                    ..
+                } if is_paused => sim.forward().map_err(GameOfLifeError::Engine)?,
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Left),
+                    ..
+                }
                 | sdl2::event::Event::KeyDown {
-                    keycode: Some(sdl2::keyboard::Keycode::Escape),
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Right),
                     ..
-                } => break 'running,
+                }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Up),
+                    ..
+                }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Down),
+                    ..
+                } if sim.board.topology() == Topology::Infinite => {
+                    let (step_r, step_c) = (
+                        (sim.board.height() / VIEW_STEPS).max(1),
+                        (sim.board.width() / VIEW_STEPS).max(1),
+                    );
+                    match key {
+                        sdl2::keyboard::Keycode::Left => sim.move_view(0, -step_c),
+                        sdl2::keyboard::Keycode::Right => sim.move_view(0, step_c),
+                        sdl2::keyboard::Keycode::Up => sim.move_view(-step_r, 0),
+                        _ => sim.move_view(step_r, 0),
+                    }
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Left),
+                    ..
+                }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Right),
+                    ..
+                }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Up),
+                    ..
+                }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(key @ sdl2::keyboard::Keycode::Down),
+                    ..
+                } => {
+                    let (view_w, view_h) = canvas
+                        .output_size()
+                        .map_err(GameOfLifeError::sdl("get the canvas size"))?;
+                    let (dx, dy) = match key {
+                        sdl2::keyboard::Keycode::Left => (-(view_w as f32), 0.0),
+                        sdl2::keyboard::Keycode::Right => (view_w as f32, 0.0),
+                        sdl2::keyboard::Keycode::Up => (0.0, -(view_h as f32)),
+                        _ => (0.0, view_h as f32),
+                    };
+                    camera.pan(dx * PAN_FRACTION, dy * PAN_FRACTION);
+                }
+                sdl2::event::Event::Window {
+                    win_event: sdl2::event::WindowEvent::Resized(w, h),
+                    ..
+                } => {
+                    camera.fit(across(&sim), sim.board.height(), w as u32, h as u32);
+                    clog.nice_to_know(&format!("Resized to {}x{}, camera {}", w, h, camera));
+                }
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Home),
+                    ..
+                } => {
+                    let (view_w, view_h) = canvas
+                        .output_size()
+                        .map_err(GameOfLifeError::sdl("get the canvas size"))?;
+                    camera.fit(across(&sim), sim.board.height(), view_w, view_h);
+                }
+                sdl2::event::Event::MouseButtonUp {
+                    mouse_btn: sdl2::mouse::MouseButton::Left,
+                    ..
+                } => {
+                    paint_state = None;
+                    sim.edits.finish();
+                }
                 _ => {}
             }
         }
 
-        let age_incr = 1.0 / (history.len() as f32);
-        let mut age = age_incr;
-        for board in &history {
-            for row in &board.cells {
-                for cell in row {
-                    show_cell(&mut canvas, &cell, age, &settings);
-                }
+        let hud = match &stamp {
+            Some(pattern) => format!(
+                "{} {} Stamp {}",
+                sim.hud(),
+                speed,
+                pattern.name.as_deref().unwrap_or("unnamed")
+            ),
+            None => format!("{} {}", sim.hud(), speed),
+        };
+        let hud = if is_selecting {
+            format!("{} Selecting", hud)
+        } else {
+            hud
+        };
+        let cursor = camera.to_board(mouse.0, mouse.1);
+        let settled = |sim: &Simulation| {
+            if sim.grey_settled {
+                Some(sim.board.locally_periodic(SETTLED_PERIOD))
+            } else {
+                None
             }
-            age += age_incr;
+        };
+        let first_settled = settled(&sim);
+        let mut hud = hud;
+        let beside_settled;
+        let mut beside = None;
+        if let Some(comparison) = comparison.as_mut() {
+            comparison.keep_up(&sim).map_err(GameOfLifeError::Engine)?;
+            hud += &comparison.hud();
+            beside_settled = settled(&comparison.sim);
+            beside = Some((
+                &mut beside_texture,
+                &comparison.sim,
+                beside_settled.as_deref(),
+            ));
         }
+        render_frame(
+            &mut canvas,
+            &mut board_texture,
+            &sim,
+            &camera,
+            if show_hud { Some(&hud) } else { None },
+            match (&stamp, &clipboard) {
+                (Some(pattern), _) => Some((pattern, stamp_origin(pattern, &cursor))),
+                // Where a paste would go, unless a selection's being dragged out
+                (None, Some(pattern)) if is_selecting && !event_pump.mouse_state().left() => {
+                    Some((pattern, cursor))
+                }
+                _ => None,
+            },
+            selection.as_ref(),
+            first_settled.as_deref(),
+            beside,
+            grid,
+        )?;
 
-        canvas.present();
-        lb = lb.process();
-
-        history.push_back(lb.clone());
-        if history.len() > settings.history_length {
-            history.pop_front();
+        let steps = speed.due(!is_paused) + if step_once { 1 } else { 0 };
+        let (stepped, chaos) = stepper.run(&mut sim, steps, settings);
+        if let Some(chaos) = chaos {
+            clog.nice_to_know(&format!("Chaos dropped {}", chaos));
+        }
+        if stepped > 0 {
+            speed.govern(sim.activity());
+        }
+        record_crash_board(&sim.board);
+        if sim.stable.is_some() {
+            break 'running;
         }
 
-        thread::sleep(time::Duration::from_millis(50));
+        if let Some(rest) = SDL_FRAME
+            .checked_sub(frame_start.elapsed())
+            .filter(|_| !is_vsynced)
+        {
+            thread::sleep(rest);
+        }
     }
+    sim.finish();
+    report_problems(&mut sim);
+    Ok(sim.stable.map(|cycle| (cycle, sim.board.clone())))
 }
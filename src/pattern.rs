@@ -0,0 +1,772 @@
+use super::cells::{CellLocation, CellState};
+use super::rule::Rule;
+
+const MAX_EDGE: i64 = 1 << 16;
+const MAX_MACROCELL_CELLS: u64 = 1 << 24;
+// Enough that the corners of the largest square still fit in an i64
+const MAX_MACROCELL_LEVEL: u32 = 62;
+
+// Ordered so the first few are the ones most worth reaching for
+const LIBRARY: [(&str, &str); 15] = [
+    ("glider", include_str!("../patterns/glider.rle")),
+    ("blinker", include_str!("../patterns/blinker.rle")),
+    ("lwss", include_str!("../patterns/lwss.rle")),
+    ("gosper-gun", include_str!("../patterns/gosper-gun.rle")),
+    ("r-pentomino", include_str!("../patterns/r-pentomino.rle")),
+    ("toad", include_str!("../patterns/toad.rle")),
+    ("beacon", include_str!("../patterns/beacon.rle")),
+    ("acorn", include_str!("../patterns/acorn.rle")),
+    ("diehard", include_str!("../patterns/diehard.rle")),
+    ("mwss", include_str!("../patterns/mwss.rle")),
+    ("hwss", include_str!("../patterns/hwss.rle")),
+    ("simkin-gun", include_str!("../patterns/simkin-gun.rle")),
+    ("pulsar", include_str!("../patterns/pulsar.rle")),
+    ("figure-eight", include_str!("../patterns/figure-eight.rle")),
+    (
+        "pentadecathlon",
+        include_str!("../patterns/pentadecathlon.rle"),
+    ),
+];
+
+const CIRCUIT: &str = "!Name: Clock
+ tH....
+.      ......................
+ ......
+";
+
+#[derive(Clone, Debug, Default)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    pub cells: Vec<CellLocation>,
+    // Only WireWorld circuits have these, cells are then the electron
+    // heads
+    pub tails: Vec<CellLocation>,
+    pub wires: Vec<CellLocation>,
+    pub rule: Option<Rule>,
+    pub comments: Vec<String>,
+}
+
+impl Pattern {
+    pub fn load(path: &std::path::Path) -> Result<Pattern, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("cells") => Pattern::from_plaintext(&text),
+            Some("lif") | Some("life") => Pattern::from_life(&text),
+            Some("wire") => Pattern::from_wireworld(&text),
+            Some("mc") => Pattern::from_macrocell(&text),
+            Some("rle") => Pattern::from_rle(&text),
+            _ => Err(format!("Unknown pattern format for {}", path.display())),
+        }
+    }
+
+    // For a pattern that comes without a file name to go by, e.g. piped
+    // in. RLE is told apart from plain text by its header or the b, o, $
+    // and counts that plain text never has.
+    pub fn from_text(text: &str) -> Result<Pattern, String> {
+        if text.starts_with("[M2]") {
+            return Pattern::from_macrocell(text);
+        }
+        if text.starts_with("#Life") {
+            return Pattern::from_life(text);
+        }
+        let is_rle = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.starts_with('!'))
+            .any(|l| {
+                l.starts_with('#')
+                    || l.starts_with('x')
+                    || l.contains(|c: char| c == '$' || c == 'b' || c == 'o' || c.is_ascii_digit())
+            });
+        if is_rle {
+            Pattern::from_rle(text)
+        } else {
+            Pattern::from_plaintext(text)
+        }
+    }
+
+    pub fn library() -> Vec<Pattern> {
+        LIBRARY
+            .iter()
+            .map(|(_, text)| Pattern::from_rle(text).expect("Unable to parse a built-in pattern"))
+            .collect()
+    }
+
+    // The names the built-in patterns go by on the command line
+    pub fn library_names() -> impl Iterator<Item = &'static str> {
+        LIBRARY.iter().map(|(name, _)| *name)
+    }
+
+    pub fn named(name: &str) -> Option<Pattern> {
+        LIBRARY
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, text)| Pattern::from_rle(text).expect("Unable to parse a built-in pattern"))
+    }
+
+    // A loop sending an electron down a wire every 14 generations, for a
+    // WireWorld board with nothing else to show
+    pub fn circuit() -> Pattern {
+        Pattern::from_wireworld(CIRCUIT).expect("Unable to parse the built-in circuit")
+    }
+
+    pub fn is_circuit(&self) -> bool {
+        !self.wires.is_empty() || !self.tails.is_empty()
+    }
+
+    // Each cell with the state it's given, the conductors first so the
+    // electrons can go on top of them
+    pub fn states(&self) -> impl Iterator<Item = (&CellLocation, CellState)> {
+        let wires = self.wires.iter().map(|cell| (cell, CellState::Wire));
+        let tails = self.tails.iter().map(|cell| (cell, CellState::Dying(1)));
+        let heads = self.cells.iter().map(|cell| (cell, CellState::Alive));
+        wires.chain(tails).chain(heads)
+    }
+
+    fn map_cells(&self, f: impl Fn(&CellLocation) -> CellLocation) -> Pattern {
+        Pattern {
+            cells: self.cells.iter().map(&f).collect(),
+            tails: self.tails.iter().map(&f).collect(),
+            wires: self.wires.iter().map(&f).collect(),
+            ..self.clone()
+        }
+    }
+
+    // A quarter turn clockwise
+    pub fn rotate90(&self) -> Pattern {
+        Pattern {
+            width: self.height,
+            height: self.width,
+            ..self.map_cells(|cell| CellLocation {
+                r: cell.c,
+                c: self.height - 1 - cell.r,
+            })
+        }
+    }
+
+    pub fn rotate180(&self) -> Pattern {
+        self.map_cells(|cell| CellLocation {
+            r: self.height - 1 - cell.r,
+            c: self.width - 1 - cell.c,
+        })
+    }
+
+    // A quarter turn anticlockwise
+    pub fn rotate270(&self) -> Pattern {
+        Pattern {
+            width: self.height,
+            height: self.width,
+            ..self.map_cells(|cell| CellLocation {
+                r: self.width - 1 - cell.c,
+                c: cell.r,
+            })
+        }
+    }
+
+    // Left to right
+    pub fn flip_horizontal(&self) -> Pattern {
+        self.map_cells(|cell| CellLocation {
+            r: cell.r,
+            c: self.width - 1 - cell.c,
+        })
+    }
+
+    // Top to bottom
+    pub fn flip_vertical(&self) -> Pattern {
+        self.map_cells(|cell| CellLocation {
+            r: self.height - 1 - cell.r,
+            c: cell.c,
+        })
+    }
+
+    pub fn from_plaintext(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::default();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix('!') {
+                match comment.strip_prefix("Name:") {
+                    Some(name) => pattern.name = Some(name.trim().to_string()),
+                    None => pattern.comments.push(comment.trim().to_string()),
+                }
+                continue;
+            }
+
+            // Spaces are dead too, that's how boards are printed
+            let row = line.trim_end();
+            for (c, ch) in row.chars().enumerate() {
+                match ch {
+                    '.' | ' ' => {}
+                    'O' | '*' => pattern.cells.push(CellLocation {
+                        r: pattern.height,
+                        c: c as i32,
+                    }),
+                    _ => {
+                        return Err(format!(
+                            "Unexpected {} in row {} of the pattern",
+                            ch,
+                            pattern.height + 1
+                        ))
+                    }
+                }
+            }
+            pattern.width = pattern.width.max(row.chars().count() as i32);
+            pattern.height += 1;
+        }
+        Ok(pattern)
+    }
+
+    // WireWorld circuits as plain text, H for an electron head, t for a
+    // tail, . for a conductor and a space for nothing
+    pub fn from_wireworld(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::default();
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix('!') {
+                match comment.strip_prefix("Name:") {
+                    Some(name) => pattern.name = Some(name.trim().to_string()),
+                    None => pattern.comments.push(comment.trim().to_string()),
+                }
+                continue;
+            }
+
+            let row = line.trim_end();
+            for (c, ch) in row.chars().enumerate() {
+                let location = CellLocation {
+                    r: pattern.height,
+                    c: c as i32,
+                };
+                match ch {
+                    ' ' => {}
+                    'H' => pattern.cells.push(location),
+                    't' => pattern.tails.push(location),
+                    '.' => pattern.wires.push(location),
+                    _ => {
+                        return Err(format!(
+                            "Unexpected {} in row {} of the circuit",
+                            ch,
+                            pattern.height + 1
+                        ))
+                    }
+                }
+            }
+            pattern.width = pattern.width.max(row.chars().count() as i32);
+            pattern.height += 1;
+        }
+        pattern.rule = Some(Rule::wireworld());
+        Ok(pattern)
+    }
+
+    // Run length encoded, after any # lines a header gives the size and
+    // rule. Then b is a dead cell, o a live one and $ the end of a row, each
+    // after an optional count, up to a !.
+    pub fn from_rle(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::default();
+        let mut points = Vec::new();
+        let (mut x, mut y) = (0i64, 0i64);
+        let mut count = String::new();
+        let mut has_header = false;
+        'lines: for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let bad_line = || format!("Unable to read line {} of the pattern: {}", n + 1, line);
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut parts = directive.splitn(2, ' ');
+                let (kind, rest) = (
+                    parts.next().unwrap_or(""),
+                    parts.next().unwrap_or("").trim(),
+                );
+                match kind {
+                    "N" => pattern.name = Some(rest.to_string()),
+                    "C" | "c" => pattern.comments.push(rest.to_string()),
+                    "r" => pattern.rule = Some(life_rule(rest)?),
+                    _ => {}
+                }
+                continue;
+            }
+            if !has_header && line.starts_with('x') {
+                has_header = true;
+                for field in line.split(',') {
+                    let mut parts = field.splitn(2, '=');
+                    match (parts.next().map(str::trim), parts.next().map(str::trim)) {
+                        (Some("rule"), Some(rule)) => pattern.rule = Some(life_rule(rule)?),
+                        (Some("x"), Some(_)) | (Some("y"), Some(_)) => {}
+                        _ => return Err(bad_line()),
+                    }
+                }
+                continue;
+            }
+
+            for ch in line.chars() {
+                if ch.is_ascii_digit() {
+                    count.push(ch);
+                    continue;
+                }
+                let run: i64 = if count.is_empty() {
+                    1
+                } else {
+                    count.parse().map_err(|_| bad_line())?
+                };
+                count.clear();
+                if run > MAX_EDGE {
+                    return Err(bad_line());
+                }
+                match ch {
+                    'b' | '.' => x += run,
+                    // Turned away before the points are made, a short line
+                    // of long runs would be a lot of them
+                    'o' | 'A' if x + run > MAX_EDGE || y >= MAX_EDGE => {
+                        return Err(format!(
+                            "Line {} puts cells past {} on a side, the most there can be",
+                            n + 1,
+                            MAX_EDGE
+                        ))
+                    }
+                    'o' | 'A' => {
+                        points.extend((x..x + run).map(|x| (x, y)));
+                        x += run;
+                    }
+                    '$' => {
+                        y += run;
+                        x = 0;
+                    }
+                    '!' => break 'lines,
+                    c if c.is_whitespace() => {}
+                    _ => return Err(bad_line()),
+                }
+            }
+        }
+        pattern.with_points(points)
+    }
+
+    // What from_rle reads, for Golly and the LifeWiki. Only the live cells
+    // are written so a circuit loses its wires and tails.
+    pub fn to_rle(&self) -> String {
+        let mut rle = String::new();
+        if let Some(name) = &self.name {
+            rle.push_str(&format!("#N {}\n", name));
+        }
+        for comment in &self.comments {
+            rle.push_str(&format!("#C {}\n", comment));
+        }
+        rle.push_str(&format!("x = {}, y = {}", self.width, self.height));
+        if let (Some(rule), false) = (&self.rule, self.is_circuit()) {
+            rle.push_str(&format!(", rule = {}", rule));
+        }
+        rle.push('\n');
+
+        let mut rows = vec![Vec::new(); self.height.max(0) as usize];
+        for cell in &self.cells {
+            if let Some(row) = rows.get_mut(cell.r as usize) {
+                row.push(cell.c);
+            }
+        }
+        let run = |n: i32, tag: char| match n {
+            1 => tag.to_string(),
+            _ => format!("{}{}", n, tag),
+        };
+        let mut tags = Vec::new();
+        // Rows ended since the last live cell, the trailing ones are left off
+        let mut ended = 0;
+        for row in rows.iter_mut() {
+            row.sort_unstable();
+            row.dedup();
+            if !row.is_empty() && ended > 0 {
+                tags.push(run(ended, '$'));
+                ended = 0;
+            }
+            let mut x = 0;
+            let mut cols = row.iter().peekable();
+            while let Some(&start) = cols.next() {
+                let mut end = start + 1;
+                while cols.next_if(|&&c| c == end).is_some() {
+                    end += 1;
+                }
+                if start > x {
+                    tags.push(run(start - x, 'b'));
+                }
+                tags.push(run(end - start, 'o'));
+                x = end;
+            }
+            ended += 1;
+        }
+        tags.push("!".to_string());
+
+        // Golly keeps the lines under 70 characters
+        let mut line = String::new();
+        for tag in tags {
+            if line.len() + tag.len() > 70 {
+                rle.push_str(&line);
+                rle.push('\n');
+                line.clear();
+            }
+            line.push_str(&tag);
+        }
+        rle.push_str(&line);
+        rle.push('\n');
+        rle
+    }
+
+    // Life 1.05 blocks of . and * under #P offsets, or Life 1.06 lists of
+    // x y coordinates
+    pub fn from_life(text: &str) -> Result<Pattern, String> {
+        let mut pattern = Pattern::default();
+        let mut points = Vec::new();
+        let is_106 = text.trim_start().starts_with("#Life 1.06");
+        let mut block = None;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad_line = || format!("Unable to read line {} of the pattern: {}", n + 1, line);
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut parts = directive.splitn(2, ' ');
+                let (kind, rest) = (
+                    parts.next().unwrap_or(""),
+                    parts.next().unwrap_or("").trim(),
+                );
+                match kind {
+                    "D" | "C" if pattern.name.is_none() && !rest.is_empty() => {
+                        pattern.name = Some(rest.to_string())
+                    }
+                    "D" | "C" => pattern.comments.push(rest.to_string()),
+                    "N" => pattern.rule = Some(Rule::default()),
+                    "R" => pattern.rule = Some(life_rule(rest)?),
+                    "P" => {
+                        let offset: Vec<i64> = rest
+                            .split_whitespace()
+                            .map(|v| v.parse().map_err(|_| bad_line()))
+                            .collect::<Result<_, _>>()?;
+                        match offset[..] {
+                            [x, y] => block = Some((x, y, 0)),
+                            _ => return Err(bad_line()),
+                        }
+                    }
+                    _ => {}
+                }
+            } else if is_106 {
+                let point: Vec<i64> = line
+                    .split_whitespace()
+                    .map(|v| v.parse().map_err(|_| bad_line()))
+                    .collect::<Result<_, _>>()?;
+                match point[..] {
+                    [x, y] => points.push((x, y)),
+                    _ => return Err(bad_line()),
+                }
+            } else {
+                let (x, y, row) = block.unwrap_or((0, 0, 0));
+                for (c, ch) in line.chars().enumerate() {
+                    match ch {
+                        '.' => {}
+                        '*' | 'O' => points.push((
+                            x.checked_add(c as i64).ok_or_else(bad_line)?,
+                            y.checked_add(row).ok_or_else(bad_line)?,
+                        )),
+                        _ => return Err(bad_line()),
+                    }
+                }
+                block = Some((x, y, row + 1));
+            }
+        }
+
+        pattern.with_points(points)
+    }
+
+    // Golly's macrocell format, a quadtree written bottom up with one node
+    // per line numbered from 1. A node is either an 8x8 leaf of . and *
+    // with $ ending each row, or its level and its nw, ne, sw and se
+    // children, 0 for an empty one. The last node is the whole pattern.
+    pub fn from_macrocell(text: &str) -> Result<Pattern, String> {
+        let mut lines = text.lines().enumerate();
+        if !lines.next().is_some_and(|(_, l)| l.starts_with("[M2]")) {
+            return Err("Macrocell files start with [M2]".to_string());
+        }
+
+        let mut pattern = Pattern::default();
+        // The level of each node, its live cells if it's a leaf and its
+        // children if not, after an empty node 0 that fits anywhere
+        let mut nodes = vec![(0, Vec::new(), [0; 4])];
+        let mut populations = vec![0u64];
+        for (n, line) in lines {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad_line = || format!("Unable to read line {} of the pattern: {}", n + 1, line);
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut parts = directive.splitn(2, ' ');
+                let (kind, rest) = (
+                    parts.next().unwrap_or(""),
+                    parts.next().unwrap_or("").trim(),
+                );
+                match kind {
+                    "C" | "D" | "N" if pattern.name.is_none() && !rest.is_empty() => {
+                        pattern.name = Some(rest.to_string())
+                    }
+                    "C" | "D" => pattern.comments.push(rest.to_string()),
+                    "R" => pattern.rule = Some(life_rule(rest)?),
+                    _ => {}
+                }
+            } else if line.starts_with(['.', '*', '$']) {
+                let (mut r, mut c) = (0, 0);
+                let mut cells = Vec::new();
+                for ch in line.chars() {
+                    match ch {
+                        '.' | '*' if c < 8 && r < 8 => {
+                            if ch == '*' {
+                                cells.push((c, r));
+                            }
+                            c += 1;
+                        }
+                        '$' if r < 8 => {
+                            r += 1;
+                            c = 0;
+                        }
+                        _ => return Err(bad_line()),
+                    }
+                }
+                populations.push(cells.len() as u64);
+                nodes.push((3, cells, [0; 4]));
+            } else {
+                let fields: Vec<u64> = line
+                    .split_whitespace()
+                    .map(|v| v.parse().map_err(|_| bad_line()))
+                    .collect::<Result<_, _>>()?;
+                let (level, children) = match fields[..] {
+                    [level, nw, ne, sw, se] => (level as u32, [nw, ne, sw, se]),
+                    _ => return Err(bad_line()),
+                };
+                if level < 4 {
+                    return Err(format!(
+                        "Line {} is a level {} node, only multi-state files have them and they aren't supported",
+                        n + 1,
+                        level
+                    ));
+                }
+                if level > MAX_MACROCELL_LEVEL {
+                    return Err(format!(
+                        "Line {} is a level {} node, the most supported is {}",
+                        n + 1,
+                        level,
+                        MAX_MACROCELL_LEVEL
+                    ));
+                }
+                let mut population = 0u64;
+                for child in children.iter() {
+                    match nodes.get(*child as usize) {
+                        Some((child_level, _, _)) if *child == 0 || *child_level + 1 == level => {
+                            population = population.saturating_add(populations[*child as usize])
+                        }
+                        _ => return Err(bad_line()),
+                    }
+                }
+                populations.push(population);
+                nodes.push((level, Vec::new(), children.map(|c| c as usize)));
+            }
+        }
+
+        let population = populations.last().copied().unwrap_or(0);
+        if population > MAX_MACROCELL_CELLS {
+            return Err(format!(
+                "Pattern has {} live cells, the most that can be loaded is {}",
+                population, MAX_MACROCELL_CELLS
+            ));
+        }
+        let mut points = Vec::new();
+        let mut stack = vec![(nodes.len() - 1, 0i64, 0i64)];
+        while let Some((node, x, y)) = stack.pop() {
+            let (level, cells, children) = &nodes[node];
+            // Empty nodes can be shared so many times over that walking
+            // every copy of them would take forever
+            if populations[node] == 0 {
+                continue;
+            }
+            if *level == 3 {
+                points.extend(cells.iter().map(|(c, r)| (x + c, y + r)));
+                continue;
+            }
+            let half = 1i64 << (level - 1);
+            let corners = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+            for (child, (x, y)) in children.iter().zip(corners.iter()) {
+                stack.push((*child, *x, *y));
+            }
+        }
+        pattern.with_points(points)
+    }
+
+    // Moves the points to the top left and keeps them if the pattern isn't
+    // too big
+    fn with_points(mut self, mut points: Vec<(i64, i64)>) -> Result<Pattern, String> {
+        let left = points.iter().map(|p| p.0).min().unwrap_or(0);
+        let top = points.iter().map(|p| p.1).min().unwrap_or(0);
+        let right = points.iter().map(|p| p.0).max().unwrap_or(-1);
+        let bottom = points.iter().map(|p| p.1).max().unwrap_or(-1);
+        // Wide enough for points at both ends of an i64
+        let width = right as i128 - left as i128 + 1;
+        let height = bottom as i128 - top as i128 + 1;
+        if width > MAX_EDGE as i128 || height > MAX_EDGE as i128 {
+            return Err(format!(
+                "Pattern is {}x{}, the most either side can be is {}",
+                width, height, MAX_EDGE
+            ));
+        }
+
+        self.width = width as i32;
+        self.height = height as i32;
+        points.sort_unstable();
+        points.dedup();
+        self.cells = points
+            .into_iter()
+            .map(|(x, y)| CellLocation {
+                r: (y - top) as i32,
+                c: (x - left) as i32,
+            })
+            .collect();
+        Ok(self)
+    }
+}
+
+// Life 1.05 writes rules survival first without letters, e.g. 23/3
+fn life_rule(rule: &str) -> Result<Rule, String> {
+    if rule.chars().any(|c| c.is_ascii_alphabetic()) {
+        return rule.parse();
+    }
+    match rule.split('/').collect::<Vec<_>>()[..] {
+        [survive, birth] => format!("B{}/S{}", birth, survive).parse(),
+        _ => Err(format!("Rule {} must be in the form S/B", rule)),
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({}x{})", name, self.width, self.height),
+            None => write!(f, "unnamed ({}x{})", self.width, self.height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_rle_reads_back_the_same() {
+        for name in Pattern::library_names() {
+            let pattern = Pattern::named(name).expect("Unable to find a built-in pattern");
+            let rle = pattern.to_rle();
+            assert!(rle.lines().all(|line| line.len() <= 70), "{}", rle);
+            let read = Pattern::from_rle(&rle).expect("Unable to read the written RLE");
+            assert_eq!(
+                (read.name, read.width, read.height, read.cells, read.rule),
+                (
+                    pattern.name,
+                    pattern.width,
+                    pattern.height,
+                    pattern.cells,
+                    pattern.rule
+                ),
+                "{}",
+                rle
+            );
+        }
+    }
+
+    #[test]
+    fn text_is_read_as_whichever_format_it_is() {
+        let glider = vec![
+            CellLocation { r: 0, c: 1 },
+            CellLocation { r: 1, c: 2 },
+            CellLocation { r: 2, c: 0 },
+            CellLocation { r: 2, c: 1 },
+            CellLocation { r: 2, c: 2 },
+        ];
+        for text in [
+            "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n",
+            "bo$2bo$3o!",
+            "!Name: Glider\n.O.\n..O\nOOO\n",
+            " *\n  *\n***\n",
+        ] {
+            let mut cells = Pattern::from_text(text)
+                .expect("Unable to read the glider")
+                .cells;
+            cells.sort_by_key(|l| (l.r, l.c));
+            assert_eq!(cells, glider, "{}", text);
+        }
+        assert!(Pattern::from_text("..?\n").is_err());
+    }
+
+    #[test]
+    fn life_files_at_the_ends_of_the_coordinates_are_turned_away() {
+        let far_apart = "#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n";
+        let error = Pattern::from_life(far_apart).expect_err("The points were read");
+        assert!(error.starts_with("Pattern is"), "{}", error);
+        let off_the_end = "#Life 1.05\n#P 9223372036854775807 0\n.**\n";
+        let error = Pattern::from_life(off_the_end).expect_err("The block was read");
+        assert!(error.starts_with("Unable to read line 3"), "{}", error);
+    }
+
+    #[test]
+    fn long_runs_are_turned_away_as_they_are_read() {
+        let wide = "65536o".repeat(300) + "!";
+        let error = Pattern::from_rle(&wide).expect_err("The row was read");
+        assert!(error.starts_with("Line 1 puts cells past"), "{}", error);
+        let tall = "65536$".repeat(2) + "o!";
+        assert!(Pattern::from_rle(&tall).is_err());
+        // Long runs of dead cells on their own are fine
+        let edge = Pattern::from_rle("65536b$o65536b!").expect("Unable to read the edge");
+        assert_eq!(edge.cells.len(), 1);
+    }
+
+    #[test]
+    fn an_empty_macrocell_tree_is_not_walked() {
+        // An empty leaf shared by every empty node of a level 30 tree,
+        // with a glider in one corner
+        let mut text = "[M2]\n$\n.*$..*$***$\n4 2 1 1 1\n4 1 1 1 1\n".to_string();
+        let (mut full, mut empty) = (3, 4);
+        for level in 5..=30 {
+            text.push_str(&format!(
+                "{} {} {} {} {}\n",
+                level, empty, empty, empty, empty
+            ));
+            text.push_str(&format!(
+                "{} {} {} {} {}\n",
+                level, full, empty, empty, empty
+            ));
+            // Numbered on from the last two
+            empty = 2 * level - 5;
+            full = empty + 1;
+        }
+        let start = std::time::Instant::now();
+        let pattern = Pattern::from_macrocell(&text).expect("Unable to read the tree");
+        assert_eq!(pattern.cells.len(), 5);
+        assert!(start.elapsed().as_secs() < 2, "took {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn transforms_compose() {
+        let f = Pattern::from_rle("3o$o$2o$o!").expect("Unable to parse the F");
+        let cells = |p: &Pattern| {
+            let mut cells = p.cells.clone();
+            cells.sort_by_key(|l| (l.r, l.c));
+            (p.width, p.height, cells)
+        };
+        let turned = f.rotate90();
+        assert_eq!((turned.width, turned.height), (4, 3));
+        assert_eq!(cells(&turned.rotate90().rotate90().rotate90()), cells(&f));
+        assert_eq!(cells(&turned.rotate270()), cells(&f));
+        assert_eq!(cells(&turned.rotate90()), cells(&f.rotate180()));
+        assert_eq!(cells(&f.rotate180().rotate90()), cells(&f.rotate270()));
+        assert_eq!(cells(&f.flip_horizontal().flip_horizontal()), cells(&f));
+        assert_eq!(cells(&f.flip_vertical().flip_vertical()), cells(&f));
+        assert_eq!(
+            cells(&f.flip_horizontal().flip_vertical()),
+            cells(&f.rotate180())
+        );
+        assert_eq!(
+            cells(&f.flip_horizontal().rotate90()),
+            cells(&f.rotate90().flip_vertical())
+        );
+        assert_ne!(cells(&f.flip_horizontal()), cells(&f));
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+// Where everything the game writes lives. Normally this follows the
+// conventions of the OS, e.g. ~/.local/share/gol on Linux or
+// %APPDATA%\geekskick\gol on Windows, but a portable install keeps it all
+// beside the executable.
+#[derive(Clone, Debug)]
+pub struct Paths {
+    config: PathBuf,
+    data: PathBuf,
+    cache: PathBuf,
+}
+
+impl Paths {
+    pub fn new(portable: bool) -> Paths {
+        if portable {
+            return Paths::beside_executable();
+        }
+        match directories::ProjectDirs::from("uk.co", "geekskick", "gol") {
+            Some(dirs) => Paths {
+                config: dirs.config_dir().to_path_buf(),
+                data: dirs.data_dir().to_path_buf(),
+                cache: dirs.cache_dir().to_path_buf(),
+            },
+            None => Paths::beside_executable(),
+        }
+    }
+
+    fn beside_executable() -> Paths {
+        let root = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+        Paths {
+            config: root.join("config"),
+            data: root.clone(),
+            cache: root.join("cache"),
+        }
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.config.clone()
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join("config.toml")
+    }
+
+    pub fn pattern_dir(&self) -> PathBuf {
+        self.data.join("patterns")
+    }
+
+    pub fn autosave_dir(&self) -> PathBuf {
+        self.data.join("saves")
+    }
+
+    pub fn screenshot_dir(&self) -> PathBuf {
+        self.data.join("screenshots")
+    }
+
+    pub fn catalog_file(&self) -> PathBuf {
+        self.cache.join("patterns.idx")
+    }
+
+    pub fn fetch_dir(&self) -> PathBuf {
+        self.cache.join("lifewiki")
+    }
+
+    pub fn crash_dir(&self) -> PathBuf {
+        self.cache.join("crashes")
+    }
+
+    // Relative pattern paths that don't exist from the working directory
+    // are looked up in the pattern directory
+    pub fn find_pattern(&self, path: &Path) -> PathBuf {
+        if path.is_relative() && !path.exists() {
+            let candidate = self.pattern_dir().join(path);
+            if candidate.exists() {
+                return candidate;
+            }
+        }
+        path.to_path_buf()
+    }
+
+    // A path in dir named prefix-<unix seconds>.extension that isn't taken,
+    // creating dir if needed. Another in the same second gets a -2, -3 and
+    // so on after the seconds.
+    pub fn timestamped(dir: &Path, prefix: &str, extension: &str) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut stamp = secs.to_string();
+        let mut taken = 1;
+        loop {
+            let name = if extension.is_empty() {
+                format!("{}-{}", prefix, stamp)
+            } else {
+                format!("{}-{}.{}", prefix, stamp, extension)
+            };
+            let path = dir.join(name);
+            if !path.exists() {
+                return Ok(path);
+            }
+            taken += 1;
+            stamp = format!("{}-{}", secs, taken);
+        }
+    }
+}
+
+impl std::fmt::Display for Paths {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.data.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamped_paths_are_never_reused() {
+        let dir = std::env::temp_dir().join(format!("gol-timestamped-{}", std::process::id()));
+        let first = Paths::timestamped(&dir, "gol", "gol").expect("Unable to make a path");
+        std::fs::write(&first, "").expect("Unable to write the first file");
+        let second = Paths::timestamped(&dir, "gol", "gol").expect("Unable to make a path");
+        std::fs::write(&second, "").expect("Unable to write the second file");
+        let third = Paths::timestamped(&dir, "gol", "gol").expect("Unable to make a path");
+        std::fs::remove_dir_all(&dir).expect("Unable to tidy up");
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+        assert_ne!(first, third);
+        let extension = |p: &PathBuf| p.extension().map(|e| e.to_os_string());
+        assert_eq!(extension(&second), Some("gol".into()));
+    }
+}
@@ -0,0 +1,64 @@
+use super::life::{LifeBoard, Term};
+use super::rule::Rule;
+use rand::{rngs::StdRng, SeedableRng};
+
+const CAVE_FILL: f64 = 0.45;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Preset {
+    Maze,
+    Cave,
+}
+
+impl Preset {
+    pub fn rule(&self) -> Rule {
+        let rule = match self {
+            Preset::Maze => "B3/S12345",
+            Preset::Cave => "B5678/S45678",
+        };
+        rule.parse().expect("Unable to parse preset rule")
+    }
+
+    pub fn generations(&self) -> usize {
+        match self {
+            Preset::Maze => 200,
+            Preset::Cave => 5,
+        }
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "maze" => Ok(Preset::Maze),
+            "cave" => Ok(Preset::Cave),
+            _ => Err(format!("Unknown preset {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let p = match self {
+            Preset::Maze => "maze",
+            Preset::Cave => "cave",
+        };
+        write!(f, "{}", p)
+    }
+}
+
+pub fn generate_cave(width: i32, height: i32, seed: u64, smoothing_steps: usize) -> LifeBoard {
+    let mut board = LifeBoard::from(Term {
+        w: width,
+        h: height,
+    })
+    .with_rule(Preset::Cave.rule());
+    board.fill_random(&mut StdRng::seed_from_u64(seed), CAVE_FILL);
+
+    for _ in 0..smoothing_steps {
+        board.step();
+    }
+    board
+}
@@ -0,0 +1,396 @@
+use std::io::Write;
+
+use super::automaton::Ca;
+use super::cells::{CellLocation, CellState};
+use super::engine::Engine;
+use super::life::{Anchor, LifeBoard, Neighbourhood, Term, Topology};
+use super::rule::Rule;
+
+pub const REPRO_HEADER: &str = "# gol repro 1";
+
+// What a session needs to be started again the same way
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReproSettings {
+    pub size: Term,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+    pub colonies: u8,
+    pub ca: Ca,
+    pub ants: usize,
+    pub rule: Rule,
+    pub engine: Engine,
+    pub history_length: usize,
+    pub rewind_depth: usize,
+    pub seed: u64,
+    pub density: f64,
+}
+
+impl Default for ReproSettings {
+    fn default() -> Self {
+        ReproSettings {
+            size: Term { w: 100, h: 50 },
+            topology: Topology::default(),
+            neighbourhood: Neighbourhood::default(),
+            colonies: 1,
+            ca: Ca::default(),
+            ants: 1,
+            rule: Rule::default(),
+            engine: Engine::default(),
+            history_length: 4,
+            rewind_depth: 100,
+            seed: 0,
+            density: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    Cell(CellLocation, CellState),
+    View(CellLocation),
+    Resize(Term, Anchor),
+    Back,
+}
+
+// Where a finished session got to, the checksum of its board then and
+// whether it was a panic that finished it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReproEnd {
+    pub generation: u64,
+    pub checksum: u64,
+    pub crashed: bool,
+}
+
+// A repro read back in. The board is only there when it couldn't be made
+// again from the seed.
+pub struct Repro {
+    pub settings: ReproSettings,
+    pub board: Option<LifeBoard>,
+    pub edits: Vec<(u64, Edit)>,
+    pub end: Option<ReproEnd>,
+}
+
+// FNV-1a of the saved board, unlike the std hasher it won't change
+// between builds
+pub fn checksum(board: &LifeBoard) -> u64 {
+    board
+        .encode()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+// A session written down as it runs: what's needed to rebuild the first
+// board, every cell changed by hand or by chaos with the generation it
+// changed at, then where the session ended
+pub struct ReproWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> ReproWriter<W> {
+    // Patterns and loaded boards can't be rebuilt from the seed, so they're
+    // given as board and written out whole
+    pub fn start(
+        mut out: W,
+        settings: &ReproSettings,
+        board: Option<&LifeBoard>,
+    ) -> std::io::Result<ReproWriter<W>> {
+        writeln!(out, "{}", REPRO_HEADER)?;
+        writeln!(out, "size {} {}", settings.size.w, settings.size.h)?;
+        writeln!(out, "topology {}", settings.topology)?;
+        writeln!(out, "neighbourhood {}", settings.neighbourhood)?;
+        writeln!(out, "colonies {}", settings.colonies)?;
+        writeln!(out, "ca {}", settings.ca)?;
+        writeln!(out, "ants {}", settings.ants)?;
+        writeln!(out, "rule {}", settings.rule)?;
+        writeln!(out, "engine {}", settings.engine)?;
+        writeln!(out, "history {}", settings.history_length)?;
+        writeln!(out, "rewind {}", settings.rewind_depth)?;
+        writeln!(out, "seed {}", settings.seed)?;
+        writeln!(out, "density {}", settings.density)?;
+        if let Some(board) = board {
+            let hex: String = board
+                .encode()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            writeln!(out, "board {}", hex)?;
+        }
+        Ok(ReproWriter { out })
+    }
+
+    pub fn edit(
+        &mut self,
+        generation: u64,
+        location: &CellLocation,
+        state: &CellState,
+    ) -> std::io::Result<()> {
+        let state = match state {
+            CellState::Alive => "alive".to_string(),
+            CellState::Dying(stage) => format!("dying-{}", stage),
+            CellState::Wire => "wire".to_string(),
+            CellState::Dead => "dead".to_string(),
+        };
+        writeln!(
+            self.out,
+            "edit {} {} {} {}",
+            generation, location.r, location.c, state
+        )
+    }
+
+    pub fn back(&mut self, generation: u64) -> std::io::Result<()> {
+        writeln!(self.out, "back {}", generation)
+    }
+
+    pub fn resize(&mut self, generation: u64, size: &Term, anchor: Anchor) -> std::io::Result<()> {
+        writeln!(
+            self.out,
+            "resize {} {} {} {}",
+            generation, size.w, size.h, anchor
+        )
+    }
+
+    pub fn view(&mut self, generation: u64, origin: &CellLocation) -> std::io::Result<()> {
+        writeln!(self.out, "view {} {} {}", generation, origin.r, origin.c)
+    }
+
+    pub fn finish(
+        &mut self,
+        generation: u64,
+        board: &LifeBoard,
+        crashed: bool,
+    ) -> std::io::Result<()> {
+        let crashed = if crashed { " crashed" } else { "" };
+        writeln!(
+            self.out,
+            "end {} {:016x}{}",
+            generation,
+            checksum(board),
+            crashed
+        )?;
+        self.out.flush()
+    }
+}
+
+impl Repro {
+    pub fn from_text(text: &str) -> Result<Repro, String> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, l)| l) != Some(REPRO_HEADER) {
+            return Err("It's not a repro".to_string());
+        }
+
+        let mut repro = Repro {
+            settings: ReproSettings::default(),
+            board: None,
+            edits: Vec::new(),
+            end: None,
+        };
+        for (n, line) in lines {
+            let bad_line = || format!("Unable to read line {} of the repro: {}", n + 1, line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let settings = &mut repro.settings;
+            match fields[..] {
+                ["size", w, h] => {
+                    settings.size = Term {
+                        w: w.parse().map_err(|_| bad_line())?,
+                        h: h.parse().map_err(|_| bad_line())?,
+                    }
+                }
+                ["topology", topology] => settings.topology = topology.parse()?,
+                ["neighbourhood", neighbourhood] => {
+                    settings.neighbourhood = neighbourhood.parse()?
+                }
+                ["ca", ca] => settings.ca = ca.parse()?,
+                ["ants", ants] => settings.ants = ants.parse().map_err(|_| bad_line())?,
+                ["colonies", colonies] => {
+                    settings.colonies = colonies.parse().map_err(|_| bad_line())?
+                }
+                ["rule", rule] => settings.rule = rule.parse()?,
+                ["engine", engine] => settings.engine = engine.parse()?,
+                ["history", history] => {
+                    settings.history_length = history.parse().map_err(|_| bad_line())?
+                }
+                ["seed", seed] => settings.seed = seed.parse().map_err(|_| bad_line())?,
+                ["density", density] => {
+                    settings.density = density.parse().map_err(|_| bad_line())?
+                }
+                ["board", hex] => {
+                    let bytes = (0..hex.len())
+                        .step_by(2)
+                        .map(|i| {
+                            hex.get(i..i + 2)
+                                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                                .ok_or_else(bad_line)
+                        })
+                        .collect::<Result<Vec<u8>, String>>()?;
+                    repro.board = Some(LifeBoard::decode(&bytes)?);
+                }
+                ["edit", generation, r, c, state] => {
+                    let state = match (state, state.strip_prefix("dying-")) {
+                        ("alive", _) => CellState::Alive,
+                        ("dead", _) => CellState::Dead,
+                        ("wire", _) => CellState::Wire,
+                        (_, Some(stage)) => {
+                            CellState::Dying(stage.parse().map_err(|_| bad_line())?)
+                        }
+                        _ => return Err(bad_line()),
+                    };
+                    repro.edits.push((
+                        generation.parse().map_err(|_| bad_line())?,
+                        Edit::Cell(
+                            CellLocation {
+                                r: r.parse().map_err(|_| bad_line())?,
+                                c: c.parse().map_err(|_| bad_line())?,
+                            },
+                            state,
+                        ),
+                    ));
+                }
+                ["rewind", depth] => {
+                    settings.rewind_depth = depth.parse().map_err(|_| bad_line())?
+                }
+                ["back", generation] => repro
+                    .edits
+                    .push((generation.parse().map_err(|_| bad_line())?, Edit::Back)),
+                ["view", generation, r, c] => {
+                    repro.edits.push((
+                        generation.parse().map_err(|_| bad_line())?,
+                        Edit::View(CellLocation {
+                            r: r.parse().map_err(|_| bad_line())?,
+                            c: c.parse().map_err(|_| bad_line())?,
+                        }),
+                    ));
+                }
+                ["resize", generation, w, h, anchor] => {
+                    repro.edits.push((
+                        generation.parse().map_err(|_| bad_line())?,
+                        Edit::Resize(
+                            Term {
+                                w: w.parse().map_err(|_| bad_line())?,
+                                h: h.parse().map_err(|_| bad_line())?,
+                            },
+                            anchor.parse()?,
+                        ),
+                    ));
+                }
+                ["end", generation, sum] | ["end", generation, sum, "crashed"] => {
+                    repro.end = Some(ReproEnd {
+                        generation: generation.parse().map_err(|_| bad_line())?,
+                        checksum: u64::from_str_radix(sum, 16).map_err(|_| bad_line())?,
+                        crashed: fields.len() == 4,
+                    });
+                }
+                _ => return Err(bad_line()),
+            }
+        }
+        Ok(repro)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider_board() -> LifeBoard {
+        let mut board = LifeBoard::from(Term { w: 12, h: 10 }).with_topology(Topology::Torus);
+        board.clear();
+        for (r, c) in [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            board.set_state(&CellLocation { r, c }, CellState::Alive);
+        }
+        board
+    }
+
+    #[test]
+    fn a_written_repro_reads_back_the_same() {
+        let settings = ReproSettings {
+            size: Term { w: 12, h: 10 },
+            topology: Topology::Torus,
+            rule: "B36/S23".parse().expect("Unable to parse the rule"),
+            engine: Engine::Sparse,
+            seed: 7,
+            density: 0.25,
+            ..ReproSettings::default()
+        };
+        let board = glider_board();
+        let edits = vec![
+            (3, Edit::Cell(CellLocation { r: 4, c: 5 }, CellState::Alive)),
+            (
+                3,
+                Edit::Cell(CellLocation { r: 4, c: 6 }, CellState::Dying(2)),
+            ),
+            (5, Edit::View(CellLocation { r: -2, c: 3 })),
+            (6, Edit::Back),
+            (6, Edit::Resize(Term { w: 20, h: 8 }, Anchor::default())),
+        ];
+
+        let mut out = Vec::new();
+        let mut writer =
+            ReproWriter::start(&mut out, &settings, Some(&board)).expect("Unable to start");
+        for (generation, edit) in &edits {
+            match edit {
+                Edit::Cell(location, state) => writer.edit(*generation, location, state),
+                Edit::View(origin) => writer.view(*generation, origin),
+                Edit::Resize(size, anchor) => writer.resize(*generation, size, *anchor),
+                Edit::Back => writer.back(*generation),
+            }
+            .expect("Unable to write an edit");
+        }
+        writer.finish(9, &board, true).expect("Unable to finish");
+
+        let text = String::from_utf8(out).expect("The repro isn't text");
+        let repro = Repro::from_text(&text).expect("Unable to read the repro back");
+        assert_eq!(repro.settings, settings);
+        assert_eq!(repro.board.map(|b| b.encode()), Some(board.encode()));
+        assert_eq!(repro.edits, edits);
+        assert_eq!(
+            repro.end,
+            Some(ReproEnd {
+                generation: 9,
+                checksum: checksum(&board),
+                crashed: true,
+            })
+        );
+    }
+
+    #[test]
+    fn replaying_a_repro_ends_on_the_board_it_recorded() {
+        let mut board = glider_board();
+        let mut out = Vec::new();
+        let mut writer = ReproWriter::start(&mut out, &ReproSettings::default(), Some(&board))
+            .expect("Unable to start");
+        for generation in 0..8 {
+            if generation == 4 {
+                let location = CellLocation { r: 8, c: 8 };
+                board.set_state(&location, CellState::Alive);
+                writer
+                    .edit(generation, &location, &CellState::Alive)
+                    .expect("Unable to write an edit");
+            }
+            board.step();
+        }
+        writer.finish(8, &board, false).expect("Unable to finish");
+
+        let repro = Repro::from_text(&String::from_utf8(out).expect("The repro isn't text"))
+            .expect("Unable to read the repro back");
+        let mut replayed = repro.board.expect("The board wasn't written");
+        let mut edits = repro.edits.iter().peekable();
+        for generation in 0..8 {
+            while let Some((_, Edit::Cell(location, state))) =
+                edits.next_if(|(g, _)| *g <= generation)
+            {
+                replayed.set_state(location, state.clone());
+            }
+            replayed.step();
+        }
+        let end = repro.end.expect("The repro has no end");
+        assert_eq!(end.generation, 8);
+        assert_eq!(checksum(&replayed), end.checksum);
+    }
+
+    #[test]
+    fn other_files_are_not_repros() {
+        assert!(Repro::from_text("x = 3, y = 1\n3o!").is_err());
+        assert!(Repro::from_text(&format!("{}\nsize three 4", REPRO_HEADER)).is_err());
+    }
+}
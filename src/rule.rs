@@ -0,0 +1,141 @@
+use super::cells::CellState;
+
+// Dying states are kept in a byte per cell
+const MAX_STATES: u8 = 255;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rule {
+    birth: [bool; 9],
+    survive: [bool; 9],
+    // Generations rules have cells that stop surviving decay through
+    // states - 2 dying states before they are dead, life-like rules have 2
+    states: u8,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        "B3/S23".parse().expect("Unable to parse the default rule")
+    }
+}
+
+impl Rule {
+    // Electron heads are alive and tails are the one dying state, so on a
+    // board that only allows births on its wires this is WireWorld
+    pub fn wireworld() -> Rule {
+        "B12/S/C3"
+            .parse()
+            .expect("Unable to parse the WireWorld rule")
+    }
+
+    pub fn is_born(&self, neighbours: u8) -> bool {
+        self.birth[neighbours as usize]
+    }
+
+    pub fn survives(&self, neighbours: u8) -> bool {
+        self.survive[neighbours as usize]
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn is_generations(&self) -> bool {
+        self.states > 2
+    }
+
+    // How brightly to draw a cell, dying cells fading out the further
+    // through their decay they are
+    pub fn brightness(&self, state: &CellState) -> f32 {
+        match state {
+            CellState::Alive => 1.0,
+            CellState::Dying(stage) => 1.0 - *stage as f32 / (self.states - 1) as f32,
+            CellState::Wire | CellState::Dead => 0.0,
+        }
+    }
+}
+
+fn parse_digits(digits: &str) -> Result<[bool; 9], String> {
+    let mut counts = [false; 9];
+    for d in digits.chars() {
+        let n = d
+            .to_digit(10)
+            .filter(|n| *n <= 8)
+            .ok_or_else(|| format!("{} is not a neighbour count between 0 and 8", d))?;
+        counts[n as usize] = true;
+    }
+    Ok(counts)
+}
+
+fn parse_states(part: &str) -> Result<u8, String> {
+    let digits = part.trim_start_matches(['C', 'c', 'G', 'g']);
+    match digits.parse() {
+        Ok(states) if (2..=MAX_STATES).contains(&states) => Ok(states),
+        _ => Err(format!(
+            "{} is not a number of states between 2 and {}",
+            part, MAX_STATES
+        )),
+    }
+}
+
+fn parse_counts(part: &str, prefix: char) -> Result<[bool; 9], String> {
+    let digits = part
+        .strip_prefix(prefix)
+        .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+        .ok_or_else(|| format!("Expected {} to start with {}", part, prefix))?;
+    parse_digits(digits)
+}
+
+impl std::str::FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split('/').collect();
+        // Generations rules are either Bx/Sy/Cz or, without the letters,
+        // survival first as y/x/z
+        if parts.len() == 3 {
+            let states = parse_states(parts[2])?;
+            let (birth, survive) = if parts[0].to_uppercase().starts_with('B') {
+                (parse_counts(parts[0], 'B')?, parse_counts(parts[1], 'S')?)
+            } else {
+                (parse_digits(parts[1])?, parse_digits(parts[0])?)
+            };
+            return Ok(Rule {
+                birth,
+                survive,
+                states,
+            });
+        }
+        if parts.len() != 2 {
+            return Err(format!("Rule {} must be in the form Bx/Sy or Bx/Sy/Cz", s));
+        }
+
+        let (b, s) = if parts[0].to_uppercase().starts_with('S') {
+            (parts[1], parts[0])
+        } else {
+            (parts[0], parts[1])
+        };
+
+        Ok(Rule {
+            birth: parse_counts(b, 'B')?,
+            survive: parse_counts(s, 'S')?,
+            states: 2,
+        })
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "B")?;
+        for n in (0..9).filter(|n| self.birth[*n]) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..9).filter(|n| self.survive[*n]) {
+            write!(f, "{}", n)?;
+        }
+        if self.is_generations() {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
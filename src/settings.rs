@@ -0,0 +1,423 @@
+use std::fmt;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::ant::{Ant, Heading};
+use super::automaton::Ca;
+use super::cells::CellLocation;
+use super::compositor::Stereo;
+use super::config::{Config, ConfigValue};
+use super::engine::Engine;
+use super::error::GameOfLifeError;
+use super::fade::Fade;
+use super::keymap::Action;
+use super::life::{Anchor, Edges, LifeBoard, Neighbourhood, StampMode, Term, Topology};
+use super::pattern::Pattern;
+use super::platform::Paths;
+use super::rule::Rule;
+use super::theme::{Grid, Theme};
+use super::tracks::Tracks;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Renderer {
+    Sdl,
+    Terminal,
+    Tui,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        if cfg!(feature = "sdl") {
+            Renderer::Sdl
+        } else {
+            Renderer::Terminal
+        }
+    }
+}
+
+impl std::str::FromStr for Renderer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sdl" => Ok(Renderer::Sdl),
+            "terminal" => Ok(Renderer::Terminal),
+            "tui" => Ok(Renderer::Tui),
+            _ => Err(format!("Unknown renderer {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Renderer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let r = match self {
+            Renderer::Sdl => "sdl",
+            Renderer::Terminal => "terminal",
+            Renderer::Tui => "tui",
+        };
+        write!(f, "{}", r)
+    }
+}
+
+#[derive(Clone)]
+pub struct GUISettings {
+    pub history_length: usize,
+    pub generations_per_second: f32,
+    pub adaptive_speed: Option<(f32, f32)>,
+    pub cell_edge: i32,
+    pub board_height: i32,
+    pub board_width: i32,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+    pub colonies: u8,
+    pub ca: Ca,
+    pub ants: usize,
+    pub rule: Rule,
+    pub engine: Engine,
+    pub pattern: Option<Pattern>,
+    pub renderer: Renderer,
+    pub paths: Paths,
+    pub chaos_interval: Option<u64>,
+    pub seed: u64,
+    pub density: f64,
+    pub record_path: Option<String>,
+    pub record_every: u64,
+    pub snapshot_cell_size: u32,
+    pub stereo: Stereo,
+    pub theme: Theme,
+    pub grid: Grid,
+    pub tracks: Tracks,
+    // What stays put when the board is grown or shrunk while running
+    pub resize_anchor: Anchor,
+    pub fade: Option<Fade>,
+    pub colour_by_age: bool,
+    // How many generations the heatmap counts over, if it's shown
+    pub heatmap: Option<usize>,
+    // Keys moved off their defaults, in the order given
+    pub bindings: Vec<(Action, String)>,
+    pub repro_path: Option<String>,
+    // Where a row of numbers is added for every generation
+    pub stats_path: Option<String>,
+    pub stop_on_stable: bool,
+    pub rewind_depth: usize,
+    // How far Shift+Right jumps ahead
+    pub skip: usize,
+    // Set to run a second board beside the first
+    pub compare_rule: Option<Rule>,
+    pub compare_engine: Option<Engine>,
+}
+
+impl Default for GUISettings {
+    fn default() -> Self {
+        GUISettings {
+            history_length: 4,
+            generations_per_second: 20.0,
+            adaptive_speed: None,
+            cell_edge: 10,
+            board_height: 50,
+            board_width: 100,
+            topology: Topology::default(),
+            neighbourhood: Neighbourhood::default(),
+            colonies: 1,
+            ca: Ca::default(),
+            ants: 1,
+            rule: Rule::default(),
+            engine: Engine::default(),
+            pattern: None,
+            renderer: Renderer::default(),
+            paths: Paths::new(false),
+            chaos_interval: None,
+            seed: 0,
+            density: 0.5,
+            record_path: None,
+            record_every: 1,
+            snapshot_cell_size: 10,
+            stereo: Stereo::default(),
+            theme: Theme::default(),
+            grid: Grid::default(),
+            tracks: Tracks::default(),
+            resize_anchor: Anchor::default(),
+            fade: None,
+            colour_by_age: false,
+            heatmap: None,
+            bindings: Vec::new(),
+            repro_path: None,
+            stats_path: None,
+            stop_on_stable: false,
+            rewind_depth: 100,
+            skip: 100,
+            compare_rule: None,
+            compare_engine: None,
+        }
+    }
+}
+
+impl GUISettings {
+    // The same as --bind takes them
+    pub fn bindings_text(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(action, key)| format!("{}={}", action, key))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // What --dump-config prints, one entry per option so it can be used as
+    // the config file. Where the pattern comes from and where recordings go
+    // change from run to run so are left out.
+    pub fn config(&self) -> Config {
+        let text = |v: &dyn fmt::Display| ConfigValue::Text(v.to_string());
+        let number = |v: &dyn fmt::Display| ConfigValue::Number(v.to_string());
+        let mut entries = vec![
+            ("renderer", text(&self.renderer)),
+            ("bwidth", number(&self.board_width)),
+            ("bheight", number(&self.board_height)),
+            ("edge", number(&self.cell_edge)),
+            ("topology", text(&self.topology)),
+            ("neighbourhood", text(&self.neighbourhood)),
+            ("rule", text(&self.rule)),
+            ("ca", text(&self.ca)),
+            ("ants", number(&self.ants)),
+            ("colonies", number(&self.colonies)),
+            ("engine", text(&self.engine)),
+            ("seed", number(&self.seed)),
+            ("density", number(&self.density)),
+            ("fps", number(&self.generations_per_second)),
+            ("history", number(&self.history_length)),
+            ("rewind", number(&self.rewind_depth)),
+            ("skip", number(&self.skip)),
+            ("stereo", text(&self.stereo)),
+            ("theme", text(&self.theme)),
+            ("grid", text(&self.grid)),
+            ("tracks", text(&self.tracks)),
+            ("resize-anchor", text(&self.resize_anchor)),
+            ("colour-by-age", ConfigValue::Bool(self.colour_by_age)),
+            ("stop-on-stable", ConfigValue::Bool(self.stop_on_stable)),
+            ("record-every", number(&self.record_every)),
+            ("snapshot-cell-size", number(&self.snapshot_cell_size)),
+        ];
+        if let Some((slowest, fastest)) = self.adaptive_speed {
+            entries.push((
+                "adaptive-speed",
+                ConfigValue::List(vec![number(&slowest), number(&fastest)]),
+            ));
+        }
+        if let Some(fade) = self.fade {
+            entries.push(("fade", text(&fade)));
+        }
+        if let Some(generations) = self.heatmap {
+            entries.push(("heatmap", number(&generations)));
+        }
+        if let Some(interval) = self.chaos_interval {
+            entries.push(("chaos-interval", number(&interval)));
+        }
+        if !self.bindings.is_empty() {
+            entries.push(("bind", text(&self.bindings_text())));
+        }
+        if let Some(rule) = &self.compare_rule {
+            entries.push(("compare-rule", text(rule)));
+        }
+        if let Some(engine) = self.compare_engine {
+            entries.push(("compare-engine", text(&engine)));
+        }
+        Config {
+            entries: entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for GUISettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let off = || "off".to_string();
+        let entries = [
+            ("History Length", self.history_length.to_string()),
+            ("Speed", format!("{} gen/s", self.generations_per_second)),
+            (
+                "Adaptive Speed",
+                self.adaptive_speed
+                    .map_or_else(off, |(lo, hi)| format!("{} to {} gen/s", lo, hi)),
+            ),
+            ("Cell Edge", self.cell_edge.to_string()),
+            ("Board Height", self.board_height.to_string()),
+            ("Board Width", self.board_width.to_string()),
+            ("Topology", self.topology.to_string()),
+            ("Neighbourhood", self.neighbourhood.to_string()),
+            ("Colonies", self.colonies.to_string()),
+            ("Automaton", self.ca.to_string()),
+            ("Ants", self.ants.to_string()),
+            ("Rule", self.rule.to_string()),
+            ("Engine", self.engine.to_string()),
+            (
+                "Pattern",
+                self.pattern
+                    .as_ref()
+                    .map_or("random".to_string(), |p| p.to_string()),
+            ),
+            ("Renderer", self.renderer.to_string()),
+            ("Data", self.paths.to_string()),
+            (
+                "Chaos Interval",
+                self.chaos_interval.map_or_else(off, |n| n.to_string()),
+            ),
+            ("Seed", self.seed.to_string()),
+            ("Density", self.density.to_string()),
+            (
+                "Record",
+                self.record_path.as_ref().map_or_else(off, |p| {
+                    format!("{} every {} generations", p, self.record_every)
+                }),
+            ),
+            ("Snapshot Cell Size", self.snapshot_cell_size.to_string()),
+            ("Stereo", self.stereo.to_string()),
+            ("Theme", self.theme.to_string()),
+            ("Grid", self.grid.to_string()),
+            ("Tracks", self.tracks.to_string()),
+            ("Resize Anchor", self.resize_anchor.to_string()),
+            (
+                "Fade",
+                self.fade.map_or("theme".to_string(), |f| f.to_string()),
+            ),
+            ("Colour By Age", self.colour_by_age.to_string()),
+            (
+                "Heatmap",
+                self.heatmap
+                    .map_or_else(off, |n| format!("{} generations", n)),
+            ),
+            (
+                "Bindings",
+                if self.bindings.is_empty() {
+                    "default".to_string()
+                } else {
+                    self.bindings_text()
+                },
+            ),
+            ("Repro", self.repro_path.clone().unwrap_or_else(off)),
+            ("Stats", self.stats_path.clone().unwrap_or_else(off)),
+            ("Stop On Stable", self.stop_on_stable.to_string()),
+            ("Rewind", format!("{} generations", self.rewind_depth)),
+            ("Skip", format!("{} generations", self.skip)),
+            (
+                "Compare",
+                match (&self.compare_rule, self.compare_engine) {
+                    (None, None) => off(),
+                    (Some(rule), None) => format!("rule {}", rule),
+                    (None, Some(engine)) => format!("engine {}", engine),
+                    (Some(rule), Some(engine)) => format!("rule {} on engine {}", rule, engine),
+                },
+            ),
+        ];
+        for (i, (name, value)) in entries.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(f, "{}{} = {}", separator, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn random_board(settings: &GUISettings) -> LifeBoard {
+    let mut lb = LifeBoard::from(Term {
+        w: settings.board_width,
+        h: settings.board_height,
+    })
+    .with_topology(settings.topology)
+    .with_neighbourhood(settings.neighbourhood)
+    .with_colonies(settings.colonies)
+    .with_rule(settings.rule.clone());
+    match settings.ca {
+        // There's no such thing as a random circuit so it starts empty
+        Ca::WireWorld => {
+            lb = lb.with_wires();
+            lb.clear();
+        }
+        Ca::Ant => {
+            lb.clear();
+            lb = lb.with_ants(starting_ants(settings));
+        }
+        Ca::Life => lb.randomize_with_seed(settings.seed, settings.density),
+    }
+    lb
+}
+
+// The first ant starts in the middle facing north, any others wherever the
+// seed puts them
+fn starting_ants(settings: &GUISettings) -> Vec<Ant> {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let first = Ant {
+        location: CellLocation {
+            r: settings.board_height / 2,
+            c: settings.board_width / 2,
+        },
+        heading: Heading::North,
+    };
+    let others = (1..settings.ants).map(|_| Ant {
+        location: CellLocation {
+            r: rng.gen_range(0, settings.board_height),
+            c: rng.gen_range(0, settings.board_width),
+        },
+        heading: Heading::from_index(rng.gen_range(0, 4)).unwrap_or_default(),
+    });
+    std::iter::once(first).chain(others).collect()
+}
+
+// Where a pattern goes to be in the middle of the board
+pub fn centre(settings: &GUISettings, pattern: &Pattern) -> CellLocation {
+    CellLocation {
+        r: (settings.board_height - pattern.height) / 2,
+        c: (settings.board_width - pattern.width) / 2,
+    }
+}
+
+pub fn new_board(settings: &GUISettings) -> Result<LifeBoard, GameOfLifeError> {
+    let mut lb = random_board(settings);
+    match (&settings.pattern, settings.ca) {
+        // On an infinite board one too big for the view goes straight into
+        // the engine when the simulation starts
+        (Some(pattern), _) => {
+            lb.clear();
+            if lb
+                .stamp(
+                    pattern,
+                    centre(settings, pattern),
+                    StampMode::Or,
+                    Edges::Reject,
+                )
+                .is_none()
+                && settings.topology != Topology::Infinite
+            {
+                return Err(GameOfLifeError::Pattern(format!(
+                    "The {}x{} pattern doesn't fit on the {}x{} board",
+                    pattern.width, pattern.height, settings.board_width, settings.board_height
+                )));
+            }
+        }
+        // Something to watch, left empty on a board too small for it
+        (None, Ca::WireWorld) => {
+            let circuit = Pattern::circuit();
+            lb.stamp(
+                &circuit,
+                centre(settings, &circuit),
+                StampMode::Or,
+                Edges::Reject,
+            );
+        }
+        (None, Ca::Life) | (None, Ca::Ant) => {}
+    }
+    Ok(lb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_settings_are_listed_in_one_line() {
+        let listed = GUISettings::default().to_string();
+        assert!(!listed.contains('\n'));
+        assert!(listed.starts_with("History Length = "));
+        assert!(listed.contains(", Bindings = default, "));
+        assert!(listed.contains(", Repro = off, "));
+    }
+}
@@ -0,0 +1,942 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::cells::{CellLocation, CellState};
+#[cfg(feature = "record")]
+use super::compositor::Compositor;
+use super::cycle::{Cycle, CycleDetector};
+use super::engine::{Engine, Universe};
+use super::error::GameOfLifeError;
+#[cfg(feature = "record")]
+use super::export;
+use super::fade::{ColorScheme, Fade};
+#[cfg(feature = "gpu")]
+use super::gpu::GpuLife;
+use super::hashlife::HashLife;
+use super::heatmap::Heatmap;
+use super::life::{Anchor, Edges, LifeBoard, StampMode, Term, Topology};
+use super::pattern::Pattern;
+use super::repro::{ReproSettings, ReproWriter};
+use super::settings::{centre, random_board, GUISettings};
+use super::sparse::SparseLife;
+use super::stats::StatsWriter;
+use super::theme::Theme;
+use super::tracks::{ShipTracker, Track, Tracks};
+use super::undo::UndoStack;
+
+// The longest trails there can be
+pub const MAX_HISTORY: usize = 20;
+const CHAOS_ATTEMPTS: usize = 100;
+const STABLE_WINDOW: usize = 64;
+// What the heatmap counts over if the settings didn't say
+const DEFAULT_HEATMAP: usize = 100;
+// Growing or shrinking the board changes each side by this much of it
+const RESIZE_STEPS: i32 = 8;
+// How many generations of a ship's trail are kept
+const TRACK_LENGTH: usize = 64;
+
+// A board as it's run for a window, the terminal or a replay: the engine
+// stepping it, what it's been and can go back to, and everything recorded
+// as it goes
+pub struct Simulation {
+    pub board: LifeBoard,
+    pub universe: Option<Universe>,
+    // Where the top left of the board is in the universe, only ever moved
+    // on an infinite board
+    pub origin: CellLocation,
+    cycles: Option<CycleDetector>,
+    pub stable: Option<Cycle>,
+    // Boards to go back to, oldest first, and the ones gone back past,
+    // newest first
+    past: VecDeque<(u64, LifeBoard)>,
+    future: Vec<(u64, LifeBoard)>,
+    rewind_depth: usize,
+    pub history: VecDeque<LifeBoard>,
+    // How many boards are kept in history for the trails, and the colours
+    // they're drawn in, all of which can be changed while running
+    pub history_length: usize,
+    pub theme: Theme,
+    pub fade: Option<Fade>,
+    pub by_age: bool,
+    pub grey_settled: bool,
+    // Only made once ships are first tracked
+    pub tracks: Tracks,
+    tracker: Option<ShipTracker>,
+    // Drawn in place of the cells while it's on
+    pub heatmap: Option<Heatmap>,
+    pub heatmap_generations: usize,
+    pub generation: u64,
+    pub births: usize,
+    pub deaths: usize,
+    #[cfg(feature = "record")]
+    recording: Option<Recording>,
+    repro: Option<ReproWriter<std::fs::File>>,
+    stats: Option<StatsWriter>,
+    // What's been changed by hand, for undoing
+    pub edits: UndoStack,
+    // Why the repro, stats or recording were given up on, for the frontend
+    // to show
+    problems: Vec<String>,
+}
+
+// A repro that wasn't finished, because the session panicked or gave up
+// with an error, is still closed off here as long as the panic unwinds.
+// There's no frontend left to tell if that fails.
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        if let Some(repro) = &mut self.repro {
+            let crashed = std::thread::panicking();
+            let _ = repro.finish(self.generation, &self.board, crashed);
+        }
+    }
+}
+
+fn start_repro(
+    path: &str,
+    board: &LifeBoard,
+    settings: &GUISettings,
+) -> Result<ReproWriter<std::fs::File>, GameOfLifeError> {
+    let repro = ReproSettings {
+        size: Term {
+            w: board.width(),
+            h: board.height(),
+        },
+        topology: board.topology(),
+        neighbourhood: board.neighbourhood(),
+        colonies: board.colonies(),
+        ca: settings.ca,
+        ants: settings.ants,
+        rule: board.rule().clone(),
+        engine: settings.engine,
+        history_length: settings.history_length,
+        rewind_depth: settings.rewind_depth,
+        seed: settings.seed,
+        density: settings.density,
+    };
+    // Patterns and loaded boards can't be rebuilt from the seed
+    let is_random = random_board(settings).encode() == board.encode();
+    std::fs::File::create(path)
+        .and_then(|out| ReproWriter::start(out, &repro, Some(board).filter(|_| !is_random)))
+        .map_err(GameOfLifeError::io("start the repro"))
+}
+
+#[cfg(feature = "record")]
+struct Recording {
+    gif: export::GifRecorder<std::io::BufWriter<std::fs::File>>,
+    compositor: Compositor,
+    every: u64,
+}
+
+#[cfg(feature = "record")]
+impl Recording {
+    fn start(
+        path: &str,
+        board: &LifeBoard,
+        settings: &GUISettings,
+    ) -> Result<Recording, GameOfLifeError> {
+        let mut compositor = Compositor::default();
+        compositor.cell_size = settings.cell_edge as u32;
+        compositor.stereo = settings.stereo;
+        compositor.background = settings.theme.background;
+        compositor.alive = settings.theme.alive;
+        compositor.fade = settings.fade;
+        compositor.by_age = settings.colour_by_age;
+        let (width, height) = compositor.image_size(board);
+        let file =
+            std::fs::File::create(path).map_err(GameOfLifeError::io("create the recording"))?;
+        let frame_ms =
+            (settings.record_every as f32 * 1000.0 / settings.generations_per_second) as u32;
+        let gif = export::GifRecorder::new(std::io::BufWriter::new(file), width, height, frame_ms)
+            .map_err(GameOfLifeError::io("start the recording"))?;
+        Ok(Recording {
+            gif,
+            compositor,
+            every: settings.record_every,
+        })
+    }
+
+    fn capture(&mut self, history: &VecDeque<LifeBoard>, generation: u64) -> std::io::Result<()> {
+        if generation.is_multiple_of(self.every) {
+            self.gif
+                .push(&self.compositor.compose(history, generation))?;
+        }
+        Ok(())
+    }
+}
+
+impl Simulation {
+    pub fn new(
+        mut board: LifeBoard,
+        settings: &GUISettings,
+    ) -> Result<Simulation, GameOfLifeError> {
+        // Recordings are coloured by age too, with or without a window
+        board.track_history(settings.colour_by_age);
+        #[cfg(feature = "record")]
+        let recording = match &settings.record_path {
+            Some(path) => {
+                let mut recording = Recording::start(path, &board, settings)?;
+                let mut history = VecDeque::new();
+                history.push_back(board.clone());
+                recording
+                    .capture(&history, board.iteration() as u64)
+                    .map_err(GameOfLifeError::io("record the first frame"))?;
+                Some(recording)
+            }
+            None => None,
+        };
+
+        let universe = match settings.engine {
+            Engine::Naive => None,
+            Engine::HashLife => Some(Universe::HashLife(
+                HashLife::from_board(&board).map_err(GameOfLifeError::Engine)?,
+            )),
+            Engine::Sparse => Some(Universe::Sparse(
+                SparseLife::from_board(&board).map_err(GameOfLifeError::Engine)?,
+            )),
+            #[cfg(feature = "gpu")]
+            Engine::Gpu => Some(Universe::Gpu(
+                GpuLife::from_board(&board).map_err(GameOfLifeError::Engine)?,
+            )),
+            #[cfg(not(feature = "gpu"))]
+            Engine::Gpu => {
+                return Err(GameOfLifeError::Unsupported(
+                    "GPU engine, rebuild with --features gpu",
+                ))
+            }
+        };
+
+        let repro = match &settings.repro_path {
+            Some(path) => Some(start_repro(path, &board, settings)?),
+            None => None,
+        };
+
+        let stats = match &settings.stats_path {
+            Some(path) => {
+                Some(StatsWriter::start(path).map_err(GameOfLifeError::io("start the stats"))?)
+            }
+            None => None,
+        };
+
+        let cycles = if settings.stop_on_stable {
+            let mut cycles = CycleDetector::new(STABLE_WINDOW);
+            cycles.observe(board.fingerprint(), board.iteration() as u64);
+            Some(cycles)
+        } else {
+            None
+        };
+
+        let tracker = match settings.tracks {
+            Tracks::Off => None,
+            _ => Some(ShipTracker::new(&board, TRACK_LENGTH).map_err(GameOfLifeError::Config)?),
+        };
+
+        let mut history = VecDeque::new();
+        history.push_front(board.clone());
+        let mut sim = Simulation {
+            generation: board.iteration() as u64,
+            board,
+            universe,
+            origin: CellLocation::default(),
+            cycles,
+            stable: None,
+            past: VecDeque::new(),
+            future: Vec::new(),
+            rewind_depth: settings.rewind_depth,
+            history,
+            history_length: settings.history_length,
+            theme: settings.theme,
+            fade: settings.fade,
+            by_age: settings.colour_by_age,
+            grey_settled: false,
+            tracks: settings.tracks,
+            tracker,
+            heatmap: None,
+            heatmap_generations: settings.heatmap.unwrap_or(DEFAULT_HEATMAP),
+            births: 0,
+            deaths: 0,
+            #[cfg(feature = "record")]
+            recording,
+            repro,
+            stats,
+            edits: UndoStack::default(),
+            problems: Vec::new(),
+        };
+        if let Some(pattern) = settings
+            .pattern
+            .as_ref()
+            .filter(|p| p.width > sim.board.width() || p.height > sim.board.height())
+        {
+            sim.place(pattern, centre(settings, pattern));
+        }
+        if settings.heatmap.is_some() {
+            sim.toggle_heatmap();
+        }
+        Ok(sim)
+    }
+
+    // Puts a pattern too big for the board into the engine all at once,
+    // the board then shows the middle of it
+    fn place(&mut self, pattern: &Pattern, top_left: CellLocation) {
+        self.write_repro(|repro, generation| {
+            pattern
+                .cells
+                .iter()
+                .try_for_each(|cell| repro.edit(generation, &(&top_left + cell), &CellState::Alive))
+        });
+        if let Some(universe) = &mut self.universe {
+            let origin = &top_left + &self.origin;
+            let cells: Vec<CellLocation> = pattern.cells.iter().map(|c| &origin + c).collect();
+            universe.insert_cells(&cells);
+            universe.write_window(&mut self.board, &self.origin);
+        }
+        if let Some(latest) = self.history.back_mut() {
+            *latest = self.board.clone();
+        }
+    }
+
+    // Like the stats, a repro that can't be written to is given up on
+    // rather than stopping the session
+    fn write_repro(
+        &mut self,
+        write: impl FnOnce(&mut ReproWriter<std::fs::File>, u64) -> std::io::Result<()>,
+    ) {
+        if let Some(repro) = &mut self.repro {
+            if let Err(e) = write(repro, self.generation) {
+                self.problems.push(format!(
+                    "Unable to write to the repro, no more will be: {}",
+                    e
+                ));
+                self.repro = None;
+            }
+        }
+    }
+
+    // A board that was changed by hand may come back round without being
+    // in a cycle
+    fn forget_cycles(&mut self) {
+        if let Some(cycles) = &mut self.cycles {
+            *cycles = CycleDetector::new(STABLE_WINDOW);
+        }
+    }
+
+    pub fn paint(&mut self, location: &CellLocation, state: CellState) {
+        self.forget_cycles();
+        self.future.clear();
+        self.write_repro(|repro, generation| repro.edit(generation, location, &state));
+        if let Some(universe) = &mut self.universe {
+            universe.set_state(&(location + &self.origin), state.clone());
+        }
+        self.board.set_state(location, state);
+        if let Some(latest) = self.history.back_mut() {
+            *latest = self.board.clone();
+        }
+    }
+
+    pub fn stamp(&mut self, pattern: &Pattern, top_left: CellLocation) -> Option<CellLocation> {
+        // Or and Reject so the engine and the repro log see the same cells
+        let top_left = self
+            .board
+            .stamp(pattern, top_left, StampMode::Or, Edges::Reject)?;
+        self.forget_cycles();
+        self.future.clear();
+        if let Some(universe) = &mut self.universe {
+            for (cell, state) in pattern.states() {
+                universe.set_state(&(&(&top_left + cell) + &self.origin), state);
+            }
+        }
+        self.write_repro(|repro, generation| {
+            pattern
+                .states()
+                .try_for_each(|(cell, state)| repro.edit(generation, &(&top_left + cell), &state))
+        });
+        if let Some(latest) = self.history.back_mut() {
+            *latest = self.board.clone();
+        }
+        Some(top_left)
+    }
+
+    // Painting that can be undone, the stroke is closed off with
+    // edits.finish() when the button goes up
+    pub fn paint_by_hand(&mut self, location: &CellLocation, state: CellState) {
+        if let Some(before) = self.board.state(location) {
+            self.paint(location, state);
+            let after = self.board.state(location).unwrap_or_default();
+            self.edits.record(location, before, after);
+        }
+    }
+
+    pub fn stamp_by_hand(
+        &mut self,
+        pattern: &Pattern,
+        top_left: CellLocation,
+    ) -> Option<CellLocation> {
+        let before: Vec<(CellLocation, CellState)> = pattern
+            .states()
+            .map(|(cell, _)| &top_left + cell)
+            .filter_map(|location| self.board.state(&location).map(|state| (location, state)))
+            .collect();
+        let top_left = self.stamp(pattern, top_left)?;
+        for (location, state) in before {
+            let after = self.board.state(&location).unwrap_or_default();
+            self.edits.record(&location, state, after);
+        }
+        self.edits.finish();
+        Some(top_left)
+    }
+
+    // Kills everything between two corners, inclusive and either way round
+    pub fn erase(&mut self, corner: &CellLocation, other: &CellLocation) {
+        let (top, bottom) = (corner.r.min(other.r), corner.r.max(other.r));
+        let (left, right) = (corner.c.min(other.c), corner.c.max(other.c));
+        let board = &self.board;
+        let living: Vec<(CellLocation, CellState)> = (top..=bottom)
+            .flat_map(|r| (left..=right).map(move |c| CellLocation { r, c }))
+            .filter_map(|location| {
+                board
+                    .state(&location)
+                    .filter(|state| *state != CellState::Dead)
+                    .map(|state| (location, state))
+            })
+            .collect();
+        for (location, state) in &living {
+            self.edits.record(location, state.clone(), CellState::Dead);
+        }
+        self.edits.finish();
+        let dead: Vec<(CellLocation, CellState)> = living
+            .into_iter()
+            .map(|(location, _)| (location, CellState::Dead))
+            .collect();
+        self.set_cells(&dead);
+    }
+
+    // Takes back the last edit made by hand, or makes the last one taken
+    // back again, false if there isn't one
+    pub fn undo(&mut self) -> bool {
+        match self.edits.undo() {
+            Some(cells) => {
+                self.set_cells(&cells);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.edits.redo() {
+            Some(cells) => {
+                self.set_cells(&cells);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Changes the whole board at once, as one edit that can be undone. On an
+    // infinite board it's only the cells in view.
+    pub fn rework(&mut self, change: impl FnOnce(&mut LifeBoard)) {
+        let mut board = self.board.clone();
+        change(&mut board);
+        let changed: Vec<CellLocation> = board.changed_cells(&self.board).collect();
+        if changed.is_empty() {
+            return;
+        }
+        self.write_repro(|repro, generation| {
+            changed.iter().try_for_each(|location| {
+                repro.edit(
+                    generation,
+                    location,
+                    &board.state(location).unwrap_or_default(),
+                )
+            })
+        });
+        for location in &changed {
+            let before = self.board.state(location).unwrap_or_default();
+            let after = board.state(location).unwrap_or_default();
+            if let Some(universe) = &mut self.universe {
+                universe.set_state(&(location + &self.origin), after.clone());
+            }
+            self.edits.record(location, before, after);
+        }
+        self.edits.finish();
+        self.board = board;
+        self.forget_cycles();
+        self.future.clear();
+        if let Some(latest) = self.history.back_mut() {
+            *latest = self.board.clone();
+        }
+    }
+
+    // Like painting each of them, with the history only brought up to date
+    // once at the end
+    fn set_cells(&mut self, cells: &[(CellLocation, CellState)]) {
+        if cells.is_empty() {
+            return;
+        }
+        self.forget_cycles();
+        self.future.clear();
+        self.write_repro(|repro, generation| {
+            cells
+                .iter()
+                .try_for_each(|(location, state)| repro.edit(generation, location, state))
+        });
+        for (location, state) in cells {
+            if let Some(universe) = &mut self.universe {
+                universe.set_state(&(location + &self.origin), state.clone());
+            }
+            self.board.set_state(location, state.clone());
+        }
+        if let Some(latest) = self.history.back_mut() {
+            *latest = self.board.clone();
+        }
+    }
+
+    // Back a generation, false if there's nothing left to go back to. On an
+    // infinite board only the cells that were in view come back.
+    pub fn back(&mut self) -> Result<bool, String> {
+        let (generation, board) = match self.past.pop_back() {
+            Some(past) => past,
+            None => return Ok(false),
+        };
+        self.write_repro(|repro, generation| repro.back(generation));
+        let current = std::mem::replace(&mut self.board, board);
+        self.future.push((self.generation, current));
+        self.generation = generation;
+        self.restore()?;
+        Ok(true)
+    }
+
+    // Forward through the generations gone back past, then on as normal
+    pub fn forward(&mut self) -> Result<(), String> {
+        match self.future.pop() {
+            Some((generation, board)) => {
+                let current = std::mem::replace(&mut self.board, board);
+                self.past.push_back((self.generation, current));
+                self.generation = generation;
+                self.restore()
+            }
+            None => {
+                self.advance();
+                Ok(())
+            }
+        }
+    }
+
+    fn restore(&mut self) -> Result<(), String> {
+        if let Some(universe) = &mut self.universe {
+            universe.reset(&self.board, &self.origin)?;
+        }
+        self.forget_cycles();
+
+        let (births, deaths) = self.past.back().map_or((0, 0), |(_, previous)| {
+            self.board.births_and_deaths(previous)
+        });
+        self.births = births;
+        self.deaths = deaths;
+
+        self.history = self
+            .past
+            .iter()
+            .rev()
+            .take(self.history_length.saturating_sub(1))
+            .map(|(_, board)| board.clone())
+            .collect();
+        self.history.make_contiguous().reverse();
+        self.history.push_back(self.board.clone());
+        Ok(())
+    }
+
+    pub fn engine(&self) -> Engine {
+        match self.universe {
+            None => Engine::Naive,
+            Some(Universe::HashLife(_)) => Engine::HashLife,
+            Some(Universe::Sparse(_)) => Engine::Sparse,
+            #[cfg(feature = "gpu")]
+            Some(Universe::Gpu(_)) => Engine::Gpu,
+        }
+    }
+
+    // Longer trails fill in as the generations go by
+    pub fn set_history_length(&mut self, history_length: usize) {
+        self.history_length = history_length.clamp(1, MAX_HISTORY);
+        while self.history.len() > self.history_length {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn next_fade(&mut self) -> Fade {
+        let fade = self.fade.unwrap_or_default().next();
+        self.fade = Some(fade);
+        fade
+    }
+
+    // Picking a theme drops the fade so its cell colours show
+    pub fn next_theme(&mut self) -> Theme {
+        self.theme = self.theme.next();
+        self.fade = None;
+        self.theme
+    }
+
+    pub fn set_tracks(&mut self, tracks: Tracks) -> Result<(), String> {
+        if tracks != Tracks::Off && self.tracker.is_none() {
+            self.tracker = Some(ShipTracker::new(&self.board, TRACK_LENGTH)?);
+        }
+        if tracks == Tracks::Off {
+            if let Some(tracker) = &mut self.tracker {
+                tracker.clear();
+            }
+        }
+        self.tracks = tracks;
+        Ok(())
+    }
+
+    // Each time it's turned on it counts from the board as it is now
+    pub fn toggle_heatmap(&mut self) -> bool {
+        self.heatmap = match self.heatmap {
+            Some(_) => None,
+            None => {
+                let mut heatmap = Heatmap::new(self.heatmap_generations);
+                heatmap.observe(&self.board);
+                Some(heatmap)
+            }
+        };
+        self.heatmap.is_some()
+    }
+
+    pub fn tracked_ships(&self) -> &[Track] {
+        match &self.tracker {
+            Some(tracker) if self.tracks != Tracks::Off => tracker.tracks(),
+            _ => &[],
+        }
+    }
+
+    pub fn cell_colours(&self) -> &dyn ColorScheme {
+        match &self.fade {
+            Some(fade) => fade,
+            None => &self.theme,
+        }
+    }
+
+    pub fn rewound(&self) -> usize {
+        self.future.len()
+    }
+
+    // The trails are dropped as they were drawn where the view used to be
+    pub fn move_view(&mut self, dr: i32, dc: i32) {
+        let universe = match &self.universe {
+            Some(universe) if self.board.topology() == Topology::Infinite => universe,
+            _ => return,
+        };
+        self.origin = &self.origin + &CellLocation { r: dr, c: dc };
+        universe.write_window(&mut self.board, &self.origin);
+        self.forget_cycles();
+        // Past boards were seen from the old view
+        self.past.clear();
+        self.future.clear();
+        self.history.clear();
+        self.history.push_back(self.board.clone());
+        let origin = self.origin.clone();
+        self.write_repro(|repro, generation| repro.view(generation, &origin));
+    }
+
+    // Grows or shrinks the board, keeping what's by anchor where it is, and
+    // gives how far the cells moved. On an infinite board it's the view that
+    // changes size. The boards behind it were another size, so the trails,
+    // rewinding and undoing start again from here.
+    pub fn resize(&mut self, size: Term, anchor: Anchor) -> Result<CellLocation, String> {
+        #[cfg(feature = "record")]
+        if self.recording.is_some() {
+            return Err("The board can't change size while recording".to_string());
+        }
+        if size.w < 1 || size.h < 1 {
+            return Err(format!("The board can't be {}x{}", size.w, size.h));
+        }
+        self.write_repro(|repro, generation| repro.resize(generation, &size, anchor));
+        let shift = self.board.resize_offset(&size, anchor);
+        self.board = self.board.resized(size, anchor);
+        if let Some(universe) = &mut self.universe {
+            if self.board.topology() == Topology::Infinite {
+                self.origin = CellLocation {
+                    r: self.origin.r - shift.r,
+                    c: self.origin.c - shift.c,
+                };
+                universe.write_window(&mut self.board, &self.origin);
+            } else {
+                universe.reset(&self.board, &self.origin)?;
+            }
+        }
+        self.forget_cycles();
+        self.past.clear();
+        self.future.clear();
+        self.history.clear();
+        self.history.push_back(self.board.clone());
+        self.edits = UndoStack::default();
+        Ok(shift)
+    }
+
+    // A step of RESIZE_STEPS bigger or smaller, which is at least a cell
+    pub fn grow(&mut self, is_growing: bool, anchor: Anchor) -> Result<CellLocation, String> {
+        let sign = if is_growing { 1 } else { -1 };
+        let (w, h) = (self.board.width(), self.board.height());
+        self.resize(
+            Term {
+                w: w + sign * (w / RESIZE_STEPS).max(1),
+                h: h + sign * (h / RESIZE_STEPS).max(1),
+            },
+            anchor,
+        )
+    }
+
+    // Drops a random built-in pattern, in a random orientation, somewhere
+    // there is nothing alive
+    pub fn chaos<R: Rng>(&mut self, rng: &mut R) -> Option<String> {
+        let library = Pattern::library();
+        let mut pattern = library[rng.gen_range(0, library.len())].clone();
+        for _ in 0..rng.gen_range(0, 4) {
+            pattern = pattern.rotate90();
+        }
+        if rng.gen() {
+            pattern = pattern.flip_horizontal();
+        }
+
+        let top_left =
+            self.board
+                .find_empty_region(rng, pattern.width, pattern.height, CHAOS_ATTEMPTS)?;
+        let top_left = self.stamp(&pattern, top_left)?;
+        Some(format!("{} at {}", pattern, top_left))
+    }
+
+    // The fraction of cells that changed in the last generation
+    pub fn activity(&self) -> f32 {
+        let cells = (self.board.width() * self.board.height()).max(1);
+        (self.births + self.deaths) as f32 / cells as f32
+    }
+
+    pub fn hud(&self) -> String {
+        let view = if self.board.topology() == Topology::Infinite {
+            format!(" At {},{}", self.origin.r, self.origin.c)
+        } else {
+            String::new()
+        };
+        let rewound = match self.rewound() {
+            0 => String::new(),
+            n => format!(" Back {}", n),
+        };
+        let colonies = match self.colonies() {
+            Some(colonies) => format!(" Colonies {}", colonies),
+            None => String::new(),
+        };
+        format!(
+            "Gen {} Pop {} +{} -{}{}{}{}",
+            self.generation,
+            self.board.population(),
+            self.births,
+            self.deaths,
+            colonies,
+            view,
+            rewound
+        )
+    }
+
+    // The population of each colony, e.g. 200/206, when there's more than
+    // one
+    pub fn colonies(&self) -> Option<String> {
+        if self.board.colonies() < 2 {
+            return None;
+        }
+        let populations: Vec<String> = self
+            .board
+            .colony_populations()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        Some(populations.join("/"))
+    }
+
+    pub fn advance_by(&mut self, steps: usize, settings: &GUISettings) -> Option<String> {
+        let mut chaos = None;
+        for _ in 0..steps {
+            if self.stable.is_some() {
+                break;
+            }
+            self.advance();
+            chaos = self.chaos_due(settings).or(chaos);
+        }
+        chaos
+    }
+
+    pub fn chaos_due(&mut self, settings: &GUISettings) -> Option<String> {
+        let interval = settings.chaos_interval?;
+        if self.generation.is_multiple_of(interval) {
+            self.chaos(&mut rand::thread_rng())
+        } else {
+            None
+        }
+    }
+
+    pub fn advance(&mut self) {
+        self.keep_history();
+        let previous = self.board.clone();
+        let start = Instant::now();
+        match &mut self.universe {
+            Some(universe) => {
+                universe.advance(1);
+                universe.write_window(&mut self.board, &self.origin);
+                self.board.record_history();
+            }
+            None => self.board.step(),
+        }
+        self.record_generation(previous, start.elapsed());
+    }
+
+    // For a board stepped somewhere else, e.g. by a Stepper
+    pub fn advance_to(&mut self, next: LifeBoard, step_time: Duration) {
+        let previous = std::mem::replace(&mut self.board, next);
+        self.keep_history();
+        self.record_generation(previous, step_time);
+    }
+
+    // What's been given up on since this was last called, for the frontend
+    // to show where it won't get in the way of what it's drawing
+    pub fn take_problems(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.problems)
+    }
+
+    // Closes off the repro when the session's quit, with a problem if it
+    // can't be
+    pub fn finish(&mut self) {
+        if let Some(mut repro) = self.repro.take() {
+            if let Err(e) = repro.finish(self.generation, &self.board, false) {
+                self.problems
+                    .push(format!("Unable to finish the repro: {}", e));
+            }
+        }
+    }
+
+    // The board only keeps its cells' ages and history while they're drawn
+    fn keep_history(&mut self) {
+        self.board.track_history(self.by_age || self.grey_settled);
+    }
+
+    fn record_generation(&mut self, previous: LifeBoard, step_time: Duration) {
+        self.future.clear();
+        if self.rewind_depth > 0 {
+            self.past.push_back((self.generation, previous.clone()));
+            if self.past.len() > self.rewind_depth {
+                self.past.pop_front();
+            }
+        }
+
+        self.generation += 1;
+        if let Some(cycles) = &mut self.cycles {
+            self.stable = cycles.observe(self.board.fingerprint(), self.generation);
+        }
+        let (births, deaths) = self.board.births_and_deaths(&previous);
+        self.births = births;
+        self.deaths = deaths;
+        // A stats file that can't be written to is given up on rather than
+        // stopping the run
+        if let Some(mut stats) = self.stats.take() {
+            match stats.generation(self, step_time) {
+                Ok(()) => self.stats = Some(stats),
+                Err(e) => self
+                    .problems
+                    .push(format!("Unable to write the stats, no more will be: {}", e)),
+            }
+        }
+        if let (Some(tracker), true) = (&mut self.tracker, self.tracks != Tracks::Off) {
+            tracker.observe(&self.board);
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.observe(&self.board);
+        }
+        self.history.push_back(self.board.clone());
+        if self.history.len() > self.history_length {
+            self.history.pop_front();
+        }
+
+        // Like the stats, a recording that can't be written to is given up
+        // on, keeping the frames it already has
+        #[cfg(feature = "record")]
+        if let Some(mut recording) = self.recording.take() {
+            match recording.capture(&self.history, self.generation) {
+                Ok(()) => self.recording = Some(recording),
+                Err(e) => self
+                    .problems
+                    .push(format!("Unable to record a frame, no more will be: {}", e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn going_back_and_forward_lands_on_the_same_board() {
+        let settings = GUISettings {
+            board_width: 20,
+            board_height: 20,
+            ..GUISettings::default()
+        };
+        let mut sim = Simulation::new(random_board(&settings), &settings)
+            .expect("Unable to start the simulation");
+        let start = sim.board.clone();
+        sim.advance();
+        sim.advance();
+        let later = sim.board.clone();
+        assert_eq!(sim.generation, 2);
+        assert_eq!(sim.back(), Ok(true));
+        assert_eq!(sim.back(), Ok(true));
+        assert_eq!(sim.generation, 0);
+        assert_eq!(sim.board.fingerprint(), start.fingerprint());
+        assert_eq!(sim.back(), Ok(false));
+        sim.forward().expect("Unable to go forward");
+        sim.forward().expect("Unable to go forward");
+        assert_eq!(sim.board.fingerprint(), later.fingerprint());
+    }
+
+    #[test]
+    fn an_edit_by_hand_can_be_taken_back() {
+        let settings = GUISettings {
+            board_width: 10,
+            board_height: 10,
+            density: 0.0,
+            ..GUISettings::default()
+        };
+        let mut sim = Simulation::new(random_board(&settings), &settings)
+            .expect("Unable to start the simulation");
+        let location = CellLocation { r: 4, c: 4 };
+        sim.paint_by_hand(&location, CellState::Alive);
+        assert_eq!(sim.board.population(), 1);
+        assert!(sim.undo());
+        assert_eq!(sim.board.population(), 0);
+        assert!(sim.redo());
+        assert_eq!(sim.board.population(), 1);
+    }
+
+    // /dev/full takes the first few frames into the buffer then fails
+    #[cfg(all(feature = "record", target_os = "linux"))]
+    #[test]
+    fn a_recording_that_cant_be_written_is_given_up_on() {
+        let settings = GUISettings {
+            board_width: 40,
+            board_height: 40,
+            record_path: Some("/dev/full".to_string()),
+            record_every: 1,
+            ..GUISettings::default()
+        };
+        let mut sim = Simulation::new(random_board(&settings), &settings)
+            .expect("Unable to start the simulation");
+        for _ in 0..200 {
+            sim.advance();
+        }
+        assert!(sim.recording.is_none());
+        let problems = sim.take_problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Unable to record a frame"));
+        assert_eq!(sim.generation, 200);
+    }
+}
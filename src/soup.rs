@@ -0,0 +1,356 @@
+use super::cells::{CellLocation, CellState};
+use super::census::{groups, Census, Recogniser};
+use super::cycle::{Cycle, CycleDetector};
+use super::life::{Edges, LifeBoard, StampMode, Term};
+use super::pattern::Pattern;
+use super::rule::Rule;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+// Every common oscillator and then some
+const CYCLE_WINDOW: usize = 64;
+
+// How each soup is made and how long it's given to settle. The soup is
+// dropped in the middle of a bounded board with room around it, so
+// anything it sends off hits an edge and settles rather than flying round
+// forever. Whatever ends up held against an edge is left out of the
+// census.
+#[derive(Clone, Debug)]
+pub struct SoupSearch {
+    pub size: i32,
+    pub room: i32,
+    pub density: f64,
+    pub rule: Rule,
+    pub max_generations: u64,
+}
+
+impl Default for SoupSearch {
+    fn default() -> Self {
+        SoupSearch {
+            size: 16,
+            room: 128,
+            density: 0.5,
+            rule: Rule::default(),
+            max_generations: 50_000,
+        }
+    }
+}
+
+// What became of one soup
+#[derive(Clone, Debug)]
+pub struct Soup {
+    pub seed: u64,
+    // The cells it started from
+    pub pattern: Pattern,
+    // None if it was still going when it ran out of generations
+    pub settled: Option<Cycle>,
+    pub population: usize,
+    // What it settled into, if the rule's one the census can be taken in
+    pub census: Option<Census>,
+}
+
+impl Soup {
+    // Soups still going count as living as long as they were run for
+    pub fn lifespan(&self, search: &SoupSearch) -> u64 {
+        self.settled.map_or(search.max_generations, |c| c.since)
+    }
+
+    // Anything moving or oscillating that isn't in the census's dictionary
+    // is worth a look, without a census anything with a longer period than
+    // a blinker is
+    pub fn is_uncommon(&self) -> bool {
+        match &self.census {
+            Some(census) => census.has_unnamed_oscillators(),
+            None => self.settled.is_some_and(|c| c.period > 2),
+        }
+    }
+
+    pub fn describe(&self, search: &SoupSearch) -> String {
+        match self.settled {
+            Some(cycle) => format!("Settled into a {} with {} cells", cycle, self.population),
+            None => format!(
+                "Still going after {} generations with {} cells",
+                search.max_generations, self.population
+            ),
+        }
+    }
+
+    // The soup as RLE with what became of it in the comments
+    pub fn to_rle(&self, search: &SoupSearch) -> String {
+        let mut pattern = self.pattern.clone();
+        pattern.comments.push(self.describe(search));
+        if let Some(census) = &self.census {
+            pattern
+                .comments
+                .extend(census.to_string().lines().map(str::to_string));
+        }
+        pattern.to_rle()
+    }
+}
+
+// What came of a whole run of soups
+#[derive(Clone, Debug, Default)]
+pub struct Haul {
+    pub soups: u64,
+    // Longest lived first
+    pub longest: Vec<Soup>,
+    // In seed order
+    pub uncommon: Vec<Soup>,
+    // Of every soup that settled, if there was a census to take
+    pub census: Option<Census>,
+}
+
+impl Haul {
+    // Laid out like a Catagolue haul, which only has C1 for 16x16 soups at
+    // half density
+    pub fn to_apgcodes(&self, search: &SoupSearch) -> Option<String> {
+        let census = self.census.as_ref()?;
+        let mut haul = format!(
+            "@RULE {}\n",
+            search.rule.to_string().to_lowercase().replace('/', "")
+        );
+        if search.size == 16 && search.density == 0.5 {
+            haul.push_str("@SYMMETRY C1\n");
+        }
+        haul.push_str(&format!(
+            "@NUM_SOUPS {}\n@NUM_OBJECTS {}\n@CENSUS TABLE\n{}",
+            self.soups,
+            census.total(),
+            census.apgcode_table()
+        ));
+        Some(haul)
+    }
+}
+
+// Debris that's flown into an edge can sit there in shapes that only hold
+// up against it, which would be counted as unsettled, so anything touching
+// an edge is taken off before the census
+fn away_from_the_edges(board: &LifeBoard) -> LifeBoard {
+    let live: HashSet<CellLocation> = board.iter_live().collect();
+    let mut away = board.clone();
+    let touches = |c: &CellLocation| {
+        c.r == 0 || c.c == 0 || c.r == board.height() - 1 || c.c == board.width() - 1
+    };
+    for group in groups(&live, 2) {
+        if group.iter().any(touches) {
+            for cell in &group {
+                away.set_state(cell, CellState::Dead);
+            }
+        }
+    }
+    away
+}
+
+impl SoupSearch {
+    // The same seed always makes the same soup
+    pub fn soup(&self, seed: u64) -> Pattern {
+        let mut soup = LifeBoard::from(Term {
+            w: self.size,
+            h: self.size,
+        })
+        .with_rule(self.rule.clone());
+        soup.randomize_with_seed(seed, self.density);
+        let mut pattern = soup.copy_region(
+            &CellLocation::default(),
+            &CellLocation {
+                r: self.size - 1,
+                c: self.size - 1,
+            },
+        );
+        pattern.name = Some(format!("soup-{}", seed));
+        pattern
+    }
+
+    pub fn run(&self, seed: u64, recogniser: Option<&Recogniser>) -> Soup {
+        let pattern = self.soup(seed);
+        let mut board = LifeBoard::from(Term {
+            w: self.room,
+            h: self.room,
+        })
+        .with_rule(self.rule.clone());
+        board.clear();
+        let middle = (self.room - self.size) / 2;
+        board.stamp(
+            &pattern,
+            CellLocation {
+                r: middle,
+                c: middle,
+            },
+            StampMode::Or,
+            Edges::Reject,
+        );
+
+        let mut cycles = CycleDetector::new(CYCLE_WINDOW);
+        let mut settled = cycles.observe(board.fingerprint(), 0);
+        let mut generation = 0;
+        while settled.is_none() && generation < self.max_generations {
+            board.step();
+            generation += 1;
+            settled = cycles.observe(board.fingerprint(), generation);
+        }
+        let census = recogniser
+            .filter(|_| settled.is_some())
+            .and_then(|r| r.census(&away_from_the_edges(&board)).ok());
+        Soup {
+            seed,
+            pattern,
+            settled,
+            population: board.population(),
+            census,
+        }
+    }
+
+    // Runs the soups from first_seed on, keeping the longest lived and the
+    // uncommon ones. Each thread takes the next soup until they've all been
+    // run, keeping its own longest lived so only those are ever held on to.
+    pub fn run_many(
+        &self,
+        first_seed: u64,
+        soups: u64,
+        keep: usize,
+        recogniser: Option<&Recogniser>,
+    ) -> Haul {
+        let next = AtomicU64::new(0);
+        let by_lifespan = |s: &Soup| (std::cmp::Reverse(s.lifespan(self)), s.seed);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let found: Vec<(Vec<Soup>, Vec<Soup>, Census)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut longest: Vec<Soup> = Vec::new();
+                        let mut uncommon = Vec::new();
+                        let mut total = Census::default();
+                        loop {
+                            let n = next.fetch_add(1, Ordering::Relaxed);
+                            if n >= soups {
+                                return (longest, uncommon, total);
+                            }
+                            let soup = self.run(first_seed.wrapping_add(n), recogniser);
+                            if let Some(census) = &soup.census {
+                                total.add(census);
+                            }
+                            if soup.is_uncommon() {
+                                uncommon.push(soup.clone());
+                            }
+                            longest.push(soup);
+                            longest.sort_by_key(by_lifespan);
+                            longest.truncate(keep);
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| w.join().expect("A soup search thread panicked"))
+                .collect()
+        });
+        let mut haul = Haul {
+            soups,
+            ..Haul::default()
+        };
+        let mut total = Census::default();
+        for (longest, uncommon, census) in found {
+            haul.longest.extend(longest);
+            haul.uncommon.extend(uncommon);
+            total.add(&census);
+        }
+        haul.longest.sort_by_key(by_lifespan);
+        haul.longest.truncate(keep);
+        haul.uncommon.sort_by_key(|s| s.seed);
+        haul.census = recogniser.map(|_| total);
+        haul
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_soup_settles_the_same_way_every_time() {
+        let search = SoupSearch {
+            room: 48,
+            max_generations: 5000,
+            ..SoupSearch::default()
+        };
+        let recogniser = Recogniser::new(search.rule.clone()).expect("Unable to take a census");
+        let first = search.run(3, Some(&recogniser));
+        let again = search.run(3, None);
+        assert_eq!(first.pattern.cells, again.pattern.cells);
+        assert_eq!(first.settled, again.settled);
+        assert!(first.settled.is_some(), "soup 3 never settled");
+        let census = first.census.expect("Soup 3 has no census");
+        assert!(census.total() > 0);
+        assert_eq!(again.census, None);
+
+        let empty = SoupSearch {
+            density: 0.0,
+            ..search
+        }
+        .run(3, Some(&recogniser));
+        assert_eq!(
+            empty.settled,
+            Some(Cycle {
+                period: 1,
+                since: 0
+            })
+        );
+        assert_eq!(empty.population, 0);
+        assert_eq!(empty.census.map(|c| c.total()), Some(0));
+    }
+
+    #[test]
+    fn debris_against_an_edge_is_left_out_of_the_census() {
+        let mut board = LifeBoard::from(Term { w: 20, h: 20 });
+        board.clear();
+        let place = |board: &mut LifeBoard, rle: &str, r, c| {
+            let pattern = Pattern::from_rle(rle).expect("Unable to parse the object");
+            board.stamp(
+                &pattern,
+                CellLocation { r, c },
+                StampMode::Or,
+                Edges::Reject,
+            );
+        };
+        // Only still because nothing can be born past the edge
+        place(&mut board, "4o$o2bo!", 0, 6);
+        place(&mut board, "2o$2o!", 9, 9);
+        let before = board.fingerprint();
+        board.step();
+        assert_eq!(board.fingerprint(), before);
+
+        let recogniser = Recogniser::new(Rule::default()).expect("Unable to take a census");
+        let everything = recogniser
+            .census(&board)
+            .expect("Unable to take the census");
+        assert_eq!(everything.apgcodes.get("PATHOLOGICAL"), Some(&1));
+        let census = recogniser
+            .census(&away_from_the_edges(&board))
+            .expect("Unable to take the census");
+        assert_eq!(census.total(), 1);
+        assert_eq!(census.apgcodes.get("xs4_33"), Some(&1));
+        assert_eq!(board.population(), 10);
+    }
+
+    #[test]
+    fn a_haul_is_the_same_however_many_threads_run_it() {
+        let search = SoupSearch {
+            room: 48,
+            max_generations: 2000,
+            ..SoupSearch::default()
+        };
+        let recogniser = Recogniser::new(search.rule.clone()).expect("Unable to take a census");
+        let haul = search.run_many(10, 6, 2, Some(&recogniser));
+        assert_eq!(haul.longest.len(), 2);
+        let lifespans: Vec<u64> = (10..16)
+            .map(|seed| search.run(seed, None).lifespan(&search))
+            .collect();
+        let longest = *lifespans.iter().max().expect("No soups were run");
+        assert_eq!(haul.longest[0].lifespan(&search), longest);
+        assert!(haul.longest[0].lifespan(&search) >= haul.longest[1].lifespan(&search));
+        let apgcodes = haul.to_apgcodes(&search).expect("No census in the haul");
+        assert!(apgcodes.starts_with("@RULE b3s23\n@SYMMETRY C1\n@NUM_SOUPS 6\n"));
+        assert_eq!(search.run_many(10, 6, 2, None).to_apgcodes(&search), None);
+    }
+}
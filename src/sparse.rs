@@ -0,0 +1,199 @@
+use super::cells::{CellLocation, CellState};
+use super::life::{LifeBoard, Neighbourhood, Topology};
+use super::rule::Rule;
+use std::collections::{HashMap, HashSet};
+
+const NEIGHBOUR_DELTAS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Only the live cells are kept and each generation only looks at them and
+// their neighbours, so the cost follows the population rather than the
+// size of the board. On an infinite topology the size is ignored and
+// cells can go anywhere.
+#[derive(Clone, Debug)]
+pub struct SparseLife {
+    rule: Rule,
+    topology: Topology,
+    width: i32,
+    height: i32,
+    live: HashSet<CellLocation>,
+    // Kept between generations so stepping reuses the allocations
+    counts: HashMap<CellLocation, u8>,
+    next: HashSet<CellLocation>,
+    generation: u64,
+}
+
+impl SparseLife {
+    pub fn new(width: i32, height: i32, topology: Topology, rule: Rule) -> Result<Self, String> {
+        if rule.is_born(0) {
+            return Err(format!(
+                "The sparse engine cannot run {} as it has B0",
+                rule
+            ));
+        }
+        if rule.is_generations() {
+            return Err(format!(
+                "The sparse engine cannot run {} as it has dying states",
+                rule
+            ));
+        }
+        Ok(SparseLife {
+            rule,
+            topology,
+            width,
+            height,
+            live: HashSet::new(),
+            counts: HashMap::new(),
+            next: HashSet::new(),
+            generation: 0,
+        })
+    }
+
+    pub fn from_board(board: &LifeBoard) -> Result<Self, String> {
+        if board.neighbourhood() != Neighbourhood::Moore {
+            return Err(format!(
+                "The sparse engine cannot run a {} neighbourhood",
+                board.neighbourhood()
+            ));
+        }
+        if !board.ants().is_empty() {
+            return Err("The sparse engine cannot run Langton's Ant".to_string());
+        }
+        if board.colonies() > 1 {
+            return Err("The sparse engine cannot run more than one colony".to_string());
+        }
+        let mut universe = SparseLife::new(
+            board.width(),
+            board.height(),
+            board.topology(),
+            board.rule().clone(),
+        )?;
+        for location in board.iter_live() {
+            universe.live.insert(location);
+        }
+        Ok(universe)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.live.len() as u64
+    }
+
+    pub fn live_cells(&self) -> impl Iterator<Item = &CellLocation> {
+        self.live.iter()
+    }
+
+    pub fn state(&self, location: &CellLocation) -> CellState {
+        if self.live.contains(location) {
+            CellState::Alive
+        } else {
+            CellState::Dead
+        }
+    }
+
+    // Cells off a finite board are ignored
+    pub fn set_state(&mut self, location: &CellLocation, state: CellState) {
+        if !self.contains(location) {
+            return;
+        }
+        match state {
+            CellState::Alive => self.live.insert(location.clone()),
+            CellState::Dying(_) | CellState::Wire | CellState::Dead => self.live.remove(location),
+        };
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    fn contains(&self, location: &CellLocation) -> bool {
+        self.topology == Topology::Infinite
+            || (location.r >= 0
+                && location.c >= 0
+                && location.r < self.height
+                && location.c < self.width)
+    }
+
+    fn neighbour(&self, location: &CellLocation, (dr, dc): (i32, i32)) -> Option<CellLocation> {
+        let neighbour = CellLocation {
+            r: location.r + dr,
+            c: location.c + dc,
+        };
+        match self.topology {
+            Topology::Bounded | Topology::Infinite => Some(neighbour).filter(|n| self.contains(n)),
+            Topology::Torus => Some(CellLocation {
+                r: neighbour.r.rem_euclid(self.height),
+                c: neighbour.c.rem_euclid(self.width),
+            }),
+        }
+    }
+
+    pub fn step(&mut self) {
+        let mut counts = std::mem::take(&mut self.counts);
+        counts.clear();
+        for location in &self.live {
+            for delta in NEIGHBOUR_DELTAS.iter() {
+                if let Some(neighbour) = self.neighbour(location, *delta) {
+                    *counts.entry(neighbour).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut next = std::mem::take(&mut self.next);
+        next.clear();
+        for (location, neighbours) in &counts {
+            let alive = if self.live.contains(location) {
+                self.rule.survives(*neighbours)
+            } else {
+                self.rule.is_born(*neighbours)
+            };
+            if alive {
+                next.insert(location.clone());
+            }
+        }
+        // Live cells with no neighbours never made it into counts
+        if self.rule.survives(0) {
+            next.extend(
+                self.live
+                    .iter()
+                    .filter(|l| !counts.contains_key(l))
+                    .cloned(),
+            );
+        }
+
+        self.next = std::mem::replace(&mut self.live, next);
+        self.counts = counts;
+        self.generation += 1;
+    }
+
+    pub fn write_to(&self, board: &mut LifeBoard) {
+        self.write_window(board, &CellLocation::default());
+    }
+
+    // Fills board with the cells in view when its top left is at origin
+    pub fn write_window(&self, board: &mut LifeBoard, origin: &CellLocation) {
+        board.clear();
+        for location in &self.live {
+            board.set_state(
+                &CellLocation {
+                    r: location.r - origin.r,
+                    c: location.c - origin.c,
+                },
+                CellState::Alive,
+            );
+        }
+    }
+}
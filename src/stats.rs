@@ -0,0 +1,45 @@
+use std::io::Write;
+use std::time::Duration;
+
+use super::simulation::Simulation;
+
+// A row for every generation worked out, added to a CSV file or, for .json
+// and .jsonl, a JSON object per line, to look at a run afterwards in a
+// spreadsheet or notebook. Generations worked out again after rewinding get
+// rows of their own.
+pub struct StatsWriter {
+    out: std::fs::File,
+    is_json: bool,
+}
+
+impl StatsWriter {
+    pub fn start(path: &str) -> std::io::Result<StatsWriter> {
+        let mut out = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let is_json = path.ends_with(".json") || path.ends_with(".jsonl");
+        if !is_json && out.metadata()?.len() == 0 {
+            writeln!(out, "generation,population,births,deaths,step_ms")?;
+        }
+        Ok(StatsWriter { out, is_json })
+    }
+
+    pub fn generation(&mut self, sim: &Simulation, step_time: Duration) -> std::io::Result<()> {
+        let step_ms = step_time.as_secs_f64() * 1000.0;
+        let population = sim.board.population();
+        if self.is_json {
+            writeln!(
+                self.out,
+                "{{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\"step_ms\":{:.3}}}",
+                sim.generation, population, sim.births, sim.deaths, step_ms
+            )
+        } else {
+            writeln!(
+                self.out,
+                "{},{},{},{},{:.3}",
+                sim.generation, population, sim.births, sim.deaths, step_ms
+            )
+        }
+    }
+}
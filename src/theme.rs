@@ -0,0 +1,144 @@
+use super::compositor::Rgb;
+use super::fade::ColorScheme;
+
+// The colours everything is drawn in unless a --fade is picked for the
+// cells
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Rgb,
+    pub alive: Rgb,
+    // The oldest trail is drawn in the first and the newest in the second
+    pub trail: [Rgb; 2],
+    pub grid: Rgb,
+}
+
+// The first is the default, it looks the way the game always has
+pub const THEMES: [Theme; 4] = [
+    Theme {
+        name: "classic",
+        background: [0, 0, 0],
+        alive: [255, 0, 0],
+        trail: [[0, 0, 0], [255, 255, 255]],
+        grid: [40, 40, 40],
+    },
+    Theme {
+        name: "solarized",
+        background: [0, 43, 54],
+        alive: [181, 137, 0],
+        trail: [[7, 54, 66], [147, 161, 161]],
+        grid: [7, 54, 66],
+    },
+    Theme {
+        name: "matrix-green",
+        background: [0, 0, 0],
+        alive: [0, 255, 65],
+        trail: [[0, 20, 0], [0, 143, 17]],
+        grid: [0, 40, 0],
+    },
+    Theme {
+        name: "high-contrast",
+        background: [0, 0, 0],
+        alive: [255, 255, 255],
+        trail: [[0, 0, 96], [255, 255, 0]],
+        grid: [128, 128, 128],
+    },
+];
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Theme> {
+        THEMES
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    // For cycling through them at runtime
+    pub fn next(&self) -> Theme {
+        let i = THEMES.iter().position(|t| t == self).unwrap_or(0);
+        THEMES[(i + 1) % THEMES.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
+
+impl ColorScheme for Theme {
+    fn colour(&self, intensity: f32) -> Rgb {
+        if intensity >= 1.0 {
+            return self.alive;
+        }
+        let t = intensity.clamp(0.0, 1.0);
+        let [from, to] = self.trail;
+        let mut colour = [0; 3];
+        for (channel, (a, b)) in colour.iter_mut().zip(from.iter().zip(to.iter())) {
+            *channel = (*a as f32 + (*b as f32 - *a as f32) * t) as u8;
+        }
+        colour
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::named(s).ok_or_else(|| format!("Unknown theme {}", s))
+    }
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// How the cells are told apart in the window, only drawn when they're at
+// least MIN_GRID_CELL pixels across so small cells aren't swamped by it
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Grid {
+    #[default]
+    Off,
+    // Faint lines in the theme's grid colour between the cells
+    Lines,
+    // A pixel of background between the cells
+    Gap,
+}
+
+pub const MIN_GRID_CELL: u32 = 4;
+
+impl Grid {
+    // For cycling through them at runtime
+    pub fn next(&self) -> Grid {
+        match self {
+            Grid::Off => Grid::Lines,
+            Grid::Lines => Grid::Gap,
+            Grid::Gap => Grid::Off,
+        }
+    }
+}
+
+impl std::str::FromStr for Grid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Grid::Off),
+            "lines" => Ok(Grid::Lines),
+            "gap" => Ok(Grid::Gap),
+            _ => Err(format!("Unknown grid {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Grid::Off => write!(f, "off"),
+            Grid::Lines => write!(f, "lines"),
+            Grid::Gap => write!(f, "gap"),
+        }
+    }
+}
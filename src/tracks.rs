@@ -0,0 +1,235 @@
+use super::cells::CellLocation;
+use super::census::{groups, shape, Recogniser, Shape};
+use super::life::{LifeBoard, Neighbourhood};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Bigger groups of cells aren't checked for being ships, working out what
+// each of them does every generation would be too slow for a soup
+const MAX_SHIP_CELLS: usize = 40;
+// Longer than the period of the gliders and the spaceships
+const MAX_SHIP_PERIOD: u64 = 8;
+// Enough for the shapes of a busy board, after which they're worked out again
+const MAX_SHAPES: usize = 10_000;
+// How far a ship can be from where its track says it should be and still
+// be the same ship, the middle of a glider wobbles as it goes
+const MAX_WOBBLE: f32 = 1.5;
+
+// How the paths of moving objects are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tracks {
+    #[default]
+    Off,
+    // A line along where each ship has been
+    Trails,
+    // An arrow from each ship in the direction it's going, longer the
+    // faster it goes
+    Vectors,
+}
+
+impl Tracks {
+    // For cycling through them at runtime
+    pub fn next(&self) -> Tracks {
+        match self {
+            Tracks::Off => Tracks::Trails,
+            Tracks::Trails => Tracks::Vectors,
+            Tracks::Vectors => Tracks::Off,
+        }
+    }
+}
+
+impl std::str::FromStr for Tracks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Tracks::Off),
+            "trails" => Ok(Tracks::Trails),
+            "vectors" => Ok(Tracks::Vectors),
+            _ => Err(format!("Unknown tracks {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Tracks {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Tracks::Off => write!(f, "off"),
+            Tracks::Trails => write!(f, "trails"),
+            Tracks::Vectors => write!(f, "vectors"),
+        }
+    }
+}
+
+// One ship followed from generation to generation. Points are rows and
+// columns on the board, to the middle of the cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    // Where the middle of the ship has been, oldest first
+    pub trail: VecDeque<(f32, f32)>,
+    // In cells a generation
+    pub velocity: (f32, f32),
+}
+
+impl Track {
+    pub fn position(&self) -> (f32, f32) {
+        self.trail.back().copied().unwrap_or_default()
+    }
+
+    // Where the ship will be in so many generations, if nothing gets in its
+    // way
+    pub fn ahead(&self, generations: f32) -> (f32, f32) {
+        let (r, c) = self.position();
+        (
+            r + self.velocity.0 * generations,
+            c + self.velocity.1 * generations,
+        )
+    }
+}
+
+// Finds the ships on a board each generation and keeps a track of each.
+// A ship is a small group of cells that comes back to the same shape
+// somewhere else when it's stepped on its own, so any rule's ships are
+// found, not just the ones in the census's dictionary. A ship that hits
+// something, or wraps round a torus, loses its track and starts a new one.
+pub struct ShipTracker {
+    recogniser: Recogniser,
+    // How each shape moves, if it does, so each is only stepped once
+    velocities: HashMap<Shape, Option<(f32, f32)>>,
+    tracks: Vec<Track>,
+    // How many points each trail keeps
+    length: usize,
+}
+
+impl ShipTracker {
+    pub fn new(board: &LifeBoard, length: usize) -> Result<ShipTracker, String> {
+        if board.neighbourhood() != Neighbourhood::Moore {
+            return Err(format!(
+                "Unable to track ships in a {} neighbourhood",
+                board.neighbourhood()
+            ));
+        }
+        Ok(ShipTracker {
+            recogniser: Recogniser::new(board.rule().clone())?,
+            velocities: HashMap::new(),
+            tracks: Vec::new(),
+            length: length.max(1),
+        })
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    // For when the board's been changed other than by stepping it
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    fn velocity(&mut self, cells: &[CellLocation]) -> Option<(f32, f32)> {
+        let key = shape(cells);
+        if let Some(velocity) = self.velocities.get(&key) {
+            return *velocity;
+        }
+        let velocity = match self.recogniser.period(cells, MAX_SHIP_PERIOD) {
+            Some((period, moved)) if moved != CellLocation::default() => Some((
+                moved.r as f32 / period as f32,
+                moved.c as f32 / period as f32,
+            )),
+            _ => None,
+        };
+        if self.velocities.len() >= MAX_SHAPES {
+            self.velocities.clear();
+        }
+        self.velocities.insert(key, velocity);
+        velocity
+    }
+
+    // Each ship carries on the track that expected it nearest to where it
+    // is, or starts one of its own. Tracks with no ship are dropped.
+    pub fn observe(&mut self, board: &LifeBoard) {
+        let live: HashSet<CellLocation> = board.iter_live().collect();
+        let mut ships = Vec::new();
+        for group in groups(&live, 2) {
+            if group.len() > MAX_SHIP_CELLS {
+                continue;
+            }
+            if let Some(velocity) = self.velocity(&group) {
+                let n = group.len() as f32;
+                let middle = group.iter().fold((0.5, 0.5), |(r, c), cell| {
+                    (r + cell.r as f32 / n, c + cell.c as f32 / n)
+                });
+                ships.push((middle, velocity));
+            }
+        }
+
+        let mut old = std::mem::take(&mut self.tracks);
+        for ((r, c), velocity) in ships {
+            let nearest = old
+                .iter()
+                .enumerate()
+                .filter(|(_, track)| track.velocity == velocity)
+                .map(|(i, track)| {
+                    let (er, ec) = track.ahead(1.0);
+                    (i, ((er - r).powi(2) + (ec - c).powi(2)).sqrt())
+                })
+                .filter(|(_, distance)| *distance <= MAX_WOBBLE)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i);
+            let mut track = match nearest {
+                Some(i) => old.swap_remove(i),
+                None => Track {
+                    trail: VecDeque::new(),
+                    velocity,
+                },
+            };
+            track.trail.push_back((r, c));
+            if track.trail.len() > self.length {
+                track.trail.pop_front();
+            }
+            self.tracks.push(track);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::{Edges, StampMode, Term};
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn a_glider_keeps_its_track() {
+        let mut board = LifeBoard::from(Term { w: 40, h: 40 });
+        board.clear();
+        let glider = Pattern::from_rle("bo$2bo$3o!").expect("Unable to parse the glider");
+        board.stamp(
+            &glider,
+            CellLocation { r: 2, c: 2 },
+            StampMode::Or,
+            Edges::Reject,
+        );
+        let block = Pattern::from_rle("2o$2o!").expect("Unable to parse the block");
+        board.stamp(
+            &block,
+            CellLocation { r: 30, c: 5 },
+            StampMode::Or,
+            Edges::Reject,
+        );
+
+        let mut tracker = ShipTracker::new(&board, 10).expect("Unable to make a tracker");
+        for _ in 0..40 {
+            tracker.observe(&board);
+            board.step();
+        }
+        assert_eq!(tracker.tracks().len(), 1);
+        let track = &tracker.tracks()[0];
+        assert_eq!(track.velocity, (0.25, 0.25));
+        assert_eq!(track.trail.len(), 10);
+        // Ten generations of a c/4 diagonal ship, give or take its wobble
+        let (first, last) = (track.trail[0], track.position());
+        assert!((last.0 - first.0 - 2.25).abs() <= 0.5, "{:?}", track.trail);
+        assert!((last.1 - first.1 - 2.25).abs() <= 0.5, "{:?}", track.trail);
+        assert_eq!("vectors".parse(), Ok(Tracks::Vectors));
+        assert_eq!(Tracks::Vectors.next(), Tracks::Off);
+    }
+}
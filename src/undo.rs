@@ -0,0 +1,98 @@
+use super::cells::{CellLocation, CellState};
+
+// More than enough to back out of a mistake, without a long session
+// holding on to every stroke
+const MAX_EDITS: usize = 100;
+
+// Each cell an edit changed, with its state before and after
+type Edit = Vec<(CellLocation, CellState, CellState)>;
+
+// The edits made by hand, kept apart from the generations so taking one
+// back only puts those cells back and leaves the rest of the board running.
+// Cells are recorded as they change and finish closes them off as one edit,
+// e.g. at the end of a stroke of painting.
+#[derive(Clone, Debug, Default)]
+pub struct UndoStack {
+    done: Vec<Edit>,
+    undone: Vec<Edit>,
+    open: Edit,
+}
+
+impl UndoStack {
+    pub fn record(&mut self, location: &CellLocation, before: CellState, after: CellState) {
+        if before != after {
+            self.open.push((location.clone(), before, after));
+        }
+    }
+
+    // A new edit means the ones undone can't be redone
+    pub fn finish(&mut self) {
+        if self.open.is_empty() {
+            return;
+        }
+        self.done.push(std::mem::take(&mut self.open));
+        self.undone.clear();
+        if self.done.len() > MAX_EDITS {
+            self.done.remove(0);
+        }
+    }
+
+    // The cells to set to take back the last edit
+    pub fn undo(&mut self) -> Option<Vec<(CellLocation, CellState)>> {
+        self.finish();
+        let edit = self.done.pop()?;
+        let cells = edit
+            .iter()
+            .rev()
+            .map(|(location, before, _)| (location.clone(), before.clone()))
+            .collect();
+        self.undone.push(edit);
+        Some(cells)
+    }
+
+    // The cells to set to make the last edit taken back again
+    pub fn redo(&mut self) -> Option<Vec<(CellLocation, CellState)>> {
+        self.finish();
+        let edit = self.undone.pop()?;
+        let cells = edit
+            .iter()
+            .map(|(location, _, after)| (location.clone(), after.clone()))
+            .collect();
+        self.done.push(edit);
+        Some(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(c: i32) -> CellLocation {
+        CellLocation { r: 0, c }
+    }
+
+    #[test]
+    fn undoing_puts_back_what_was_there() {
+        let mut stack = UndoStack::default();
+        stack.record(&at(0), CellState::Dead, CellState::Alive);
+        stack.record(&at(0), CellState::Alive, CellState::Alive);
+        stack.record(&at(1), CellState::Dead, CellState::Alive);
+        stack.finish();
+        stack.record(&at(1), CellState::Alive, CellState::Dead);
+
+        assert_eq!(stack.undo(), Some(vec![(at(1), CellState::Alive)]));
+        assert_eq!(
+            stack.undo(),
+            Some(vec![(at(1), CellState::Dead), (at(0), CellState::Dead)])
+        );
+        assert_eq!(stack.undo(), None);
+        assert_eq!(
+            stack.redo(),
+            Some(vec![(at(0), CellState::Alive), (at(1), CellState::Alive)])
+        );
+
+        stack.record(&at(2), CellState::Dead, CellState::Alive);
+        assert_eq!(stack.redo(), None);
+        assert_eq!(stack.undo(), Some(vec![(at(2), CellState::Dead)]));
+    }
+}
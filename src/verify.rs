@@ -0,0 +1,165 @@
+use super::cells::CellLocation;
+use super::life::{LifeBoard, Neighbourhood, Topology};
+use super::rule::Rule;
+use std::collections::HashSet;
+
+// The simplest engine there could be, to check the fast ones against. Each
+// cell's neighbours are counted one at a time straight from the rule and
+// neighbourhood, with none of the bit tricks, caching or threads the others
+// use. It only knows two state Life, but any neighbourhood or topology.
+#[derive(Clone, Debug)]
+pub struct Reference {
+    rule: Rule,
+    neighbourhood: Neighbourhood,
+    topology: Topology,
+    width: i32,
+    height: i32,
+    live: HashSet<CellLocation>,
+}
+
+impl Reference {
+    pub fn from_board(board: &LifeBoard) -> Result<Self, String> {
+        let rule = board.rule().clone();
+        if rule.is_generations() {
+            return Err(format!(
+                "The reference engine cannot run {} as it has dying states",
+                rule
+            ));
+        }
+        if rule.is_born(0) && board.topology() == Topology::Infinite {
+            return Err(format!(
+                "The reference engine cannot run {} on an infinite board as it has B0",
+                rule
+            ));
+        }
+        if board.is_wired() || !board.ants().is_empty() || board.colonies() > 1 {
+            return Err("The reference engine only runs plain Life boards".to_string());
+        }
+        Ok(Reference {
+            rule,
+            neighbourhood: board.neighbourhood(),
+            topology: board.topology(),
+            width: board.width(),
+            height: board.height(),
+            live: board.iter_live().collect(),
+        })
+    }
+
+    pub fn live_cells(&self) -> &HashSet<CellLocation> {
+        &self.live
+    }
+
+    // None if it's off the edge of a bounded board
+    fn wrap(&self, location: CellLocation) -> Option<CellLocation> {
+        let (r, c) = (location.r, location.c);
+        match self.topology {
+            Topology::Infinite => Some(location),
+            Topology::Torus => Some(CellLocation {
+                r: r.rem_euclid(self.height),
+                c: c.rem_euclid(self.width),
+            }),
+            Topology::Bounded => Some(location)
+                .filter(|_| (0..self.height).contains(&r) && (0..self.width).contains(&c)),
+        }
+    }
+
+    fn neighbours(&self, location: &CellLocation) -> impl Iterator<Item = CellLocation> + '_ {
+        let (r, c) = (location.r, location.c);
+        self.neighbourhood
+            .offsets(r)
+            .iter()
+            .filter_map(move |(dr, dc)| {
+                self.wrap(CellLocation {
+                    r: r + dr,
+                    c: c + dc,
+                })
+            })
+    }
+
+    pub fn step(&mut self) {
+        // On an infinite plane only the live cells and their neighbours can
+        // change, there's no B0 there
+        let candidates: HashSet<CellLocation> = if self.topology == Topology::Infinite {
+            self.live
+                .iter()
+                .flat_map(|l| self.neighbours(l).chain(std::iter::once(l.clone())))
+                .collect()
+        } else {
+            (0..self.height)
+                .flat_map(|r| (0..self.width).map(move |c| CellLocation { r, c }))
+                .collect()
+        };
+        self.live = candidates
+            .into_iter()
+            .filter(|location| {
+                let neighbours = self
+                    .neighbours(location)
+                    .filter(|n| self.live.contains(n))
+                    .count() as u8;
+                if self.live.contains(location) {
+                    self.rule.survives(neighbours)
+                } else {
+                    self.rule.is_born(neighbours)
+                }
+            })
+            .collect();
+    }
+}
+
+// The first cell in reading order that's alive in only one of expected and
+// actual, and whether it's the expected one it's alive in
+pub fn first_difference(
+    expected: &HashSet<CellLocation>,
+    actual: &HashSet<CellLocation>,
+) -> Option<(CellLocation, bool)> {
+    expected
+        .symmetric_difference(actual)
+        .min_by_key(|l| (l.r, l.c))
+        .map(|l| (l.clone(), expected.contains(l)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::Term;
+
+    #[test]
+    fn the_reference_agrees_with_the_board() {
+        for topology in [Topology::Bounded, Topology::Torus] {
+            for neighbourhood in [
+                Neighbourhood::Moore,
+                Neighbourhood::VonNeumann,
+                Neighbourhood::Hexagonal,
+            ] {
+                let mut board = LifeBoard::from(Term { w: 70, h: 20 })
+                    .with_topology(topology)
+                    .with_neighbourhood(neighbourhood);
+                board.randomize_with_seed(7, 0.4);
+                let mut reference =
+                    Reference::from_board(&board).expect("Unable to start the reference");
+                for generation in 0..20 {
+                    board.step();
+                    reference.step();
+                    let live = board.iter_live().collect();
+                    assert_eq!(
+                        first_difference(reference.live_cells(), &live),
+                        None,
+                        "{} {} board parted at generation {}",
+                        topology,
+                        neighbourhood,
+                        generation + 1
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn differences_are_found_in_reading_order() {
+        let at = |r, c| CellLocation { r, c };
+        let expected = vec![at(0, 5), at(2, 0), at(3, 3)].into_iter().collect();
+        let actual = vec![at(0, 5), at(3, 3), at(2, 1)].into_iter().collect();
+        assert_eq!(first_difference(&expected, &actual), Some((at(2, 0), true)));
+        assert_eq!(first_difference(&actual, &actual), None);
+    }
+}
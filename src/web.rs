@@ -0,0 +1,91 @@
+use super::cells::{CellLocation, CellState};
+use super::fade::ColorScheme;
+use super::life::{LifeBoard, Term, Topology};
+use super::theme::Theme;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+// A board drawn into an HTML canvas, one canvas pixel per cell so the page
+// sizes it with CSS, e.g. image-rendering: pixelated. The page decides how
+// often to step and draw, usually from requestAnimationFrame.
+#[wasm_bindgen]
+pub struct WebLife {
+    board: LifeBoard,
+    context: CanvasRenderingContext2d,
+    theme: Theme,
+    pixels: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl WebLife {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas: HtmlCanvasElement,
+        rule: &str,
+        theme: &str,
+        seed: u32,
+        density: f64,
+    ) -> Result<WebLife, JsValue> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("The canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        let mut board = LifeBoard::from(Term {
+            w: canvas.width() as i32,
+            h: canvas.height() as i32,
+        })
+        .with_topology(Topology::Torus)
+        .with_rule(rule.parse().map_err(|e: String| JsValue::from_str(&e))?);
+        board.randomize_with_seed(seed as u64, density);
+        Ok(WebLife {
+            board,
+            context,
+            theme: theme.parse().map_err(|e: String| JsValue::from_str(&e))?,
+            pixels: Vec::new(),
+        })
+    }
+
+    pub fn step(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.board.step();
+        }
+    }
+
+    pub fn draw(&mut self) -> Result<(), JsValue> {
+        let background = self.theme.background;
+        self.pixels.clear();
+        for cell in self.board.iter_cells() {
+            let [red, green, blue] = match cell.state {
+                CellState::Dead | CellState::Wire => background,
+                _ => self.theme.colour(self.board.rule().brightness(&cell.state)),
+            };
+            self.pixels.extend([red, green, blue, 255]);
+        }
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.pixels),
+            self.board.width() as u32,
+            self.board.height() as u32,
+        )?;
+        self.context.put_image_data(&image, 0.0, 0.0)
+    }
+
+    // For clicks, r and c being the canvas pixel
+    pub fn toggle(&mut self, r: i32, c: i32) {
+        let location = CellLocation { r, c };
+        let state = match self.board.state(&location) {
+            Some(CellState::Alive) => CellState::Dead,
+            Some(_) => CellState::Alive,
+            None => return,
+        };
+        self.board.set_state(&location, state);
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.board.iteration() as u32
+    }
+
+    pub fn population(&self) -> u32 {
+        self.board.population() as u32
+    }
+}